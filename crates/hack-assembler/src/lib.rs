@@ -1,200 +1,393 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Write};
 
-const USER_MEM_START: u32 = 16;
-const PREDEFINED_SYMBOLS: [(&str, u32); 23] = [
-    ("R0", 0),
-    ("R1", 1),
-    ("R2", 2),
-    ("R3", 3),
-    ("R4", 4),
-    ("R5", 5),
-    ("R6", 6),
-    ("R7", 7),
-    ("R8", 8),
-    ("R9", 9),
-    ("R10", 10),
-    ("R11", 11),
-    ("R12", 12),
-    ("R13", 13),
-    ("R14", 14),
-    ("R15", 15),
-    ("SP", 0),
-    ("LCL", 1),
-    ("ARG", 2),
-    ("THIS", 3),
-    ("THAT", 4),
-    ("SCREEN", 16384),
-    ("KBD", 24576),
-];
-
-pub fn translate_file(file: &Vec<String>) -> String {
-    let (instructions, mut symbols, label_count) = process_raw_file(file);
-
-    let result: Vec<String> = instructions
-        .iter()
-        .map(|x| translate_line(x, &mut symbols, label_count))
-        .collect();
-
-    result.join("\n") + "\n"
-}
+use shared::hack::{AluOutput, Assignment, Branch, Instruction, Location, MAX_ADDRESS};
+use thiserror::Error;
 
-fn process_raw_file(file: &Vec<String>) -> (Vec<String>, HashMap<&str, u32>, u32) {
-    let mut instructions = Vec::with_capacity(file.len());
-    let mut labels: HashMap<&str, u32> = PREDEFINED_SYMBOLS.iter().copied().collect();
-    let mut inst_count = 0;
-    let mut label_count = 0;
-
-    for line in file {
-        let clean_line = if let Some((pre_comment, _)) = line.split_once("//") {
-            pre_comment.trim()
-        } else {
-            line.trim()
-        };
+/// The first RAM address handed out to variables.
+const USER_MEM_START: u16 = 16;
+/// The number of instructions that fit in ROM.
+const ROM_SIZE: usize = MAX_ADDRESS as usize + 1;
 
-        if clean_line.starts_with('(') {
-            let label = &clean_line[1..clean_line.len() - 1];
-            let prev_label = labels.insert(label, inst_count);
+/// Assembles & encodes the provided file, returning one 16 character binary
+/// word per line.
+pub fn translate_file(file: &str, source: &str) -> Result<String, Vec<AssembleError>> {
+    let words = assemble(file, source)?;
 
-            assert_eq!(prev_label, None, "Duplicate labels: {}", label); // TODO: Propogate error upwards
-            label_count += 1;
-        } else if !clean_line.is_empty() {
-            instructions.push(clean_line.to_owned());
-            inst_count += 1;
-        }
+    let mut output = String::with_capacity(words.len() * 17);
+    for word in words {
+        writeln!(output, "{:016b}", word).unwrap();
     }
 
-    (instructions, labels, label_count)
+    Ok(output)
 }
 
-fn translate_line<'a>(
-    line: &'a str,
-    symbols: &mut HashMap<&'a str, u32>,
-    label_count: u32,
-) -> String {
-    let first_char = line.chars().next();
+/// Assembles the provided file into Hack machine words.
+///
+/// All errors in the file are collected & returned together.
+pub fn assemble(file: &str, source: &str) -> Result<Vec<u16>, Vec<AssembleError>> {
+    let lines: Vec<_> = source.lines().collect();
+    let error = |line: usize, column: usize, text: &str, kind| AssembleError {
+        file: file.to_owned(),
+        line: line + 1,
+        column: column + 1,
+        text: text.to_owned(),
+        source_line: lines[line].to_owned(),
+        kind,
+    };
+
+    // Parse every line, collecting all errors as we go.
+    let mut instructions = Vec::with_capacity(lines.len());
+    let mut errors = Vec::default();
+    for (number, line) in lines.iter().enumerate() {
+        // Strip comments & surrounding whitespace.
+        let code = line.split_once("//").map_or(*line, |(code, _)| code);
+        let column = code.len() - code.trim_start().len();
+        let code = code.trim();
+        if code.is_empty() {
+            continue;
+        }
+
+        match parse_instruction(code) {
+            Ok(instruction) => instructions.push((number, column, instruction)),
+            Err((offset, text, kind)) => errors.push(error(number, column + offset, text, kind)),
+        }
+    }
+
+    // Bind each label to the ROM address of the instruction following it.
+    let mut symbols = HashMap::new();
+    let mut label_lines = HashMap::new();
+    let mut rom_address = 0;
+    for (number, column, instruction) in &instructions {
+        match instruction {
+            Instruction::Label(label) => match label_lines.get(label) {
+                Some(first_line) => errors.push(error(
+                    *number,
+                    *column,
+                    lines[*number].trim(),
+                    AssembleErrorKind::DuplicateLabel { first_line: first_line + 1 },
+                )),
+                None => {
+                    label_lines.insert(label.clone(), *number);
+                    symbols.insert(label.clone(), rom_address as u16);
+                }
+            },
+            _ => {
+                if rom_address == ROM_SIZE {
+                    errors.push(error(
+                        *number,
+                        *column,
+                        lines[*number].trim(),
+                        AssembleErrorKind::RomOverflow,
+                    ));
+                }
+                rom_address += 1;
+            }
+        }
+    }
+    if !errors.is_empty() {
+        errors.sort_by_key(|err| (err.line, err.column));
 
-    match first_char {
-        Some('@') => translate_a(line, symbols, label_count),
-        _ => translate_c(line),
+        return Err(errors);
     }
+
+    // Allocate variables for all remaining symbols in order of first use.
+    let mut next_variable = USER_MEM_START;
+    Ok(instructions
+        .into_iter()
+        .filter_map(|(_, _, instruction)| match instruction {
+            Instruction::Label(_) => None,
+            Instruction::A(Location::Label(symbol)) => {
+                let address = *symbols.entry(symbol).or_insert_with(|| {
+                    next_variable += 1;
+
+                    next_variable - 1
+                });
+
+                Some(address)
+            }
+            instruction => Some(instruction.encode()),
+        })
+        .collect())
 }
 
-fn translate_a<'a>(
-    line: &'a str,
-    symbols: &mut HashMap<&'a str, u32>,
-    symbol_count: u32,
-) -> String {
-    let symbol = &line[1..];
-
-    if let Ok(address) = symbol.parse::<u16>() {
-        format!("{:016b}", address)
-    } else if let Some(address) = symbols.get(symbol) {
-        return format!("{:016b}", address);
+/// Parses a single comment free & trimmed line.
+///
+/// Whitespace within C instructions is ignored, so `D = M + 1 ; JGT` is
+/// accepted, while A instructions & labels must not contain any.
+///
+/// On error returns the offset & text of the offending section of the line.
+fn parse_instruction(code: &str) -> Result<Instruction, (usize, &str, AssembleErrorKind)> {
+    if let Some(target) = code.strip_prefix('@') {
+        match target.as_bytes().first() {
+            Some(b'0'..=b'9') => {
+                if !target
+                    .parse::<u16>()
+                    .is_ok_and(|address| address <= MAX_ADDRESS)
+                {
+                    return Err((1, target, AssembleErrorKind::InvalidAddress));
+                }
+            }
+            _ => {
+                if !is_symbol(target) {
+                    return Err((1, target, AssembleErrorKind::InvalidSymbol));
+                }
+            }
+        }
+    } else if let Some(label) = code.strip_prefix('(') {
+        if !label.strip_suffix(')').is_some_and(is_symbol) {
+            return Err((0, code, AssembleErrorKind::InvalidLabel));
+        }
     } else {
-        let address = next_free_mem_addr(symbols, symbol_count);
-        symbols.insert(symbol, address);
+        let (dest, comp_offset, rest) = match code.split_once('=') {
+            Some((dest, rest)) => (Some(dest), dest.len() + 1, rest),
+            None => (None, 0, code),
+        };
+        let (comp, jump) = match rest.split_once(';') {
+            Some((comp, jump)) => (comp, Some((comp_offset + comp.len() + 1, jump))),
+            None => (rest, None),
+        };
 
-        return format!("{:016b}", address);
+        let assignment = match dest.map(|dest| trim(0, dest)) {
+            Some((offset, dest)) => Some(
+                without_whitespace(dest)
+                    .parse::<Assignment>()
+                    .map_err(|_| (offset, dest, AssembleErrorKind::InvalidDest))?,
+            ),
+            None => None,
+        };
+        let (comp_offset, comp) = trim(comp_offset, comp);
+        let alu_output = without_whitespace(comp)
+            .parse::<AluOutput>()
+            .map_err(|_| (comp_offset, comp, AssembleErrorKind::InvalidComp))?;
+        // An empty jump after the `;` never jumps.
+        let branch = match jump.map(|(offset, jump)| trim(offset, jump)) {
+            None | Some((_, "")) => None,
+            Some((offset, jump)) => Some(
+                without_whitespace(jump)
+                    .parse::<Branch>()
+                    .map_err(|_| (offset, jump, AssembleErrorKind::InvalidJump))?,
+            ),
+        };
+
+        return Ok(Instruction::C(assignment, alu_output, branch));
     }
+
+    // All instruction components have been validated above.
+    Ok(code.parse().unwrap())
 }
 
-fn next_free_mem_addr(symbols: &HashMap<&str, u32>, label_count: u32) -> u32 {
-    let address = symbols.len() as u32;
-    let address = address + USER_MEM_START;
-    let address = address - PREDEFINED_SYMBOLS.len() as u32;
+/// Trims the `text` found at `offset` within a line, returning the offset of
+/// the trimmed text.
+fn trim(offset: usize, text: &str) -> (usize, &str) {
+    (offset + text.len() - text.trim_start().len(), text.trim())
+}
 
-    address - label_count
+fn without_whitespace(text: &str) -> String {
+    text.split_whitespace().collect()
 }
 
-fn translate_c(line: &str) -> String {
-    let boundaries = &['=', ';'][..];
-    line.split(boundaries).next();
+/// Symbols are made of letters, digits, `_`, `.`, `$` & `:` and cannot begin
+/// with a digit.
+fn is_symbol(symbol: &str) -> bool {
+    symbol
+        .as_bytes()
+        .first()
+        .is_some_and(|byte| !byte.is_ascii_digit())
+        && symbol
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'$' | b':'))
+}
 
-    let mut dest = None;
-    let mut comp = line;
-    let mut jump = None;
-    if let Some(split) = line.split_once('=') {
-        dest = Some(split.0);
-        comp = split.1;
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AssembleErrorKind {
+    #[error("Invalid address")]
+    InvalidAddress,
+    #[error("Invalid symbol")]
+    InvalidSymbol,
+    #[error("Invalid label")]
+    InvalidLabel,
+    #[error("Invalid dest")]
+    InvalidDest,
+    #[error("Invalid comp")]
+    InvalidComp,
+    #[error("Invalid jump")]
+    InvalidJump,
+    #[error("Duplicate label; first_line={first_line}")]
+    DuplicateLabel { first_line: usize },
+    #[error("Program does not fit in ROM")]
+    RomOverflow,
+}
+
+/// An error at a specific location within an assembly file.
+///
+/// Displays as the error message followed by a snippet of the offending source.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct AssembleError {
+    pub file: String,
+    /// 1-indexed line number.
+    pub line: usize,
+    /// 1-indexed column number.
+    pub column: usize,
+    /// The offending section of the line.
+    pub text: String,
+    /// The full source line.
+    pub source_line: String,
+    pub kind: AssembleErrorKind,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = self.line.to_string().len();
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(self.column - 1)
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(f, "error: {}; text={}", self.kind, self.text)?;
+        writeln!(f, "{:gutter$}--> {}:{}:{}", "", self.file, self.line, self.column)?;
+        writeln!(f, "{:gutter$} |", "")?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(f, "{:gutter$} | {indent}{}", "", "^".repeat(self.text.len().max(1)))
     }
+}
 
-    if let Some(split) = line.split_once(';') {
-        comp = split.0;
-        jump = Some(split.1);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every error in a file is reported, in source order.
+    #[test]
+    fn multiple_errors() {
+        let source = "@1\nX=M\n(LOOP)\nD=Q\n@99999\n(LOOP)\nD;JXX\n@1abc\n(2BAD)\n";
+        let errors = assemble("Test.asm", source).unwrap_err();
+
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|err| (err.line, err.column, err.text, err.kind))
+            .collect();
+        let expected = [
+            (2, 1, "X", AssembleErrorKind::InvalidDest),
+            (4, 3, "Q", AssembleErrorKind::InvalidComp),
+            (5, 2, "99999", AssembleErrorKind::InvalidAddress),
+            (6, 1, "(LOOP)", AssembleErrorKind::DuplicateLabel { first_line: 3 }),
+            (7, 3, "JXX", AssembleErrorKind::InvalidJump),
+            (8, 2, "1abc", AssembleErrorKind::InvalidAddress),
+            (9, 1, "(2BAD)", AssembleErrorKind::InvalidLabel),
+        ]
+        .map(|(line, column, text, kind)| (line, column, text.to_owned(), kind));
+        assert_eq!(errors, expected);
     }
 
-    let dest = translate_dest(dest);
-    let comp = translate_comp(comp);
-    let jump = translate_jump(jump);
+    #[test]
+    fn rendering() {
+        let errors = assemble("Test.asm", "@0\n  AM=D+Q;JMP // comment\n").unwrap_err();
+        let expected = "\
+error: Invalid comp; text=D+Q
+ --> Test.asm:2:6
+  |
+2 |   AM=D+Q;JMP // comment
+  |      ^^^";
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), expected);
 
-    format!("111{}{}{}", comp, dest, jump)
-}
+        // The gutter grows with the line number.
+        let source = format!("{}\t(LOOP)\n\t(LOOP)\n", "D=M\n".repeat(9));
+        let errors = assemble("Test.asm", &source).unwrap_err();
+        let expected = "\
+error: Duplicate label; first_line=10; text=(LOOP)
+  --> Test.asm:11:2
+   |
+11 | \t(LOOP)
+   | \t^^^^^^";
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].to_string(), expected);
+    }
 
-fn translate_dest(source: Option<&str>) -> String {
-    let mut result = 0;
+    #[test]
+    fn duplicate_labels() {
+        let source = "(A)\n@A\n(B)\n(A)\n0;JMP\n(B)\n(A)\n";
+        let errors = assemble("Test.asm", source).unwrap_err();
 
-    if let Some(dest) = source {
-        if dest.contains('A') {
-            result += 4;
-        }
-        if dest.contains('D') {
-            result += 2;
+        let errors: Vec<_> = errors.into_iter().map(|err| (err.line, err.kind)).collect();
+        let duplicate = |first_line| AssembleErrorKind::DuplicateLabel { first_line };
+        assert_eq!(errors, [(4, duplicate(1)), (6, duplicate(3)), (7, duplicate(1))]);
+    }
+
+    #[test]
+    fn invalid_components() {
+        let cases = [
+            ("X=M", 1, "X", AssembleErrorKind::InvalidDest),
+            ("=M", 1, "", AssembleErrorKind::InvalidDest),
+            ("ADMX=M", 1, "ADMX", AssembleErrorKind::InvalidDest),
+            ("D=M+2", 3, "M+2", AssembleErrorKind::InvalidComp),
+            ("D=", 3, "", AssembleErrorKind::InvalidComp),
+            ("!D&A", 1, "!D&A", AssembleErrorKind::InvalidComp),
+            ("0;JUMP", 3, "JUMP", AssembleErrorKind::InvalidJump),
+            ("D=M;jmp", 5, "jmp", AssembleErrorKind::InvalidJump),
+        ];
+
+        let mut failures = Vec::default();
+        for (source, column, text, kind) in cases {
+            let errors = assemble("Test.asm", source).unwrap_err();
+            let err = &errors[0];
+            if errors.len() != 1
+                || (err.column, err.text.as_str(), &err.kind) != (column, text, &kind)
+            {
+                failures.push(format!("{source}: {errors:?}"));
+            }
         }
-        if dest.contains('M') {
-            result += 1;
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    /// A instructions load 15-bit values, the MSB selects C instructions.
+    #[test]
+    fn address_range() {
+        assert_eq!(assemble("Test.asm", "@32767\n"), Ok(vec![0x7FFF]));
+
+        for source in ["@32768", "@65535", "@65536", "@-1"] {
+            let errors = assemble("Test.asm", source).unwrap_err();
+            let kinds: Vec<_> = errors.iter().map(|err| &err.kind).collect();
+            let expected = match source {
+                // Not a number, nor a valid symbol.
+                "@-1" => AssembleErrorKind::InvalidSymbol,
+                _ => AssembleErrorKind::InvalidAddress,
+            };
+            assert_eq!(kinds, [&expected], "{source}");
+            assert_eq!((errors[0].column, errors[0].text.as_str()), (2, &source[1..]), "{source}");
         }
     }
 
-    format!("{:03b}", result)
-}
+    /// Whitespace is ignored throughout C instructions, but not within A
+    /// instructions or labels.
+    #[test]
+    fn whitespace() {
+        let expected = assemble("Test.asm", "D=M+1;JGT\nAM=D|A\n0;JMP\nD;\n").unwrap();
+        let source = "D = M + 1 ; JGT\n\tA M =D | A\n0; JMP\n D ;\n";
+        assert_eq!(assemble("Test.asm", source), Ok(expected));
 
-fn translate_comp(source: &str) -> String {
-    match source {
-        "0" => "0101010".to_owned(),
-        "1" => "0111111".to_owned(),
-        "-1" => "0111010".to_owned(),
-        "D" => "0001100".to_owned(),
-        "A" => "0110000".to_owned(),
-        "!D" => "0001101".to_owned(),
-        "!A" => "0110001".to_owned(),
-        "-D" => "0001111".to_owned(),
-        "-A" => "0110011".to_owned(),
-        "D+1" => "0011111".to_owned(),
-        "A+1" => "0110111".to_owned(),
-        "D-1" => "0001110".to_owned(),
-        "A-1" => "0110010".to_owned(),
-        "D+A" => "0000010".to_owned(),
-        "D-A" => "0010011".to_owned(),
-        "A-D" => "0000111".to_owned(),
-        "D&A" => "0000000".to_owned(),
-        "D|A" => "0010101".to_owned(),
-        "M" => "1110000".to_owned(),
-        "!M" => "1110001".to_owned(),
-        "-M" => "1110011".to_owned(),
-        "M+1" => "1110111".to_owned(),
-        "M-1" => "1110010".to_owned(),
-        "D+M" => "1000010".to_owned(),
-        "D-M" => "1010011".to_owned(),
-        "M-D" => "1000111".to_owned(),
-        "D&M" => "1000000".to_owned(),
-        "D|M" => "1010101".to_owned(),
-        _ => panic!("Invalid comp instruction: {}", source),
+        let errors = assemble("Test.asm", "@ 5\n( LOOP )\n").unwrap_err();
+        let kinds: Vec<_> = errors.into_iter().map(|err| err.kind).collect();
+        assert_eq!(kinds, [AssembleErrorKind::InvalidSymbol, AssembleErrorKind::InvalidLabel]);
     }
-}
 
-fn translate_jump(source: Option<&str>) -> String {
-    if let Some(jump) = source {
-        match jump {
-            "JGT" => "001".to_owned(),
-            "JEQ" => "010".to_owned(),
-            "JGE" => "011".to_owned(),
-            "JLT" => "100".to_owned(),
-            "JNE" => "101".to_owned(),
-            "JLE" => "110".to_owned(),
-            "JMP" => "111".to_owned(),
-            _ => panic!("Invalid jump instruction: {}", jump),
+    /// Errors point at the trimmed component of a C instruction.
+    #[test]
+    fn whitespace_columns() {
+        let cases =
+            [("  D =  X ; JMP", 8, "X"), ("D = M ;  JXX ", 10, "JXX"), (" X Y = M", 2, "X Y")];
+
+        let mut failures = Vec::default();
+        for (source, column, text) in cases {
+            let errors = assemble("Test.asm", source).unwrap_err();
+            if (errors[0].column, errors[0].text.as_str()) != (column, text) {
+                failures.push(format!("{source}: {:?}", errors[0]));
+            }
         }
-    } else {
-        "000".to_owned()
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
     }
 }
@@ -1,26 +1,34 @@
-use std::error::Error;
+use std::path::Path;
 use std::{env, fs, process};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if let Some(first_arg) = args.get(1) {
-        let (file_name, _) = first_arg.split_once('.').unwrap();
-        let lines = read_lines(first_arg).unwrap_or_else(|e| {
-            eprintln!("Could not read file ({}): {}", first_arg, e);
-            process::exit(1);
-        });
+    let Some(first_arg) = args.get(1) else {
+        eprintln!("Usage: {} <file.asm>", args[0]);
+        process::exit(1);
+    };
 
-        let result = hack_assembler::translate_file(&lines);
-        fs::write(format!("{}.hack", file_name), result).unwrap_or_else(|e| {
-            eprintln!("Could not write file {}.hack: {}", file_name, e);
-        });
-    }
-}
+    let source = fs::read_to_string(first_arg).unwrap_or_else(|e| {
+        eprintln!("Could not read file ({}): {}", first_arg, e);
+        process::exit(1);
+    });
 
-fn read_lines(file_name: &str) -> Result<Vec<String>, Box<dyn Error>> {
-    let file = fs::read_to_string(file_name)?;
-    let lines = file.lines().map(str::to_owned).collect();
+    let result = hack_assembler::translate_file(first_arg, &source).unwrap_or_else(|errors| {
+        for error in &errors {
+            eprintln!("{error}\n");
+        }
+        eprintln!(
+            "error: could not assemble {} due to {} previous errors",
+            first_arg,
+            errors.len()
+        );
+        process::exit(1);
+    });
 
-    Ok(lines)
+    let output = Path::new(first_arg).with_extension("hack");
+    fs::write(&output, result).unwrap_or_else(|e| {
+        eprintln!("Could not write file {}: {}", output.display(), e);
+        process::exit(1);
+    });
 }
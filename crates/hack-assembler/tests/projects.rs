@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+
+/// Assembles every program in `projects/06` and compares the result against
+/// its reference `.hack` file.
+#[test]
+fn project_programs() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects/06");
+
+    let mut programs: Vec<PathBuf> = Vec::default();
+    for dir in std::fs::read_dir(projects).unwrap() {
+        for entry in std::fs::read_dir(dir.unwrap().path()).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "asm") {
+                programs.push(path);
+            }
+        }
+    }
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    let mut failures = Vec::default();
+    for program in programs {
+        let source = std::fs::read_to_string(&program).unwrap();
+        let expected = std::fs::read_to_string(program.with_extension("hack")).unwrap();
+        match hack_assembler::translate_file(&program.display().to_string(), &source) {
+            Ok(actual) if actual.lines().eq(expected.lines()) => {}
+            Ok(_) => failures.push(format!("{}: output differs", program.display())),
+            Err(errors) => failures.push(format!("{}: {errors:?}", program.display())),
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+pub(crate) struct Args {
+    /// The `.hack` file to disassemble.
+    pub(crate) path: PathBuf,
+    /// Replace jump target addresses with synthesized labels.
+    #[arg(long)]
+    pub(crate) labels: bool,
+    /// Replace memory accesses to predefined addresses with their symbols.
+    #[arg(long)]
+    pub(crate) symbols: bool,
+}
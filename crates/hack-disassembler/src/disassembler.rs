@@ -0,0 +1,223 @@
+use shared::hack::{DecodeError, Instruction, Location, PredefinedSymbols};
+use thiserror::Error;
+
+/// Decodes a `.hack` file containing one 16 character binary word per line.
+pub(crate) fn decode(source: &str) -> Result<Vec<Instruction>, Vec<DisassembleError>> {
+    let mut instructions = Vec::default();
+    let mut errors = Vec::default();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // `from_str_radix` would also accept a leading `+`.
+        let word = match line.len() == 16 && line.bytes().all(|byte| matches!(byte, b'0' | b'1')) {
+            true => u16::from_str_radix(line, 2).ok(),
+            false => None,
+        };
+        let Some(word) = word else {
+            errors.push(DisassembleError::Word { line: number + 1, word: line.to_owned() });
+
+            continue;
+        };
+
+        match Instruction::decode(word) {
+            Ok(instruction) => instructions.push(instruction),
+            Err(err) => errors.push(DisassembleError::Decode { line: number + 1, err }),
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(instructions),
+        false => Err(errors),
+    }
+}
+
+/// Replaces the addresses loaded ahead of jumps with labels, inserting the
+/// label declarations at the targeted ROM addresses.
+pub(crate) fn synthesize_labels(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let label = |address: u16| format!("L{address}");
+
+    // Find all `@N` instructions immediately followed by a jump.
+    let mut targets = vec![false; instructions.len() + 1];
+    let mut jumps = vec![false; instructions.len()];
+    for (i, pair) in instructions.windows(2).enumerate() {
+        if let [Instruction::A(Location::Address(address)), Instruction::C(_, _, Some(_))] = pair {
+            if let Some(target) = targets.get_mut(usize::from(*address)) {
+                *target = true;
+                jumps[i] = true;
+            }
+        }
+    }
+
+    // Rewrite the program with the labels in place.
+    let mut output = Vec::with_capacity(instructions.len());
+    for (i, instruction) in instructions.into_iter().enumerate() {
+        if targets[i] {
+            output.push(Instruction::Label(label(i as u16)));
+        }
+
+        match instruction {
+            Instruction::A(Location::Address(address)) if jumps[i] => {
+                output.push(Instruction::A(Location::Label(label(address))));
+            }
+            instruction => output.push(instruction),
+        }
+    }
+    if targets[targets.len() - 1] {
+        output.push(Instruction::Label(label(targets.len() as u16 - 1)));
+    }
+
+    output
+}
+
+/// Names predefined addresses, registers are only named when the following
+/// instruction accesses memory as they are otherwise likely constants.
+pub(crate) fn name_symbols(instructions: &mut [Instruction]) {
+    for i in 0..instructions.len() {
+        let Instruction::A(Location::Address(address)) = instructions[i] else {
+            continue;
+        };
+
+        let accesses_memory = match instructions.get(i + 1) {
+            Some(Instruction::C(assignment, alu_output, _)) => {
+                alu_output.reads_m() || assignment.is_some_and(|assignment| assignment.writes_m())
+            }
+            _ => false,
+        };
+        let symbol = match (address, PredefinedSymbols::from_address(address)) {
+            (0..=4, Some(symbol)) if accesses_memory => symbol.to_string(),
+            (5..=15, _) if accesses_memory => format!("R{address}"),
+            (16.., Some(symbol)) => symbol.to_string(),
+            _ => continue,
+        };
+
+        instructions[i] = Instruction::A(Location::Label(symbol));
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum DisassembleError {
+    #[error("Invalid word; line={line}; word={word}")]
+    Word { line: usize, word: String },
+    #[error("Unsupported instruction; line={line}; err={err}")]
+    Decode { line: usize, err: DecodeError },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn program(source: &str) -> Vec<Instruction> {
+        source.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    fn lines(instructions: &[Instruction]) -> Vec<String> {
+        instructions
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn decode_errors() {
+        let source = "\
+0000000000000101
+
+0101
++000000000000001
+00000000000000001
+000000000000000x
+1110000001000000
+ 1110110000010000 
+";
+        let errors = decode(source).unwrap_err();
+
+        let errors: Vec<_> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            [
+                "Invalid word; line=3; word=0101",
+                "Invalid word; line=4; word=+000000000000001",
+                "Invalid word; line=5; word=00000000000000001",
+                "Invalid word; line=6; word=000000000000000x",
+                "Unsupported instruction; line=7; err=Unsupported comp bits; comp=0b0000001",
+            ]
+        );
+
+        let instructions = decode("0000000000000101\n\n 1110110000010000 \n").unwrap();
+        assert_eq!(lines(&instructions), ["@5", "D=A"]);
+    }
+
+    /// Only addresses loaded right before a jump are replaced, targets past
+    /// the end of the program are left alone.
+    #[test]
+    fn synthesize_labels_at_targets() {
+        let instructions = program("@4\n0;JMP\n@3\nD=A\n@8\nD;JGT\n@100\n0;JMP");
+
+        assert_eq!(
+            lines(&synthesize_labels(instructions)),
+            ["@L4", "0;JMP", "@3", "D=A", "(L4)", "@L8", "D;JGT", "@100", "0;JMP", "(L8)"]
+        );
+    }
+
+    /// Registers are only named ahead of memory accesses, which a following
+    /// label hides.
+    #[test]
+    fn name_symbols_on_access() {
+        let mut instructions =
+            program("@0\nM=D\n@1\nD=A\n@5\nD=M\n@16384\nD=A\n@2\n(L)\nM=D\n@16\nM=1\n@24576");
+        name_symbols(&mut instructions);
+
+        assert_eq!(
+            lines(&instructions),
+            [
+                "@SP", "M=D", "@1", "D=A", "@R5", "D=M", "@SCREEN", "D=A", "@2", "(L)", "M=D",
+                "@16", "M=1", "@KBD",
+            ]
+        );
+    }
+
+    /// Disassembling the `projects/06` programs with any options & assembling
+    /// the output again reproduces the same binary.
+    #[test]
+    fn round_trip() {
+        let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects/06");
+        let mut paths: Vec<_> = std::fs::read_dir(&projects)
+            .unwrap()
+            .flat_map(|dir| std::fs::read_dir(dir.unwrap().path()).unwrap())
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "asm"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        let mut failures = Vec::default();
+        for path in paths {
+            let file = path.display().to_string();
+            let source = std::fs::read_to_string(&path).unwrap();
+            let binary = hack_assembler::translate_file(&file, &source).unwrap();
+
+            for (labels, symbols) in [(false, false), (true, false), (false, true), (true, true)] {
+                let mut instructions = decode(&binary).unwrap();
+                if labels {
+                    instructions = synthesize_labels(instructions);
+                }
+                if symbols {
+                    name_symbols(&mut instructions);
+                }
+                let asm: String = lines(&instructions).join("\n");
+
+                match hack_assembler::translate_file(&file, &asm) {
+                    Ok(output) if output == binary => {}
+                    _ => failures.push(format!("{file} (labels={labels}, symbols={symbols})")),
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
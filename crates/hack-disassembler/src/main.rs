@@ -0,0 +1,48 @@
+mod args;
+mod disassembler;
+
+fn main() -> std::process::ExitCode {
+    use std::io::{BufWriter, Write};
+    use std::process::ExitCode;
+
+    use clap::Parser;
+
+    // Parse command line args.
+    let args = args::Args::parse();
+
+    // Read & decode the binary.
+    let source = match std::fs::read_to_string(&args.path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Failed to read file; path={}; err={err}", args.path.display());
+
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut instructions = match disassembler::decode(&source) {
+        Ok(instructions) => instructions,
+        Err(errors) => {
+            for err in errors {
+                eprintln!("{err}");
+            }
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Optionally make the output more readable.
+    if args.labels {
+        instructions = disassembler::synthesize_labels(instructions);
+    }
+    if args.symbols {
+        disassembler::name_symbols(&mut instructions);
+    }
+
+    // Write the assembly.
+    let mut output = BufWriter::new(std::io::stdout().lock());
+    for instruction in instructions {
+        writeln!(output, "{instruction}").unwrap();
+    }
+
+    ExitCode::SUCCESS
+}
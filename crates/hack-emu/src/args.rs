@@ -0,0 +1,52 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+pub(crate) struct Args {
+    /// The `.hack` or `.asm` program to execute, or a `.tst` script to run.
+    pub(crate) path: PathBuf,
+    /// The maximum number of instructions to execute.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub(crate) max_cycles: u64,
+    /// Initialize RAM before execution, e.g. `--set 0=5`.
+    #[arg(long, value_parser = parse_assignment)]
+    pub(crate) set: Vec<(usize, i16)>,
+    /// A keyboard script to feed the program, see `KeyboardScript`.
+    #[arg(long)]
+    pub(crate) keyboard: Option<PathBuf>,
+    /// Dump the screen after execution, as PNG if the extension is `.png` else
+    /// as PBM.
+    #[arg(long)]
+    pub(crate) screen: Option<PathBuf>,
+    /// The RAM addresses to print after execution, e.g. `--ram 0..16`.
+    #[arg(long, value_parser = parse_range, default_value = "0..16")]
+    pub(crate) ram: Range<usize>,
+}
+
+fn parse_assignment(s: &str) -> Result<(usize, i16), String> {
+    let (address, value) = s.split_once('=').ok_or("Expected ADDRESS=VALUE")?;
+    let address = address
+        .parse()
+        .map_err(|err| format!("Invalid address; err={err}"))?;
+    let value = value
+        .parse()
+        .map_err(|err| format!("Invalid value; err={err}"))?;
+
+    Ok((address, value))
+}
+
+fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let (start, end) = s.split_once("..").ok_or("Expected START..END")?;
+    let start = start
+        .parse()
+        .map_err(|err| format!("Invalid start; err={err}"))?;
+    let end = end
+        .parse()
+        .map_err(|err| format!("Invalid end; err={err}"))?;
+
+    Ok(start..end)
+}
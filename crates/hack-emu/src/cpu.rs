@@ -0,0 +1,381 @@
+use shared::hack::{AluOutput, Branch, Instruction, Location, PredefinedSymbols};
+use thiserror::Error;
+
+use crate::{KeyboardScript, LoadError};
+
+pub const ROM_SIZE: usize = 32 * 1024;
+pub const RAM_SIZE: usize = 32 * 1024;
+
+/// The Hack CPU along with its instruction & data memory.
+pub struct Cpu {
+    rom: Vec<Instruction>,
+    program_len: usize,
+    ram: Box<[i16]>,
+    a: i16,
+    d: i16,
+    pc: u16,
+    cycles: u64,
+    keyboard: KeyboardScript,
+}
+
+impl Cpu {
+    /// Decodes & loads the provided machine words into ROM.
+    pub fn new(program: &[u16]) -> Result<Self, LoadError> {
+        if program.len() > ROM_SIZE {
+            return Err(LoadError::TooLarge(program.len()));
+        }
+
+        // Unused ROM is zeroed, i.e. filled with `@0`.
+        let mut rom = program
+            .iter()
+            .enumerate()
+            .map(|(address, word)| {
+                Instruction::decode(*word).map_err(|err| LoadError::Decode { address, err })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rom.resize(ROM_SIZE, Instruction::A(Location::Address(0)));
+
+        Ok(Cpu {
+            rom,
+            program_len: program.len(),
+            ram: vec![0; RAM_SIZE].into_boxed_slice(),
+            a: 0,
+            d: 0,
+            pc: 0,
+            cycles: 0,
+            keyboard: KeyboardScript::default(),
+        })
+    }
+
+    /// The loaded program, excluding the zeroed remainder of ROM.
+    pub fn rom(&self) -> &[Instruction] {
+        &self.rom[..self.program_len]
+    }
+
+    pub fn ram(&self) -> &[i16] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [i16] {
+        &mut self.ram
+    }
+
+    pub fn a(&self) -> i16 {
+        self.a
+    }
+
+    pub fn set_a(&mut self, a: i16) {
+        self.a = a;
+    }
+
+    pub fn d(&self) -> i16 {
+        self.d
+    }
+
+    pub fn set_d(&mut self, d: i16) {
+        self.d = d;
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    /// The total number of instructions executed.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Schedules key presses against the CPU's cycle count, replacing any
+    /// previously scheduled events.
+    pub fn set_keyboard(&mut self, keyboard: KeyboardScript) {
+        self.keyboard = keyboard;
+    }
+
+    /// Restarts execution from the first instruction, memory is left untouched.
+    pub fn reset(&mut self) {
+        self.pc = 0;
+    }
+
+    /// Returns true if the CPU has run past the end of the program or is parked
+    /// in the conventional `(END) @END 0;JMP` infinite loop.
+    pub fn is_halted(&self) -> bool {
+        let pc = usize::from(self.pc);
+
+        pc >= self.program_len
+            || matches!(
+                (self.rom.get(pc), self.rom.get(pc + 1)),
+                (
+                    Some(Instruction::A(Location::Address(target))),
+                    Some(Instruction::C(None, _, Some(Branch::JMP)))
+                ) if usize::from(*target) == pc
+            )
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> Result<(), ExecuteError> {
+        // Deliver any key presses that are due.
+        if let Some(key) = self.keyboard.poll(self.cycles) {
+            self.ram[usize::from(PredefinedSymbols::Kbd.address())] = key;
+        }
+
+        let instruction = self
+            .rom
+            .get(usize::from(self.pc))
+            .ok_or(ExecuteError::PcOutOfBounds(self.pc))?;
+
+        match instruction {
+            Instruction::A(Location::Address(address)) => {
+                self.a = *address as i16;
+                self.pc += 1;
+            }
+            Instruction::C(assignment, alu_output, branch) => {
+                // All reads & writes happen against the register values at the start of
+                // the cycle.
+                let a = self.a;
+                let m = match alu_output.reads_m() {
+                    true => self.ram[self.address(a)?],
+                    false => 0,
+                };
+                let out = evaluate(*alu_output, self.d, a, m);
+
+                if let Some(assignment) = assignment {
+                    if assignment.writes_m() {
+                        let address = self.address(a)?;
+                        self.ram[address] = out;
+                    }
+                    if assignment.writes_a() {
+                        self.a = out;
+                    }
+                    if assignment.writes_d() {
+                        self.d = out;
+                    }
+                }
+
+                self.pc = match branch.is_some_and(|branch| jumps(branch, out)) {
+                    true => a as u16,
+                    false => self.pc + 1,
+                };
+            }
+            Instruction::A(Location::Label(_)) | Instruction::Label(_) => {
+                unreachable!("Decoded instructions cannot contain symbols")
+            }
+        }
+        self.cycles += 1;
+
+        Ok(())
+    }
+
+    /// Executes until `max_cycles` instructions have run or the CPU halts,
+    /// returning the number of instructions executed.
+    pub fn run(&mut self, max_cycles: u64) -> Result<u64, ExecuteError> {
+        let start = self.cycles;
+        while self.cycles - start < max_cycles && !self.is_halted() {
+            self.step()?;
+        }
+
+        Ok(self.cycles - start)
+    }
+
+    fn address(&self, a: i16) -> Result<usize, ExecuteError> {
+        let address = usize::from(a as u16);
+        match address < RAM_SIZE {
+            true => Ok(address),
+            false => Err(ExecuteError::AddressOutOfBounds { pc: self.pc, address: a as u16 }),
+        }
+    }
+}
+
+/// Computes the ALU output for the given register & memory values.
+pub fn evaluate(alu_output: AluOutput, d: i16, a: i16, m: i16) -> i16 {
+    match alu_output {
+        AluOutput::ZERO => 0,
+        AluOutput::ONE => 1,
+        AluOutput::NEGATIVE_ONE => -1,
+        AluOutput::D => d,
+        AluOutput::A => a,
+        AluOutput::M => m,
+        AluOutput::NEGATE_D => !d,
+        AluOutput::NEGATE_A => !a,
+        AluOutput::NEGATE_M => !m,
+        AluOutput::NEGATIVE_D => d.wrapping_neg(),
+        AluOutput::NEGATIVE_A => a.wrapping_neg(),
+        AluOutput::NEGATIVE_M => m.wrapping_neg(),
+        AluOutput::D_INC => d.wrapping_add(1),
+        AluOutput::A_INC => a.wrapping_add(1),
+        AluOutput::M_INC => m.wrapping_add(1),
+        AluOutput::D_DEC => d.wrapping_sub(1),
+        AluOutput::A_DEC => a.wrapping_sub(1),
+        AluOutput::M_DEC => m.wrapping_sub(1),
+        AluOutput::D_PLUS_A => d.wrapping_add(a),
+        AluOutput::D_PLUS_M => d.wrapping_add(m),
+        AluOutput::D_MINUS_A => d.wrapping_sub(a),
+        AluOutput::D_MINUS_M => d.wrapping_sub(m),
+        AluOutput::A_MINUS_D => a.wrapping_sub(d),
+        AluOutput::M_MINUS_D => m.wrapping_sub(d),
+        AluOutput::D_AND_A => d & a,
+        AluOutput::D_AND_M => d & m,
+        AluOutput::D_OR_A => d | a,
+        AluOutput::D_OR_M => d | m,
+    }
+}
+
+fn jumps(branch: Branch, out: i16) -> bool {
+    match branch {
+        Branch::JGT => out > 0,
+        Branch::JEQ => out == 0,
+        Branch::JGE => out >= 0,
+        Branch::JLT => out < 0,
+        Branch::JNE => out != 0,
+        Branch::JLE => out <= 0,
+        Branch::JMP => true,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum ExecuteError {
+    #[error("Program counter outside of ROM; pc={0}")]
+    PcOutOfBounds(u16),
+    #[error("Memory access outside of RAM; pc={pc}; address={address}")]
+    AddressOutOfBounds { pc: u16, address: u16 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `asm` for one step per instruction, starting from `d`, `a` & RAM[a]
+    /// = `m`.
+    fn run(asm: &str, d: i16, a: i16, m: i16) -> Cpu {
+        let mut cpu = Cpu::from_asm("Test.asm", asm).unwrap();
+        cpu.set_d(d);
+        cpu.set_a(a);
+        cpu.ram_mut()[a as usize] = m;
+        for _ in 0..asm.lines().count() {
+            cpu.step().unwrap();
+        }
+
+        cpu
+    }
+
+    #[test]
+    fn alu_outputs() {
+        let (d, a, m) = (0b0101, 0b0011, -7);
+        let cases = [
+            ("0", 0),
+            ("1", 1),
+            ("-1", -1),
+            ("D", d),
+            ("A", a),
+            ("M", m),
+            ("!D", !d),
+            ("!A", !a),
+            ("!M", !m),
+            ("-D", -d),
+            ("-A", -a),
+            ("-M", -m),
+            ("D+1", d + 1),
+            ("A+1", a + 1),
+            ("M+1", m + 1),
+            ("D-1", d - 1),
+            ("A-1", a - 1),
+            ("M-1", m - 1),
+            ("D+A", d + a),
+            ("D+M", d + m),
+            ("D-A", d - a),
+            ("D-M", d - m),
+            ("A-D", a - d),
+            ("M-D", m - d),
+            ("D&A", d & a),
+            ("D&M", d & m),
+            ("D|A", d | a),
+            ("D|M", d | m),
+        ];
+
+        let mut failures = Vec::default();
+        for (comp, expected) in cases {
+            let cpu = run(&format!("D={comp}"), d, a, m);
+            if cpu.d() != expected {
+                failures.push(format!("{comp}: expected={expected}; actual={}", cpu.d()));
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn overflow_wraps() {
+        assert_eq!(run("D=D+1", i16::MAX, 0, 0).d(), i16::MIN);
+        assert_eq!(run("D=-D", i16::MIN, 0, 0).d(), i16::MIN);
+        assert_eq!(run("D=M-1", 0, 100, i16::MIN).d(), i16::MAX);
+    }
+
+    #[test]
+    fn jumps() {
+        let cases = [
+            ("JGT", [false, false, true]),
+            ("JEQ", [false, true, false]),
+            ("JGE", [false, true, true]),
+            ("JLT", [true, false, false]),
+            ("JNE", [true, false, true]),
+            ("JLE", [true, true, false]),
+            ("JMP", [true, true, true]),
+        ];
+
+        let mut failures = Vec::default();
+        for (jump, expected) in cases {
+            for (d, expected) in [-1, 0, 1].into_iter().zip(expected) {
+                let cpu = run(&format!("D;{jump}"), d, 100, 0);
+                let pc = match expected {
+                    true => 100,
+                    false => 1,
+                };
+                if cpu.pc() != pc {
+                    failures.push(format!("D={d};{jump}: expected={pc}; actual={}", cpu.pc()));
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn a_instruction() {
+        let cpu = run("@12345", 0, 0, 0);
+
+        assert_eq!((cpu.a(), cpu.pc(), cpu.cycles()), (12345, 1, 1));
+    }
+
+    #[test]
+    fn assignments_use_registers_from_start_of_cycle() {
+        // M is written at the old A, & the jump targets the old A.
+        let cpu = run("AMD=A+1;JMP", 0, 100, 0);
+        assert_eq!((cpu.a(), cpu.d(), cpu.ram()[100], cpu.ram()[101]), (101, 101, 101, 0));
+        assert_eq!(cpu.pc(), 100);
+
+        // M is read before being written.
+        let cpu = run("MD=M+1", 0, 100, 7);
+        assert_eq!((cpu.d(), cpu.ram()[100]), (8, 8));
+
+        // Without an assignment only the jump has an effect.
+        let cpu = run("D+1;JEQ", -1, 100, 0);
+        assert_eq!((cpu.d(), cpu.pc()), (-1, 100));
+    }
+
+    #[test]
+    fn execute_errors() {
+        let mut cpu = Cpu::from_asm("Test.asm", "M=1").unwrap();
+        cpu.set_a(-1);
+        assert_eq!(cpu.step(), Err(ExecuteError::AddressOutOfBounds { pc: 0, address: u16::MAX }));
+
+        // Reading M is checked as well, even when it is only used for a jump.
+        let mut cpu = Cpu::from_asm("Test.asm", "M;JMP").unwrap();
+        cpu.set_a(-1);
+        assert!(cpu.step().is_err());
+
+        let mut cpu = Cpu::from_asm("Test.asm", "@0").unwrap();
+        cpu.set_pc(u16::MAX);
+        assert_eq!(cpu.step(), Err(ExecuteError::PcOutOfBounds(u16::MAX)));
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// The largest Hack key code, `f12`.
+const MAX_KEY: i16 = 152;
+
+/// A queue of scripted key presses.
+///
+/// Scripts contain one `<cycle> <key>` event per line, the key is held from
+/// that cycle until the next event. Keys are either a single character, one of
+/// the named Hack keys (`space`, `newline`, `backspace`, `left`, `up`, `right`,
+/// `down`, `home`, `end`, `pageup`, `pagedown`, `insert`, `delete`, `esc`,
+/// `f1`..`f12`), `release`, or a raw code up to `#152` such as `#130`.
+#[derive(Debug, Default, Clone)]
+pub struct KeyboardScript {
+    events: VecDeque<(u64, i16)>,
+}
+
+impl KeyboardScript {
+    /// Pops all events due by `cycle`, returning the latest key if any were
+    /// due.
+    pub fn poll(&mut self, cycle: u64) -> Option<i16> {
+        let mut key = None;
+        while let Some((_, next)) = self.events.front().filter(|(due, _)| *due <= cycle) {
+            key = Some(*next);
+            self.events.pop_front();
+        }
+
+        key
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl FromStr for KeyboardScript {
+    type Err = KeyboardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut events = VecDeque::default();
+        for (number, line) in s.lines().enumerate() {
+            let line = line
+                .split_once("//")
+                .map_or(line, |(event, _)| event)
+                .trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let event = line
+                .split_once(char::is_whitespace)
+                .and_then(|(cycle, key)| Some((cycle.parse().ok()?, key_code(key.trim())?)))
+                .ok_or_else(|| KeyboardError::Event { line: number + 1, event: line.to_owned() })?;
+            if events.back().is_some_and(|(prev, _)| *prev > event.0) {
+                return Err(KeyboardError::Order { line: number + 1 });
+            }
+
+            events.push_back(event);
+        }
+
+        Ok(KeyboardScript { events })
+    }
+}
+
+/// Maps a key name to its Hack key code.
+pub fn key_code(key: &str) -> Option<i16> {
+    if let Some(code) = key.strip_prefix('#') {
+        // Signs are rejected along with codes outside the Hack character set.
+        return code
+            .bytes()
+            .all(|byte| byte.is_ascii_digit())
+            .then(|| code.parse().ok())
+            .flatten()
+            .filter(|code| (0..=MAX_KEY).contains(code));
+    }
+
+    let mut chars = key.chars();
+    if let (Some(char), None) = (chars.next(), chars.next()) {
+        return char.is_ascii_graphic().then_some(char as i16);
+    }
+
+    match key.to_lowercase().as_str() {
+        "release" => Some(0),
+        "space" => Some(32),
+        "newline" => Some(128),
+        "backspace" => Some(129),
+        "left" => Some(130),
+        "up" => Some(131),
+        "right" => Some(132),
+        "down" => Some(133),
+        "home" => Some(134),
+        "end" => Some(135),
+        "pageup" => Some(136),
+        "pagedown" => Some(137),
+        "insert" => Some(138),
+        "delete" => Some(139),
+        "esc" => Some(140),
+        function => (1..=12)
+            .find(|number| function == format!("f{number}"))
+            .map(|number| 140 + number),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum KeyboardError {
+    #[error("Invalid keyboard event; line={line}; event={event}")]
+    Event { line: usize, event: String },
+    #[error("Keyboard event scheduled before previous event; line={line}")]
+    Order { line: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let source = "\
+// Comments & blank lines are skipped.
+
+0 a
+  10   newline  // Trailing comment.
+10 #0
+
+  \t
+20 release
+";
+        let mut script: KeyboardScript = source.parse().unwrap();
+
+        assert_eq!(Vec::from(script.events.clone()), [(0, 97), (10, 128), (10, 0), (20, 0)]);
+        assert!(!script.is_empty());
+        script.poll(20);
+        assert!(script.is_empty());
+        assert!("".parse::<KeyboardScript>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("0 a\n\n// Comment.\n5 b\n4 c\n", KeyboardError::Order { line: 5 }),
+            ("0 a\n\nx b\n", KeyboardError::Event { line: 3, event: "x b".to_owned() }),
+            ("0 a\n1\n", KeyboardError::Event { line: 2, event: "1".to_owned() }),
+            ("\n-1 a\n", KeyboardError::Event { line: 2, event: "-1 a".to_owned() }),
+            (
+                "0 a\n1 foo // Comment.\n",
+                KeyboardError::Event { line: 2, event: "1 foo".to_owned() },
+            ),
+            ("0 #-5\n", KeyboardError::Event { line: 1, event: "0 #-5".to_owned() }),
+        ];
+
+        let mut failures = Vec::default();
+        for (source, expected) in cases {
+            let res = source.parse::<KeyboardScript>();
+            if res.as_ref().err() != Some(&expected) {
+                failures.push(format!("{source:?}: {res:?}"));
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    /// Each poll returns the latest of the keys that came due, or nothing when
+    /// none did.
+    #[test]
+    fn poll() {
+        let mut script: KeyboardScript = "5 a\n7 b\n8 c\n8 d\n12 release\n".parse().unwrap();
+
+        assert_eq!(script.poll(4), None);
+        assert_eq!(script.poll(5), Some(97));
+        assert_eq!(script.poll(6), None);
+        assert_eq!(script.poll(10), Some(100));
+        assert_eq!(script.poll(11), None);
+        assert_eq!(script.poll(12), Some(0));
+        assert!(script.is_empty());
+        assert_eq!(script.poll(100), None);
+    }
+
+    #[test]
+    fn key_codes() {
+        let cases = [
+            ("a", Some(97)),
+            ("A", Some(65)),
+            ("~", Some(126)),
+            ("é", None),
+            (" ", None),
+            ("", None),
+            ("release", Some(0)),
+            ("space", Some(32)),
+            ("newline", Some(128)),
+            ("Backspace", Some(129)),
+            ("left", Some(130)),
+            ("up", Some(131)),
+            ("right", Some(132)),
+            ("down", Some(133)),
+            ("home", Some(134)),
+            ("end", Some(135)),
+            ("pageup", Some(136)),
+            ("pagedown", Some(137)),
+            ("insert", Some(138)),
+            ("delete", Some(139)),
+            ("ESC", Some(140)),
+            ("f1", Some(141)),
+            ("F12", Some(152)),
+            ("f0", None),
+            ("f13", None),
+            ("f01", None),
+            ("f+1", None),
+            ("f", Some(102)),
+            ("#0", Some(0)),
+            ("#130", Some(130)),
+            ("#152", Some(152)),
+            ("#153", None),
+            ("#40000", None),
+            ("#-5", None),
+            ("#+5", None),
+            ("#", None),
+            ("enter", None),
+        ];
+
+        let mut failures = Vec::default();
+        for (key, expected) in cases {
+            let code = key_code(key);
+            if code != expected {
+                failures.push(format!("{key:?}: expected {expected:?}, got {code:?}"));
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
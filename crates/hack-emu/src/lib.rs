@@ -0,0 +1,14 @@
+mod cpu;
+pub use cpu::*;
+
+mod keyboard;
+pub use keyboard::*;
+
+mod loader;
+pub use loader::*;
+
+mod screen;
+pub use screen::*;
+
+mod script;
+pub use script::*;
@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use hack_assembler::AssembleError;
+use shared::hack::DecodeError;
+use thiserror::Error;
+
+use crate::Cpu;
+
+impl Cpu {
+    /// Loads either a `.hack` binary or `.asm` source file.
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| LoadError::Io { path: path.to_owned(), err })?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("hack") => Self::from_hack(&source),
+            Some("asm") => Self::from_asm(&path.display().to_string(), &source),
+            _ => Err(LoadError::Extension(path.to_owned())),
+        }
+    }
+
+    /// Loads a program made of one 16 character binary word per line.
+    pub fn from_hack(source: &str) -> Result<Self, LoadError> {
+        Self::new(&parse_hack(source)?)
+    }
+
+    /// Assembles & loads a program.
+    pub fn from_asm(file: &str, source: &str) -> Result<Self, LoadError> {
+        Self::new(&hack_assembler::assemble(file, source).map_err(LoadError::Assemble)?)
+    }
+}
+
+/// Parses a `.hack` file containing one 16 character binary word per line.
+pub fn parse_hack(source: &str) -> Result<Vec<u16>, LoadError> {
+    source
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| match line.len() {
+            16 => u16::from_str_radix(line, 2)
+                .map_err(|_| LoadError::Word { line: number + 1, word: line.to_owned() }),
+            _ => Err(LoadError::Word { line: number + 1, word: line.to_owned() }),
+        })
+        .collect()
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Unsupported file extension; path={}", .0.display())]
+    Extension(PathBuf),
+    #[error("Invalid word; line={line}; word={word}")]
+    Word { line: usize, word: String },
+    #[error("Unsupported instruction; address={address}; err={err}")]
+    Decode { address: usize, err: DecodeError },
+    #[error("Program does not fit in ROM; instructions={0}")]
+    TooLarge(usize),
+    #[error("Failed to assemble; errors={}", .0.len())]
+    Assemble(Vec<AssembleError>),
+}
@@ -0,0 +1,116 @@
+mod args;
+
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use clap::Parser;
+    use hack_emu::{Cpu, KeyboardScript, LoadError, RAM_SIZE};
+
+    // Parse command line args.
+    let args = args::Args::parse();
+
+    // Test scripts drive the emulator themselves.
+    if args.path.extension().is_some_and(|ext| ext == "tst") {
+        return match hack_emu::run_script(&args.path) {
+            Ok(lines) => {
+                println!("End of script; compared_lines={lines}");
+
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Load the program.
+    let mut cpu = match Cpu::load(&args.path) {
+        Ok(cpu) => cpu,
+        Err(LoadError::Assemble(errors)) => {
+            for err in errors {
+                eprintln!("{err}\n");
+            }
+
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Initialize RAM.
+    for (address, value) in args.set {
+        let Some(word) = cpu.ram_mut().get_mut(address) else {
+            eprintln!("Address outside of RAM; address={address}");
+
+            return ExitCode::FAILURE;
+        };
+        *word = value;
+    }
+
+    // Schedule the keyboard input.
+    if let Some(path) = &args.keyboard {
+        let script = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|script| {
+                script
+                    .parse::<KeyboardScript>()
+                    .map_err(|err| err.to_string())
+            });
+        match script {
+            Ok(script) => cpu.set_keyboard(script),
+            Err(err) => {
+                eprintln!("Failed to load keyboard script; path={}; err={err}", path.display());
+
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    // Execute the program.
+    let cycles = match cpu.run(args.max_cycles) {
+        Ok(cycles) => cycles,
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+    match cpu.is_halted() {
+        true => println!("Halted after {cycles} cycles"),
+        false => println!("Stopped after {cycles} cycles"),
+    }
+
+    // Dump the final state.
+    println!("PC={} A={} D={}", cpu.pc(), cpu.a(), cpu.d());
+    for address in args.ram.start.min(RAM_SIZE)..args.ram.end.min(RAM_SIZE) {
+        println!("RAM[{address}]={}", cpu.ram()[address]);
+    }
+
+    // Dump the screen.
+    if let Some(path) = &args.screen {
+        let screen = cpu.screen();
+        let res = std::fs::File::create(path).and_then(|file| {
+            let mut file = std::io::BufWriter::new(file);
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("png") => screen.write_png(&mut file),
+                _ => screen.write_pbm(&mut file),
+            }?;
+
+            file.into_inner()
+                .map_err(|err| err.into_error())?
+                .sync_all()
+        });
+        if let Err(err) = res {
+            eprintln!("Failed to write screen; path={}; err={err}", path.display());
+
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,190 @@
+use std::io::Write;
+
+use shared::hack::PredefinedSymbols;
+
+use crate::Cpu;
+
+pub const SCREEN_WIDTH: usize = 512;
+pub const SCREEN_HEIGHT: usize = 256;
+const WORDS_PER_ROW: usize = SCREEN_WIDTH / 16;
+
+/// A view of the memory mapped screen, each word maps 16 horizontal pixels with
+/// the least significant bit being the leftmost pixel.
+pub struct Screen<'a> {
+    words: &'a [i16],
+}
+
+impl Cpu {
+    pub fn screen(&self) -> Screen<'_> {
+        let start = usize::from(PredefinedSymbols::Screen.address());
+
+        Screen { words: &self.ram()[start..start + WORDS_PER_ROW * SCREEN_HEIGHT] }
+    }
+}
+
+impl<'a> Screen<'a> {
+    /// Returns true if the pixel is black.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        assert!(x < SCREEN_WIDTH && y < SCREEN_HEIGHT);
+
+        self.words[y * WORDS_PER_ROW + x / 16] as u16 & (1 << (x % 16)) != 0
+    }
+
+    /// Writes the screen as a binary (P4) PBM image.
+    pub fn write_pbm(&self, wx: &mut impl Write) -> std::io::Result<()> {
+        write!(wx, "P4\n{SCREEN_WIDTH} {SCREEN_HEIGHT}\n")?;
+        for y in 0..SCREEN_HEIGHT {
+            wx.write_all(&self.packed_row(y))?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the screen as a 1-bit grayscale PNG image.
+    pub fn write_png(&self, wx: &mut impl Write) -> std::io::Result<()> {
+        // Raw scanlines, each prefixed with filter type 0 (none). PNG grayscale
+        // treats 1 as white, so we invert our black pixels.
+        let mut scanlines = Vec::with_capacity(SCREEN_HEIGHT * (1 + SCREEN_WIDTH / 8));
+        for y in 0..SCREEN_HEIGHT {
+            scanlines.push(0);
+            scanlines.extend(self.packed_row(y).map(|byte| !byte));
+        }
+
+        // Wrap the scanlines in a zlib stream made of a single stored deflate block.
+        let len = u16::try_from(scanlines.len()).unwrap();
+        let mut idat = vec![0x78, 0x01, 0x01];
+        idat.extend(len.to_le_bytes());
+        idat.extend((!len).to_le_bytes());
+        idat.extend(&scanlines);
+        idat.extend(adler32(&scanlines).to_be_bytes());
+
+        // Width, height, bit depth 1, grayscale, default compression/filter/interlace.
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend((SCREEN_WIDTH as u32).to_be_bytes());
+        ihdr.extend((SCREEN_HEIGHT as u32).to_be_bytes());
+        ihdr.extend([1, 0, 0, 0, 0]);
+
+        wx.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(wx, b"IHDR", &ihdr)?;
+        write_chunk(wx, b"IDAT", &idat)?;
+        write_chunk(wx, b"IEND", &[])
+    }
+
+    /// Packs a row into bytes with the leftmost pixel in the most significant
+    /// bit.
+    fn packed_row(&self, y: usize) -> [u8; SCREEN_WIDTH / 8] {
+        let mut row = [0; SCREEN_WIDTH / 8];
+        for (i, word) in self.words[y * WORDS_PER_ROW..(y + 1) * WORDS_PER_ROW]
+            .iter()
+            .enumerate()
+        {
+            let [low, high] = (*word as u16).reverse_bits().to_be_bytes();
+            row[i * 2] = low;
+            row[i * 2 + 1] = high;
+        }
+
+        row
+    }
+}
+
+fn write_chunk(wx: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    wx.write_all(&u32::try_from(data.len()).unwrap().to_be_bytes())?;
+    wx.write_all(kind)?;
+    wx.write_all(data)?;
+    wx.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+
+        (a, (b + a) % 65521)
+    });
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789".iter()), 0xCBF4_3926);
+        assert_eq!(crc32(b"IEND".iter()), 0xAE42_6082);
+        assert_eq!(crc32([].iter()), 0);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn png() {
+        let mut cpu = Cpu::new(&[]).unwrap();
+        let screen = usize::from(PredefinedSymbols::Screen.address());
+        // The leftmost pixel of the first row & the rightmost of the last.
+        cpu.ram_mut()[screen] = 1;
+        cpu.ram_mut()[screen + WORDS_PER_ROW * SCREEN_HEIGHT - 1] = i16::MIN;
+
+        let mut png = Vec::default();
+        cpu.screen().write_png(&mut png).unwrap();
+
+        // Splits the next chunk off `rest`, checking its CRC.
+        let mut rest = png.strip_prefix(b"\x89PNG\r\n\x1a\n").unwrap();
+        let mut chunk = || {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(kind.iter().chain(data)));
+            rest = &rest[12 + len..];
+
+            (kind, data)
+        };
+
+        let (kind, ihdr) = chunk();
+        assert_eq!(kind, b"IHDR");
+        assert_eq!(ihdr, [0, 0, 2, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0]);
+
+        // A zlib header & a single final stored block, followed by its Adler-32.
+        let (kind, idat) = chunk();
+        assert_eq!(kind, b"IDAT");
+        let stride = 1 + SCREEN_WIDTH / 8;
+        let len = u16::try_from(stride * SCREEN_HEIGHT).unwrap();
+        assert_eq!(idat[..3], [0x78, 0x01, 0x01]);
+        assert_eq!(idat[3..7], [len.to_le_bytes(), (!len).to_le_bytes()].concat());
+        let scanlines = &idat[7..idat.len() - 4];
+        assert_eq!(scanlines.len(), usize::from(len));
+        assert_eq!(idat[idat.len() - 4..], adler32(scanlines).to_be_bytes());
+
+        // Each scanline is unfiltered, with black pixels as 0 bits.
+        let first = &scanlines[..stride];
+        assert_eq!(first[..3], [0, 0b0111_1111, 0xFF]);
+        assert!(first[3..].iter().all(|byte| *byte == 0xFF));
+        let last = &scanlines[scanlines.len() - stride..];
+        assert_eq!(last[last.len() - 1], 0b1111_1110);
+        assert!(scanlines.chunks(stride).all(|line| line[0] == 0));
+        let black: u32 = scanlines
+            .chunks(stride)
+            .flat_map(|line| &line[1..])
+            .map(|byte| byte.count_zeros())
+            .sum();
+        assert_eq!(black, 2);
+
+        assert_eq!(chunk(), (&b"IEND"[..], &[][..]));
+        assert!(rest.is_empty());
+    }
+}
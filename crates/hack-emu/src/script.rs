@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use shared::tst::{self, Command, Simulator, TestError};
+use thiserror::Error;
+
+use crate::{Cpu, ExecuteError, LoadError};
+
+/// Runs a CPU emulator test script, returning the number of output lines that
+/// matched the compare file.
+pub fn run_script(path: &Path) -> Result<usize, TestError<CpuTestError>> {
+    tst::run_script(path, &mut CpuSimulator::default())
+}
+
+/// Exposes the CPU to test scripts via `PC`, `A`, `D`, `time` & `RAM[n]`.
+#[derive(Default)]
+pub struct CpuSimulator {
+    cpu: Option<Cpu>,
+}
+
+impl CpuSimulator {
+    fn cpu(&self) -> Result<&Cpu, CpuTestError> {
+        self.cpu.as_ref().ok_or(CpuTestError::NoProgram)
+    }
+
+    fn cpu_mut(&mut self) -> Result<&mut Cpu, CpuTestError> {
+        self.cpu.as_mut().ok_or(CpuTestError::NoProgram)
+    }
+}
+
+impl Simulator for CpuSimulator {
+    type Error = CpuTestError;
+
+    fn load(&mut self, dir: &Path, file: Option<&str>) -> Result<(), Self::Error> {
+        let file = file.ok_or_else(|| CpuTestError::Unsupported("load".to_owned()))?;
+        self.cpu = Some(Cpu::load(&dir.join(file))?);
+
+        Ok(())
+    }
+
+    fn load_part(&mut self, part: &str, path: &Path) -> Result<(), Self::Error> {
+        match part {
+            "ROM32K" => self.cpu = Some(Cpu::load(path)?),
+            _ => return Err(CpuTestError::Unsupported(format!("{part} load"))),
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, variable: &str) -> Result<i64, Self::Error> {
+        let cpu = self.cpu()?;
+
+        Ok(match variable {
+            "PC" => i64::from(cpu.pc()),
+            "A" => i64::from(cpu.a()),
+            "D" => i64::from(cpu.d()),
+            "time" => cpu.cycles() as i64,
+            _ => i64::from(cpu.ram()[ram_address(variable)?]),
+        })
+    }
+
+    fn set(&mut self, variable: &str, value: i64) -> Result<(), Self::Error> {
+        let cpu = self.cpu_mut()?;
+        match variable {
+            "PC" => cpu.set_pc(value as u16),
+            "A" => cpu.set_a(value as i16),
+            "D" => cpu.set_d(value as i16),
+            _ => cpu.ram_mut()[ram_address(variable)?] = value as i16,
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self, command: &Command) -> Result<(), Self::Error> {
+        match command {
+            Command::TickTock => Ok(self.cpu_mut()?.step()?),
+            _ => Err(CpuTestError::Unsupported(format!("{command:?}"))),
+        }
+    }
+}
+
+/// Parses `RAM[n]` into `n`.
+fn ram_address(variable: &str) -> Result<usize, CpuTestError> {
+    variable
+        .strip_prefix("RAM[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|address| address.parse().ok())
+        .filter(|address| *address < crate::RAM_SIZE)
+        .ok_or_else(|| CpuTestError::UnknownVariable(variable.to_owned()))
+}
+
+#[derive(Debug, Error)]
+pub enum CpuTestError {
+    #[error("Failed to load program; err={0}")]
+    Load(#[from] LoadError),
+    #[error("Execution failed; err={0}")]
+    Execute(#[from] ExecuteError),
+    #[error("No program loaded")]
+    NoProgram,
+    #[error("Unknown variable; variable={0}")]
+    UnknownVariable(String),
+    #[error("Unsupported command; command={0}")]
+    Unsupported(String),
+}
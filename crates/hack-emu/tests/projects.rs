@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+/// Assembles every program in `projects/04` and runs its automatic CPU
+/// emulator test scripts against a scratch copy of its directory, so the
+/// generated `.hack` & `.out` files stay out of the tree. Interactive scripts
+/// have no compare file & are skipped.
+#[test]
+fn project_scripts() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects");
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("projects");
+
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(projects.join("04"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    dirs.sort();
+    assert!(!dirs.is_empty());
+
+    let mut scripts = Vec::default();
+    let mut failures = Vec::default();
+    for dir in dirs {
+        let copy = scratch.join(dir.strip_prefix(&projects).unwrap());
+        std::fs::create_dir_all(&copy).unwrap();
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let target = copy.join(path.file_name().unwrap());
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("asm") => {
+                    let source = std::fs::read_to_string(&path).unwrap();
+                    match hack_assembler::translate_file(&path.display().to_string(), &source) {
+                        Ok(hack) => std::fs::write(target.with_extension("hack"), hack).unwrap(),
+                        Err(errors) => failures.push(format!("{}: {errors:?}", path.display())),
+                    }
+                }
+                // The programs are assembled from source instead.
+                Some("hack") => {}
+                Some("tst") if path.with_extension("cmp").is_file() => {
+                    std::fs::copy(&path, &target).unwrap();
+                    scripts.push(target);
+                }
+                _ => {
+                    std::fs::copy(&path, &target).unwrap();
+                }
+            }
+        }
+    }
+    scripts.sort();
+    assert!(!scripts.is_empty());
+
+    for script in scripts {
+        if let Err(err) = hack_emu::run_script(&script) {
+            failures.push(format!("{}: {err}", script.display()));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
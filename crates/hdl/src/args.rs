@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+pub(crate) struct Args {
+    /// The `.hdl` chip to simulate, or a `.tst` script to run.
+    pub(crate) path: PathBuf,
+    /// Additional directories to search for parts.
+    #[arg(long)]
+    pub(crate) lib: Vec<PathBuf>,
+    /// Drive an input pin before simulating, e.g. `--set sel=1`.
+    #[arg(long, value_parser = parse_assignment)]
+    pub(crate) set: Vec<(String, i32)>,
+    /// The number of clock cycles to run, zero only evaluates the chip.
+    #[arg(long, default_value_t = 0)]
+    pub(crate) cycles: u64,
+}
+
+fn parse_assignment(s: &str) -> Result<(String, i32), String> {
+    let (pin, value) = s.split_once('=').ok_or("Expected PIN=VALUE")?;
+    let value = value
+        .parse()
+        .map_err(|err| format!("Invalid value; err={err}"))?;
+
+    Ok((pin.to_owned(), value))
+}
@@ -0,0 +1,444 @@
+/// The behaviour of a built-in chip.
+///
+/// Pin values are passed in the order of the chip's [`BuiltinDef`] with the
+/// bits of each bus packed into a `u16`.
+pub trait Primitive {
+    /// Computes the outputs from the inputs & current state.
+    fn eval(&self, inputs: &[u16], outputs: &mut [u16]);
+
+    /// Latches the inputs into the state on the rising clock edge.
+    fn tick(&mut self, _inputs: &[u16]) {}
+
+    /// Reads an internal register, e.g. `RAM16K[3]` or `PC[]` (index 0).
+    fn state(&self, _index: usize) -> Option<u16> {
+        None
+    }
+
+    /// Writes an internal register, returning false if it does not exist.
+    fn set_state(&mut self, _index: usize, _value: u16) -> bool {
+        false
+    }
+}
+
+/// The signature & implementation of a built-in chip.
+pub struct BuiltinDef {
+    pub name: &'static str,
+    pub inputs: &'static [(&'static str, u8)],
+    pub outputs: &'static [(&'static str, u8)],
+    /// The inputs that combinationally affect the outputs of a clocked chip,
+    /// `None` if the chip is purely combinational.
+    pub clocked: Option<&'static [&'static str]>,
+    pub new: fn() -> Box<dyn Primitive>,
+}
+
+impl BuiltinDef {
+    /// Returns true if `input` combinationally affects the outputs.
+    pub fn is_combinational(&self, input: &str) -> bool {
+        self.clocked.map_or(true, |inputs| inputs.contains(&input))
+    }
+}
+
+/// Looks up a built-in chip by name.
+pub fn builtin(name: &str) -> Option<&'static BuiltinDef> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// A stateless chip.
+struct Gate(fn(&[u16], &mut [u16]));
+
+impl Primitive for Gate {
+    fn eval(&self, inputs: &[u16], outputs: &mut [u16]) {
+        (self.0)(inputs, outputs);
+    }
+}
+
+/// A register with an optional load pin, covers `DFF`, `Bit` & `Register`.
+#[derive(Default)]
+struct Register {
+    value: u16,
+    /// The index of the load pin, `None` if always loading.
+    load: Option<usize>,
+}
+
+impl Primitive for Register {
+    fn eval(&self, _: &[u16], outputs: &mut [u16]) {
+        outputs[0] = self.value;
+    }
+
+    fn tick(&mut self, inputs: &[u16]) {
+        if self.load.map_or(true, |load| inputs[load] != 0) {
+            self.value = inputs[0];
+        }
+    }
+
+    fn state(&self, index: usize) -> Option<u16> {
+        (index == 0).then_some(self.value)
+    }
+
+    fn set_state(&mut self, index: usize, value: u16) -> bool {
+        if index == 0 {
+            self.value = value;
+        }
+
+        index == 0
+    }
+}
+
+/// The program counter.
+#[derive(Default)]
+struct Counter {
+    value: u16,
+}
+
+impl Primitive for Counter {
+    fn eval(&self, _: &[u16], outputs: &mut [u16]) {
+        outputs[0] = self.value;
+    }
+
+    fn tick(&mut self, inputs: &[u16]) {
+        let [input, load, inc, reset] = inputs else { unreachable!() };
+        self.value = match (*reset != 0, *load != 0, *inc != 0) {
+            (true, ..) => 0,
+            (false, true, _) => *input,
+            (false, false, true) => self.value.wrapping_add(1),
+            (false, false, false) => self.value,
+        };
+    }
+
+    fn state(&self, index: usize) -> Option<u16> {
+        (index == 0).then_some(self.value)
+    }
+
+    fn set_state(&mut self, index: usize, value: u16) -> bool {
+        if index == 0 {
+            self.value = value;
+        }
+
+        index == 0
+    }
+}
+
+/// Addressable memory, covers the RAM chips, `ROM32K`, `Screen` & `Keyboard`.
+///
+/// Writable memories take `in`, `load` & `address` inputs, read only memories
+/// just `address` and the keyboard none at all.
+struct Memory {
+    words: Vec<u16>,
+}
+
+impl Memory {
+    fn new(size: usize) -> Self {
+        Memory { words: vec![0; size] }
+    }
+
+    fn address(&self, inputs: &[u16]) -> usize {
+        inputs.last().map_or(0, |address| usize::from(*address))
+    }
+}
+
+impl Primitive for Memory {
+    fn eval(&self, inputs: &[u16], outputs: &mut [u16]) {
+        outputs[0] = self.words[self.address(inputs)];
+    }
+
+    fn tick(&mut self, inputs: &[u16]) {
+        if let [input, load, _] = inputs {
+            if *load != 0 {
+                let address = self.address(inputs);
+                self.words[address] = *input;
+            }
+        }
+    }
+
+    fn state(&self, index: usize) -> Option<u16> {
+        self.words.get(index).copied()
+    }
+
+    fn set_state(&mut self, index: usize, value: u16) -> bool {
+        self.words
+            .get_mut(index)
+            .map(|word| *word = value)
+            .is_some()
+    }
+}
+
+fn alu(inputs: &[u16], outputs: &mut [u16]) {
+    let [x, y, zx, nx, zy, ny, f, no] = inputs else { unreachable!() };
+    let x = if *zx != 0 { 0 } else { *x };
+    let x = if *nx != 0 { !x } else { x };
+    let y = if *zy != 0 { 0 } else { *y };
+    let y = if *ny != 0 { !y } else { y };
+    let out = if *f != 0 { x.wrapping_add(y) } else { x & y };
+    let out = if *no != 0 { !out } else { out };
+
+    outputs[0] = out;
+    outputs[1] = u16::from(out == 0);
+    outputs[2] = out >> 15;
+}
+
+/// Routes `input` to the output selected by `sel`.
+fn demux(input: u16, sel: u16, outputs: &mut [u16]) {
+    for (index, output) in outputs.iter_mut().enumerate() {
+        *output = if usize::from(sel) == index { input } else { 0 };
+    }
+}
+
+const GATE_1: &[(&str, u8)] = &[("a", 1), ("b", 1)];
+const GATE_16: &[(&str, u8)] = &[("a", 16), ("b", 16)];
+const OUT_1: &[(&str, u8)] = &[("out", 1)];
+const OUT_16: &[(&str, u8)] = &[("out", 16)];
+const MEMORY: &[&str] = &["address"];
+
+macro_rules! ram {
+    ($name:literal, $bits:literal) => {
+        BuiltinDef {
+            name: $name,
+            inputs: &[("in", 16), ("load", 1), ("address", $bits)],
+            outputs: OUT_16,
+            clocked: Some(MEMORY),
+            new: || Box::new(Memory::new(1 << $bits)),
+        }
+    };
+}
+
+static BUILTINS: &[BuiltinDef] = &[
+    BuiltinDef {
+        name: "Nand",
+        inputs: GATE_1,
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = !(i[0] & i[1]))),
+    },
+    BuiltinDef {
+        name: "Not",
+        inputs: &[("in", 1)],
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = !i[0])),
+    },
+    BuiltinDef {
+        name: "And",
+        inputs: GATE_1,
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0] & i[1])),
+    },
+    BuiltinDef {
+        name: "Or",
+        inputs: GATE_1,
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0] | i[1])),
+    },
+    BuiltinDef {
+        name: "Xor",
+        inputs: GATE_1,
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0] ^ i[1])),
+    },
+    BuiltinDef {
+        name: "Mux",
+        inputs: &[("a", 1), ("b", 1), ("sel", 1)],
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[usize::from(i[2] != 0)])),
+    },
+    BuiltinDef {
+        name: "DMux",
+        inputs: &[("in", 1), ("sel", 1)],
+        outputs: &[("a", 1), ("b", 1)],
+        clocked: None,
+        new: || Box::new(Gate(|i, o| demux(i[0], i[1], o))),
+    },
+    BuiltinDef {
+        name: "Not16",
+        inputs: &[("in", 16)],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = !i[0])),
+    },
+    BuiltinDef {
+        name: "And16",
+        inputs: GATE_16,
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0] & i[1])),
+    },
+    BuiltinDef {
+        name: "Or16",
+        inputs: GATE_16,
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0] | i[1])),
+    },
+    BuiltinDef {
+        name: "Mux16",
+        inputs: &[("a", 16), ("b", 16), ("sel", 1)],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[usize::from(i[2] != 0)])),
+    },
+    BuiltinDef {
+        name: "Or8Way",
+        inputs: &[("in", 8)],
+        outputs: OUT_1,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = u16::from(i[0] != 0))),
+    },
+    BuiltinDef {
+        name: "Mux4Way16",
+        inputs: &[("a", 16), ("b", 16), ("c", 16), ("d", 16), ("sel", 2)],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[usize::from(i[4])])),
+    },
+    BuiltinDef {
+        name: "Mux8Way16",
+        inputs: &[
+            ("a", 16),
+            ("b", 16),
+            ("c", 16),
+            ("d", 16),
+            ("e", 16),
+            ("f", 16),
+            ("g", 16),
+            ("h", 16),
+            ("sel", 3),
+        ],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[usize::from(i[8])])),
+    },
+    BuiltinDef {
+        name: "DMux4Way",
+        inputs: &[("in", 1), ("sel", 2)],
+        outputs: &[("a", 1), ("b", 1), ("c", 1), ("d", 1)],
+        clocked: None,
+        new: || Box::new(Gate(|i, o| demux(i[0], i[1], o))),
+    },
+    BuiltinDef {
+        name: "DMux8Way",
+        inputs: &[("in", 1), ("sel", 3)],
+        outputs: &[("a", 1), ("b", 1), ("c", 1), ("d", 1), ("e", 1), ("f", 1), ("g", 1), ("h", 1)],
+        clocked: None,
+        new: || Box::new(Gate(|i, o| demux(i[0], i[1], o))),
+    },
+    BuiltinDef {
+        name: "HalfAdder",
+        inputs: GATE_1,
+        outputs: &[("sum", 1), ("carry", 1)],
+        clocked: None,
+        new: || {
+            Box::new(Gate(|i, o| {
+                o[0] = i[0] ^ i[1];
+                o[1] = i[0] & i[1];
+            }))
+        },
+    },
+    BuiltinDef {
+        name: "FullAdder",
+        inputs: &[("a", 1), ("b", 1), ("c", 1)],
+        outputs: &[("sum", 1), ("carry", 1)],
+        clocked: None,
+        new: || {
+            Box::new(Gate(|i, o| {
+                let sum = i[0] + i[1] + i[2];
+                o[0] = sum & 1;
+                o[1] = sum >> 1;
+            }))
+        },
+    },
+    BuiltinDef {
+        name: "Add16",
+        inputs: GATE_16,
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0].wrapping_add(i[1]))),
+    },
+    BuiltinDef {
+        name: "Inc16",
+        inputs: &[("in", 16)],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Gate(|i, o| o[0] = i[0].wrapping_add(1))),
+    },
+    BuiltinDef {
+        name: "ALU",
+        inputs: &[
+            ("x", 16),
+            ("y", 16),
+            ("zx", 1),
+            ("nx", 1),
+            ("zy", 1),
+            ("ny", 1),
+            ("f", 1),
+            ("no", 1),
+        ],
+        outputs: &[("out", 16), ("zr", 1), ("ng", 1)],
+        clocked: None,
+        new: || Box::new(Gate(alu)),
+    },
+    BuiltinDef {
+        name: "DFF",
+        inputs: &[("in", 1)],
+        outputs: OUT_1,
+        clocked: Some(&[]),
+        new: || Box::<Register>::default(),
+    },
+    BuiltinDef {
+        name: "Bit",
+        inputs: &[("in", 1), ("load", 1)],
+        outputs: OUT_1,
+        clocked: Some(&[]),
+        new: || Box::new(Register { value: 0, load: Some(1) }),
+    },
+    BuiltinDef {
+        name: "Register",
+        inputs: &[("in", 16), ("load", 1)],
+        outputs: OUT_16,
+        clocked: Some(&[]),
+        new: || Box::new(Register { value: 0, load: Some(1) }),
+    },
+    BuiltinDef {
+        name: "ARegister",
+        inputs: &[("in", 16), ("load", 1)],
+        outputs: OUT_16,
+        clocked: Some(&[]),
+        new: || Box::new(Register { value: 0, load: Some(1) }),
+    },
+    BuiltinDef {
+        name: "DRegister",
+        inputs: &[("in", 16), ("load", 1)],
+        outputs: OUT_16,
+        clocked: Some(&[]),
+        new: || Box::new(Register { value: 0, load: Some(1) }),
+    },
+    BuiltinDef {
+        name: "PC",
+        inputs: &[("in", 16), ("load", 1), ("inc", 1), ("reset", 1)],
+        outputs: OUT_16,
+        clocked: Some(&[]),
+        new: || Box::<Counter>::default(),
+    },
+    ram!("RAM8", 3),
+    ram!("RAM64", 6),
+    ram!("RAM512", 9),
+    ram!("RAM4K", 12),
+    ram!("RAM16K", 14),
+    ram!("Screen", 13),
+    BuiltinDef {
+        name: "ROM32K",
+        inputs: &[("address", 15)],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Memory::new(1 << 15)),
+    },
+    BuiltinDef {
+        name: "Keyboard",
+        inputs: &[],
+        outputs: OUT_16,
+        clocked: None,
+        new: || Box::new(Memory::new(1)),
+    },
+];
@@ -0,0 +1,686 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    BuiltinDef, ChipBody, ChipDef, Library, ParseError, PartDef, PinRef, Primitive, Resolved,
+    Signal,
+};
+
+/// A chip flattened into built-in parts connected by single bit nets.
+pub struct Chip {
+    name: String,
+    inputs: Vec<(String, u8)>,
+    outputs: Vec<(String, u8)>,
+    pins: HashMap<String, Vec<usize>>,
+    nets: Vec<bool>,
+    parts: Vec<Part>,
+    /// Parts in combinational dependency order.
+    order: Vec<usize>,
+}
+
+struct Part {
+    builtin: &'static BuiltinDef,
+    primitive: Box<dyn Primitive>,
+    inputs: Vec<Vec<usize>>,
+    outputs: Vec<Vec<usize>>,
+    input_values: Vec<u16>,
+    output_values: Vec<u16>,
+}
+
+impl Part {
+    fn read_inputs(&mut self, nets: &[bool]) {
+        for (value, bits) in self.input_values.iter_mut().zip(&self.inputs) {
+            *value = read(nets, bits);
+        }
+    }
+}
+
+impl Chip {
+    /// Loads a chip from an `.hdl` file, resolving parts from the file's
+    /// directory, then `dirs`, then the built-in chips.
+    pub fn load(path: &Path, dirs: &[PathBuf]) -> Result<Self, LoadError> {
+        let def = Library::parse_file(path)?;
+        let mut library = Library::new(
+            std::iter::once(path.parent().unwrap_or(Path::new(".")).to_owned())
+                .chain(dirs.iter().cloned())
+                .collect(),
+        );
+
+        Self::build(&def, &mut library)
+    }
+
+    /// Flattens a chip definition into a simulator.
+    pub fn build(def: &ChipDef, library: &mut Library) -> Result<Self, LoadError> {
+        let mut builder = Builder {
+            library,
+            parent: vec![FALSE, TRUE],
+            drivers: vec![Some(Driver::Constant), Some(Driver::Constant)],
+            parts: Vec::default(),
+            stack: Vec::default(),
+        };
+
+        // The chip's own pins, inputs are driven from outside.
+        let mut pins = HashMap::new();
+        for pin in &def.inputs {
+            let nets = (0..pin.width)
+                .map(|_| builder.net(Some(Driver::Input)))
+                .collect();
+            pins.insert(pin.name.clone(), nets);
+        }
+        for pin in &def.outputs {
+            let nets = (0..pin.width).map(|_| builder.net(None)).collect();
+            pins.insert(pin.name.clone(), nets);
+        }
+        let internal = builder.instantiate(def, &pins)?;
+        pins.extend(internal);
+
+        // Compact the nets down to their representatives.
+        let mut ids = HashMap::new();
+        let mut canonical = |builder: &mut Builder, net: usize| {
+            let root = builder.find(net);
+            let next = ids.len();
+
+            *ids.entry(root).or_insert(next)
+        };
+        let true_net = canonical(&mut builder, TRUE);
+        let mut parts: Vec<Part> = Vec::default();
+        let mut drivers = HashMap::new();
+        for (index, part) in std::mem::take(&mut builder.parts).into_iter().enumerate() {
+            let mut map = |pins: Vec<Vec<usize>>| {
+                pins.into_iter()
+                    .map(|bits| {
+                        bits.into_iter()
+                            .map(|net| canonical(&mut builder, net))
+                            .collect()
+                    })
+                    .collect::<Vec<Vec<_>>>()
+            };
+            let inputs = map(part.inputs);
+            let outputs = map(part.outputs);
+            for net in outputs.iter().flatten() {
+                drivers.insert(*net, index);
+            }
+
+            parts.push(Part {
+                builtin: part.builtin,
+                primitive: (part.builtin.new)(),
+                input_values: vec![0; inputs.len()],
+                output_values: vec![0; outputs.len()],
+                inputs,
+                outputs,
+            });
+        }
+        let pins = pins
+            .into_iter()
+            .map(|(name, bits)| {
+                let bits = bits
+                    .into_iter()
+                    .map(|net| canonical(&mut builder, net))
+                    .collect();
+
+                (name, bits)
+            })
+            .collect();
+        let mut nets = vec![false; ids.len()];
+        nets[true_net] = true;
+
+        // Order the parts so each is evaluated after the parts driving its
+        // combinational inputs.
+        let mut dependents = vec![Vec::default(); parts.len()];
+        let mut pending = vec![0; parts.len()];
+        for (index, part) in parts.iter().enumerate() {
+            for (bits, (pin, _)) in part.inputs.iter().zip(part.builtin.inputs) {
+                if !part.builtin.is_combinational(pin) {
+                    continue;
+                }
+
+                for driver in bits.iter().filter_map(|net| drivers.get(net)) {
+                    dependents[*driver].push(index);
+                    pending[index] += 1;
+                }
+            }
+        }
+        let mut ready: VecDeque<_> = (0..parts.len())
+            .filter(|part| pending[*part] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(parts.len());
+        while let Some(part) = ready.pop_front() {
+            order.push(part);
+            for dependent in &dependents[part] {
+                pending[*dependent] -= 1;
+                if pending[*dependent] == 0 {
+                    ready.push_back(*dependent);
+                }
+            }
+        }
+        if let Some(part) = (0..parts.len()).find(|part| pending[*part] != 0) {
+            return Err(LoadError::CombinationalLoop {
+                chip: def.name.clone(),
+                part: parts[part].builtin.name,
+            });
+        }
+
+        let mut chip = Chip {
+            name: def.name.clone(),
+            inputs: def
+                .inputs
+                .iter()
+                .map(|pin| (pin.name.clone(), pin.width))
+                .collect(),
+            outputs: def
+                .outputs
+                .iter()
+                .map(|pin| (pin.name.clone(), pin.width))
+                .collect(),
+            pins,
+            nets,
+            parts,
+            order,
+        };
+        chip.eval();
+
+        Ok(chip)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn inputs(&self) -> &[(String, u8)] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(String, u8)] {
+        &self.outputs
+    }
+
+    /// Reads an input, output or internal pin of the top level chip.
+    pub fn get(&self, pin: &str) -> Option<u16> {
+        self.pins.get(pin).map(|bits| read(&self.nets, bits))
+    }
+
+    /// Drives an input pin, the new value propagates on the next evaluation.
+    pub fn set(&mut self, pin: &str, value: u16) -> Result<(), PinError> {
+        if !self.inputs.iter().any(|(name, _)| name == pin) {
+            return Err(PinError::NotInput(pin.to_owned()));
+        }
+        write(&mut self.nets, &self.pins[pin], value);
+
+        Ok(())
+    }
+
+    /// Returns the width of a top level pin.
+    pub fn width(&self, pin: &str) -> Option<u8> {
+        self.pins.get(pin).map(|bits| bits.len() as u8)
+    }
+
+    /// Propagates the inputs through all combinational logic.
+    pub fn eval(&mut self) {
+        let Chip { nets, parts, order, .. } = self;
+        for index in order.iter() {
+            let part = &mut parts[*index];
+            part.read_inputs(nets);
+            part.primitive
+                .eval(&part.input_values, &mut part.output_values);
+            for (value, bits) in part.output_values.iter().zip(&part.outputs) {
+                write(nets, bits, *value);
+            }
+        }
+    }
+
+    /// The rising clock edge, clocked parts latch their inputs.
+    ///
+    /// As with the reference simulator, the new state only reaches the outputs
+    /// on the following [`Chip::tock`].
+    pub fn tick(&mut self) {
+        self.eval();
+
+        let Chip { nets, parts, .. } = self;
+        for part in parts
+            .iter_mut()
+            .filter(|part| part.builtin.clocked.is_some())
+        {
+            part.read_inputs(nets);
+            part.primitive.tick(&part.input_values);
+        }
+    }
+
+    /// The falling clock edge, latched state propagates to the outputs.
+    pub fn tock(&mut self) {
+        self.eval();
+    }
+
+    /// Returns the first built-in part of the given chip type, e.g. `RAM16K`.
+    pub fn part(&self, chip: &str) -> Option<&dyn Primitive> {
+        self.parts
+            .iter()
+            .find(|part| part.builtin.name == chip)
+            .map(|part| part.primitive.as_ref())
+    }
+
+    /// Returns the first built-in part of the given chip type, e.g. `RAM16K`.
+    pub fn part_mut(&mut self, chip: &str) -> Option<&mut (dyn Primitive + 'static)> {
+        self.parts
+            .iter_mut()
+            .find(|part| part.builtin.name == chip)
+            .map(|part| part.primitive.as_mut())
+    }
+}
+
+fn read(nets: &[bool], bits: &[usize]) -> u16 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |value, (bit, net)| value | (u16::from(nets[*net]) << bit))
+}
+
+fn write(nets: &mut [bool], bits: &[usize], value: u16) {
+    for (bit, net) in bits.iter().enumerate() {
+        nets[*net] = value >> bit & 1 == 1;
+    }
+}
+
+const FALSE: usize = 0;
+const TRUE: usize = 1;
+
+#[derive(Debug, Clone, Copy)]
+enum Driver {
+    Constant,
+    Input,
+    Part,
+}
+
+struct PartBuilder {
+    builtin: &'static BuiltinDef,
+    inputs: Vec<Vec<usize>>,
+    outputs: Vec<Vec<usize>>,
+}
+
+/// Flattens chips into built-in parts, merging connected nets with a union
+/// find.
+struct Builder<'a> {
+    library: &'a mut Library,
+    parent: Vec<usize>,
+    /// The driver of each net, only valid for representatives.
+    drivers: Vec<Option<Driver>>,
+    parts: Vec<PartBuilder>,
+    /// The chips currently being instantiated, to reject recursive chips.
+    stack: Vec<String>,
+}
+
+impl Builder<'_> {
+    fn net(&mut self, driver: Option<Driver>) -> usize {
+        self.parent.push(self.parent.len());
+        self.drivers.push(driver);
+
+        self.parent.len() - 1
+    }
+
+    fn find(&mut self, net: usize) -> usize {
+        let mut root = net;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Compress the path.
+        let mut net = net;
+        while self.parent[net] != root {
+            let next = self.parent[net];
+            self.parent[net] = root;
+            net = next;
+        }
+
+        root
+    }
+
+    /// Connects two nets, returning false if both are already driven.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return true;
+        }
+
+        let driver = match (self.drivers[a], self.drivers[b]) {
+            (Some(_), Some(_)) => return false,
+            (driver, None) | (None, driver) => driver,
+        };
+        self.parent[b] = a;
+        self.drivers[a] = driver;
+
+        true
+    }
+
+    /// Instantiates `def` with its pins bound to `pins`, returning the nets of
+    /// its internal pins.
+    fn instantiate(
+        &mut self,
+        def: &ChipDef,
+        pins: &HashMap<String, Vec<usize>>,
+    ) -> Result<HashMap<String, Vec<usize>>, LoadError> {
+        if self.stack.contains(&def.name) {
+            return Err(LoadError::Recursive { chip: def.name.clone() });
+        }
+        self.stack.push(def.name.clone());
+
+        let internal = match &def.body {
+            ChipBody::Builtin(name) => {
+                let builtin = crate::builtin(name).ok_or_else(|| LoadError::UnknownChip {
+                    chip: def.name.clone(),
+                    line: 0,
+                    part: name.clone(),
+                })?;
+                let bind = |pins: &HashMap<String, Vec<usize>>, name: &str| {
+                    pins.get(name)
+                        .cloned()
+                        .ok_or_else(|| LoadError::UnknownPin {
+                            chip: def.name.clone(),
+                            line: 0,
+                            pin: name.to_owned(),
+                        })
+                };
+                let inputs = builtin
+                    .inputs
+                    .iter()
+                    .map(|(name, _)| bind(pins, name))
+                    .collect::<Result<_, _>>()?;
+                let outputs: Vec<Vec<usize>> = builtin
+                    .outputs
+                    .iter()
+                    .map(|(name, _)| bind(pins, name))
+                    .collect::<Result<_, _>>()?;
+                self.drive(&def.name, 0, name, &outputs)?;
+                self.parts.push(PartBuilder { builtin, inputs, outputs });
+
+                HashMap::new()
+            }
+            ChipBody::Parts(parts) => self.instantiate_parts(def, parts, pins)?,
+        };
+
+        self.stack.pop();
+
+        Ok(internal)
+    }
+
+    fn instantiate_parts(
+        &mut self,
+        def: &ChipDef,
+        parts: &[PartDef],
+        pins: &HashMap<String, Vec<usize>>,
+    ) -> Result<HashMap<String, Vec<usize>>, LoadError> {
+        let chip = def.name.as_str();
+        let resolved = parts
+            .iter()
+            .map(|part| {
+                let resolved =
+                    self.library
+                        .resolve(&part.chip)?
+                        .ok_or_else(|| LoadError::UnknownChip {
+                            chip: chip.to_owned(),
+                            line: part.line,
+                            part: part.chip.clone(),
+                        })?;
+
+                Ok((part, Signature::new(&resolved), resolved))
+            })
+            .collect::<Result<Vec<_>, LoadError>>()?;
+
+        // Internal pins take the width of the part output driving them.
+        let mut internal: HashMap<String, Vec<usize>> = HashMap::new();
+        for (part, signature, _) in &resolved {
+            for connection in &part.connections {
+                let (Some(width), Signal::Pin(signal)) =
+                    (signature.output(&connection.pin.name), &connection.signal)
+                else {
+                    continue;
+                };
+                if pins.contains_key(&signal.name) {
+                    continue;
+                }
+                if signal.range.is_some() {
+                    return Err(LoadError::InternalSubscript {
+                        chip: chip.to_owned(),
+                        line: part.line,
+                        pin: signal.to_string(),
+                    });
+                }
+
+                let width = bits(chip, part.line, &connection.pin, width)?.len();
+                match internal.get(&signal.name) {
+                    Some(nets) if nets.len() != width => {
+                        return Err(LoadError::Width {
+                            chip: chip.to_owned(),
+                            line: part.line,
+                            pin: signal.to_string(),
+                            expected: nets.len(),
+                            actual: width,
+                        })
+                    }
+                    Some(_) => {}
+                    None => {
+                        let nets = (0..width).map(|_| self.net(None)).collect();
+                        internal.insert(signal.name.clone(), nets);
+                    }
+                }
+            }
+        }
+
+        for (part, signature, resolved) in resolved {
+            let line = part.line;
+
+            // Allocate the part's pins.
+            let mut part_pins = HashMap::new();
+            for (name, width) in signature.inputs.iter().chain(&signature.outputs) {
+                let nets: Vec<_> = (0..*width).map(|_| self.net(None)).collect();
+                part_pins.insert(name.clone(), nets);
+            }
+
+            for connection in &part.connections {
+                let pin = &connection.pin;
+                let (width, is_output) =
+                    match (signature.input(&pin.name), signature.output(&pin.name)) {
+                        (Some(width), _) => (width, false),
+                        (_, Some(width)) => (width, true),
+                        (None, None) => {
+                            return Err(LoadError::UnknownPin {
+                                chip: chip.to_owned(),
+                                line,
+                                pin: format!("{}.{}", part.chip, pin.name),
+                            })
+                        }
+                    };
+                let pin_nets: Vec<_> = bits(chip, line, pin, width)?
+                    .map(|bit| part_pins[&pin.name][usize::from(bit)])
+                    .collect();
+
+                let signal_nets: Vec<_> = match &connection.signal {
+                    Signal::True | Signal::False if is_output => {
+                        return Err(LoadError::OutputToConstant {
+                            chip: chip.to_owned(),
+                            line,
+                            pin: pin.to_string(),
+                        })
+                    }
+                    Signal::True => vec![TRUE; pin_nets.len()],
+                    Signal::False => vec![FALSE; pin_nets.len()],
+                    Signal::Pin(signal) => {
+                        let nets = pins
+                            .get(&signal.name)
+                            .or_else(|| internal.get(&signal.name))
+                            .ok_or_else(|| LoadError::UnknownPin {
+                                chip: chip.to_owned(),
+                                line,
+                                pin: signal.name.clone(),
+                            })?;
+
+                        bits(chip, line, signal, nets.len() as u8)?
+                            .map(|bit| nets[usize::from(bit)])
+                            .collect()
+                    }
+                };
+                if signal_nets.len() != pin_nets.len() {
+                    return Err(LoadError::Width {
+                        chip: chip.to_owned(),
+                        line,
+                        pin: pin.to_string(),
+                        expected: pin_nets.len(),
+                        actual: signal_nets.len(),
+                    });
+                }
+
+                for (pin_net, signal_net) in pin_nets.into_iter().zip(signal_nets) {
+                    if !self.union(pin_net, signal_net) {
+                        return Err(LoadError::MultipleDrivers {
+                            chip: chip.to_owned(),
+                            line,
+                            pin: pin.to_string(),
+                        });
+                    }
+                }
+            }
+
+            match resolved {
+                Resolved::Hdl(def) => {
+                    self.instantiate(&def, &part_pins)?;
+                }
+                Resolved::Builtin(builtin) => {
+                    let pins = |names: &[(&str, u8)]| -> Vec<Vec<usize>> {
+                        names
+                            .iter()
+                            .map(|(name, _)| part_pins[*name].clone())
+                            .collect()
+                    };
+                    let (inputs, outputs) = (pins(builtin.inputs), pins(builtin.outputs));
+                    self.drive(chip, line, builtin.name, &outputs)?;
+                    self.parts.push(PartBuilder { builtin, inputs, outputs });
+                }
+            }
+        }
+
+        Ok(internal)
+    }
+
+    /// Marks the outputs of a built-in part as driven.
+    fn drive(
+        &mut self,
+        chip: &str,
+        line: usize,
+        part: &str,
+        outputs: &[Vec<usize>],
+    ) -> Result<(), LoadError> {
+        for net in outputs.iter().flatten() {
+            let driven = self.net(Some(Driver::Part));
+            if !self.union(*net, driven) {
+                return Err(LoadError::MultipleDrivers {
+                    chip: chip.to_owned(),
+                    line,
+                    pin: part.to_owned(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The pins of a resolved chip.
+struct Signature {
+    inputs: Vec<(String, u8)>,
+    outputs: Vec<(String, u8)>,
+}
+
+impl Signature {
+    fn new(resolved: &Resolved) -> Self {
+        match resolved {
+            Resolved::Hdl(def) => Signature {
+                inputs: def
+                    .inputs
+                    .iter()
+                    .map(|pin| (pin.name.clone(), pin.width))
+                    .collect(),
+                outputs: def
+                    .outputs
+                    .iter()
+                    .map(|pin| (pin.name.clone(), pin.width))
+                    .collect(),
+            },
+            Resolved::Builtin(builtin) => Signature {
+                inputs: builtin
+                    .inputs
+                    .iter()
+                    .map(|(name, width)| (name.to_string(), *width))
+                    .collect(),
+                outputs: builtin
+                    .outputs
+                    .iter()
+                    .map(|(name, width)| (name.to_string(), *width))
+                    .collect(),
+            },
+        }
+    }
+
+    fn input(&self, name: &str) -> Option<u8> {
+        self.inputs
+            .iter()
+            .find(|(pin, _)| pin == name)
+            .map(|(_, width)| *width)
+    }
+
+    fn output(&self, name: &str) -> Option<u8> {
+        self.outputs
+            .iter()
+            .find(|(pin, _)| pin == name)
+            .map(|(_, width)| *width)
+    }
+}
+
+/// Returns the bits referenced by `pin` given its full `width`.
+fn bits(
+    chip: &str,
+    line: usize,
+    pin: &PinRef,
+    width: u8,
+) -> Result<std::ops::RangeInclusive<u8>, LoadError> {
+    match pin.range {
+        None => Ok(0..=width - 1),
+        Some((_, end)) if end >= width => {
+            Err(LoadError::PinRange { chip: chip.to_owned(), line, pin: pin.to_string(), width })
+        }
+        Some((start, end)) => Ok(start..=end),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Failed to parse; path={}; err={err}", path.display())]
+    Parse { path: PathBuf, err: ParseError },
+    #[error("Unknown chip; chip={chip}; line={line}; part={part}")]
+    UnknownChip { chip: String, line: usize, part: String },
+    #[error("Unknown pin; chip={chip}; line={line}; pin={pin}")]
+    UnknownPin { chip: String, line: usize, pin: String },
+    #[error("Pin range out of bounds; chip={chip}; line={line}; pin={pin}; width={width}")]
+    PinRange { chip: String, line: usize, pin: String, width: u8 },
+    #[error(
+        "Mismatched bus width; chip={chip}; line={line}; pin={pin}; expected={expected}; \
+         actual={actual}"
+    )]
+    Width { chip: String, line: usize, pin: String, expected: usize, actual: usize },
+    #[error("Output connected to constant; chip={chip}; line={line}; pin={pin}")]
+    OutputToConstant { chip: String, line: usize, pin: String },
+    #[error("Sub-bus of internal pin; chip={chip}; line={line}; pin={pin}")]
+    InternalSubscript { chip: String, line: usize, pin: String },
+    #[error("Pin has multiple drivers; chip={chip}; line={line}; pin={pin}")]
+    MultipleDrivers { chip: String, line: usize, pin: String },
+    #[error("Combinational loop; chip={chip}; part={part}")]
+    CombinationalLoop { chip: String, part: &'static str },
+    #[error("Chip contains itself; chip={chip}")]
+    Recursive { chip: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PinError {
+    #[error("Not an input pin; pin={0}")]
+    NotInput(String),
+}
@@ -0,0 +1,14 @@
+mod builtin;
+pub use builtin::*;
+
+mod chip;
+pub use chip::*;
+
+mod library;
+pub use library::*;
+
+mod parser;
+pub use parser::*;
+
+mod script;
+pub use script::*;
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::{builtin, parse_hdl, BuiltinDef, ChipDef, LoadError};
+
+/// Resolves chip names to HDL definitions or built-in chips.
+///
+/// Like the reference HardwareSimulator, a chip is loaded from `<Name>.hdl`
+/// in the first search directory containing it and otherwise falls back to
+/// the built-in implementation.
+pub struct Library {
+    dirs: Vec<PathBuf>,
+    cache: HashMap<String, Option<Rc<ChipDef>>>,
+}
+
+/// A resolved chip.
+#[derive(Clone)]
+pub enum Resolved {
+    Hdl(Rc<ChipDef>),
+    Builtin(&'static BuiltinDef),
+}
+
+impl Library {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Library { dirs, cache: HashMap::new() }
+    }
+
+    /// Parses a chip definition from a file.
+    pub fn parse_file(path: &Path) -> Result<ChipDef, LoadError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| LoadError::Io { path: path.to_owned(), err })?;
+
+        parse_hdl(&source).map_err(|err| LoadError::Parse { path: path.to_owned(), err })
+    }
+
+    /// Resolves a chip by name, returning `None` if it is not defined anywhere.
+    pub fn resolve(&mut self, name: &str) -> Result<Option<Resolved>, LoadError> {
+        if !self.cache.contains_key(name) {
+            let path = self
+                .dirs
+                .iter()
+                .map(|dir| dir.join(format!("{name}.hdl")))
+                .find(|path| path.is_file());
+            let def = match path {
+                Some(path) => Some(Rc::new(Self::parse_file(&path)?)),
+                None => None,
+            };
+            self.cache.insert(name.to_owned(), def);
+        }
+
+        Ok(match &self.cache[name] {
+            Some(def) => Some(Resolved::Hdl(def.clone())),
+            None => builtin(name).map(Resolved::Builtin),
+        })
+    }
+}
@@ -0,0 +1,60 @@
+mod args;
+
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use clap::Parser;
+    use hdl::Chip;
+
+    // Parse command line args.
+    let args = args::Args::parse();
+
+    // Test scripts drive the simulator themselves.
+    if args.path.extension().is_some_and(|ext| ext == "tst") {
+        return match hdl::run_script(&args.path, &args.lib) {
+            Ok(lines) => {
+                println!("End of script; compared_lines={lines}");
+
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Load the chip.
+    let mut chip = match Chip::load(&args.path, &args.lib) {
+        Ok(chip) => chip,
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Drive the inputs.
+    for (pin, value) in args.set {
+        if let Err(err) = chip.set(&pin, value as u16) {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    }
+
+    // Simulate.
+    chip.eval();
+    for _ in 0..args.cycles {
+        chip.tick();
+        chip.tock();
+    }
+
+    // Print the outputs.
+    for (pin, _) in chip.outputs() {
+        println!("{pin}={}", chip.get(pin).unwrap() as i16);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,404 @@
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use thiserror::Error;
+
+/// The maximum width of a bus.
+pub const MAX_WIDTH: u8 = 16;
+
+/// A parsed `CHIP` definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChipDef {
+    pub name: String,
+    pub inputs: Vec<PinDef>,
+    pub outputs: Vec<PinDef>,
+    pub body: ChipBody,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChipBody {
+    Parts(Vec<PartDef>),
+    /// Implemented by the named built-in chip.
+    Builtin(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinDef {
+    pub name: String,
+    pub width: u8,
+}
+
+/// A part instantiation, e.g. `And (a=x, b=y[0], out=z);`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartDef {
+    pub line: usize,
+    pub chip: String,
+    pub connections: Vec<Connection>,
+}
+
+/// Connects a pin of the part to a signal of the enclosing chip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub pin: PinRef,
+    pub signal: Signal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signal {
+    Pin(PinRef),
+    True,
+    False,
+}
+
+/// A reference to a pin, optionally sliced, e.g. `a`, `a[3]` or `a[0..7]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinRef {
+    pub name: String,
+    /// The inclusive bit range.
+    pub range: Option<(u8, u8)>,
+}
+
+impl std::fmt::Display for PinRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.range {
+            None => write!(f, "{}", self.name),
+            Some((start, end)) if start == end => write!(f, "{}[{start}]", self.name),
+            Some((start, end)) => write!(f, "{}[{start}..{end}]", self.name),
+        }
+    }
+}
+
+/// Parses a `.hdl` file containing a single chip.
+pub fn parse_hdl(source: &str) -> Result<ChipDef, ParseError> {
+    let mut tokens = tokenize(source)?.into_iter().peekable();
+
+    expect_word(&mut tokens, "CHIP")?;
+    let name = identifier(&mut tokens)?;
+    expect(&mut tokens, Token::LeftBrace)?;
+
+    let mut inputs = Vec::default();
+    let mut outputs = Vec::default();
+    let body = loop {
+        match next(&mut tokens, "IN, OUT, PARTS or BUILTIN")? {
+            (_, Token::Word(word)) if word == "IN" => inputs.extend(pin_defs(&mut tokens)?),
+            (_, Token::Word(word)) if word == "OUT" => outputs.extend(pin_defs(&mut tokens)?),
+            (_, Token::Word(word)) if word == "PARTS" => {
+                expect(&mut tokens, Token::Colon)?;
+
+                break ChipBody::Parts(parts(&mut tokens)?);
+            }
+            (_, Token::Word(word)) if word == "BUILTIN" => {
+                let builtin = identifier(&mut tokens)?;
+                expect(&mut tokens, Token::Semicolon)?;
+
+                // Clocked pins are known by the built-in implementation.
+                if matches!(tokens.peek(), Some((_, Token::Word(word))) if word == "CLOCKED") {
+                    tokens.next();
+                    pin_defs(&mut tokens)?;
+                }
+
+                break ChipBody::Builtin(builtin);
+            }
+            (line, token) => {
+                return Err(ParseError::UnexpectedToken {
+                    line,
+                    token: token.to_string(),
+                    expected: "IN, OUT, PARTS or BUILTIN",
+                })
+            }
+        }
+    };
+    expect(&mut tokens, Token::RightBrace)?;
+
+    match tokens.next() {
+        Some((line, token)) => {
+            Err(ParseError::UnexpectedToken { line, token: token.to_string(), expected: "eof" })
+        }
+        None => Ok(ChipDef { name, inputs, outputs, body }),
+    }
+}
+
+type Tokens = Peekable<IntoIter<(usize, Token)>>;
+
+fn pin_defs(tokens: &mut Tokens) -> Result<Vec<PinDef>, ParseError> {
+    let mut pins = Vec::default();
+    loop {
+        let name = identifier(tokens)?;
+        let width = match tokens.next_if(|(_, token)| token == &Token::LeftBracket) {
+            Some((line, _)) => {
+                let width = number(tokens)?;
+                expect(tokens, Token::RightBracket)?;
+                if width == 0 || width > MAX_WIDTH {
+                    return Err(ParseError::Width { line, width });
+                }
+
+                width
+            }
+            None => 1,
+        };
+        pins.push(PinDef { name, width });
+
+        match next(tokens, ", or ;")? {
+            (_, Token::Comma) => continue,
+            (_, Token::Semicolon) => return Ok(pins),
+            (line, token) => {
+                return Err(ParseError::UnexpectedToken {
+                    line,
+                    token: token.to_string(),
+                    expected: ", or ;",
+                })
+            }
+        }
+    }
+}
+
+fn parts(tokens: &mut Tokens) -> Result<Vec<PartDef>, ParseError> {
+    let mut parts = Vec::default();
+    while let Some((line, Token::Word(_))) = tokens.peek() {
+        let line = *line;
+        let chip = identifier(tokens)?;
+        expect(tokens, Token::LeftParen)?;
+
+        let mut connections = Vec::default();
+        loop {
+            let pin = pin_ref(tokens)?;
+            expect(tokens, Token::Equals)?;
+            let signal = match tokens.peek() {
+                Some((_, Token::Word(word))) if word == "true" => {
+                    tokens.next();
+
+                    Signal::True
+                }
+                Some((_, Token::Word(word))) if word == "false" => {
+                    tokens.next();
+
+                    Signal::False
+                }
+                _ => Signal::Pin(pin_ref(tokens)?),
+            };
+            connections.push(Connection { pin, signal });
+
+            match next(tokens, ", or )")? {
+                (_, Token::Comma) => continue,
+                (_, Token::RightParen) => break,
+                (line, token) => {
+                    return Err(ParseError::UnexpectedToken {
+                        line,
+                        token: token.to_string(),
+                        expected: ", or )",
+                    })
+                }
+            }
+        }
+        expect(tokens, Token::Semicolon)?;
+
+        parts.push(PartDef { line, chip, connections });
+    }
+
+    Ok(parts)
+}
+
+fn pin_ref(tokens: &mut Tokens) -> Result<PinRef, ParseError> {
+    let name = identifier(tokens)?;
+    let range = match tokens.next_if(|(_, token)| token == &Token::LeftBracket) {
+        Some((line, _)) => {
+            let start = number(tokens)?;
+            let end = match tokens.next_if(|(_, token)| token == &Token::Range) {
+                Some(_) => number(tokens)?,
+                None => start,
+            };
+            expect(tokens, Token::RightBracket)?;
+            if start > end || end >= MAX_WIDTH {
+                return Err(ParseError::Range { line, start, end });
+            }
+
+            Some((start, end))
+        }
+        None => None,
+    };
+
+    Ok(PinRef { name, range })
+}
+
+fn next(tokens: &mut Tokens, expected: &'static str) -> Result<(usize, Token), ParseError> {
+    tokens.next().ok_or(ParseError::UnexpectedEof { expected })
+}
+
+fn expect(tokens: &mut Tokens, expected: Token) -> Result<(), ParseError> {
+    let symbol = expected.symbol();
+    match next(tokens, symbol)? {
+        (_, token) if token == expected => Ok(()),
+        (line, token) => {
+            Err(ParseError::UnexpectedToken { line, token: token.to_string(), expected: symbol })
+        }
+    }
+}
+
+fn expect_word(tokens: &mut Tokens, expected: &'static str) -> Result<(), ParseError> {
+    match next(tokens, expected)? {
+        (_, Token::Word(word)) if word == expected => Ok(()),
+        (line, token) => {
+            Err(ParseError::UnexpectedToken { line, token: token.to_string(), expected })
+        }
+    }
+}
+
+fn identifier(tokens: &mut Tokens) -> Result<String, ParseError> {
+    match next(tokens, "identifier")? {
+        (_, Token::Word(word)) => Ok(word),
+        (line, token) => Err(ParseError::UnexpectedToken {
+            line,
+            token: token.to_string(),
+            expected: "identifier",
+        }),
+    }
+}
+
+fn number(tokens: &mut Tokens) -> Result<u8, ParseError> {
+    match next(tokens, "number")? {
+        (line, Token::Number(number)) => {
+            u8::try_from(number).map_err(|_| ParseError::Range { line, start: 0, end: u8::MAX })
+        }
+        (line, token) => {
+            Err(ParseError::UnexpectedToken { line, token: token.to_string(), expected: "number" })
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, strum::Display)]
+enum Token {
+    #[strum(to_string = "{0}")]
+    Word(String),
+    #[strum(to_string = "{0}")]
+    Number(u32),
+    #[strum(to_string = "{{")]
+    LeftBrace,
+    #[strum(to_string = "}}")]
+    RightBrace,
+    #[strum(to_string = "(")]
+    LeftParen,
+    #[strum(to_string = ")")]
+    RightParen,
+    #[strum(to_string = "[")]
+    LeftBracket,
+    #[strum(to_string = "]")]
+    RightBracket,
+    #[strum(to_string = ",")]
+    Comma,
+    #[strum(to_string = ";")]
+    Semicolon,
+    #[strum(to_string = ":")]
+    Colon,
+    #[strum(to_string = "=")]
+    Equals,
+    #[strum(to_string = "..")]
+    Range,
+}
+
+impl Token {
+    fn symbol(&self) -> &'static str {
+        match self {
+            Token::Word(_) => "identifier",
+            Token::Number(_) => "number",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::LeftParen => "(",
+            Token::RightParen => ")",
+            Token::LeftBracket => "[",
+            Token::RightBracket => "]",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Equals => "=",
+            Token::Range => "..",
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::default();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(char) = chars.next() {
+        let token = match char {
+            '\n' => {
+                line += 1;
+                continue;
+            }
+            char if char.is_whitespace() => continue,
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|char| char != &'\n').is_some() {}
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                let start = line;
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(char) => {
+                            line += usize::from(char == '\n');
+                            prev = char;
+                        }
+                        None => return Err(ParseError::UnclosedComment { line: start }),
+                    }
+                }
+                continue;
+            }
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            ':' => Token::Colon,
+            '=' => Token::Equals,
+            '.' if chars.next_if_eq(&'.').is_some() => Token::Range,
+            char if char.is_ascii_digit() => {
+                let mut number = String::from(char);
+                while let Some(char) = chars.next_if(char::is_ascii_digit) {
+                    number.push(char);
+                }
+
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| ParseError::InvalidChar { line, char })?,
+                )
+            }
+            char if char.is_ascii_alphabetic() || char == '_' => {
+                let mut word = String::from(char);
+                while let Some(char) =
+                    chars.next_if(|char| char.is_ascii_alphanumeric() || *char == '_')
+                {
+                    word.push(char);
+                }
+
+                Token::Word(word)
+            }
+            char => return Err(ParseError::InvalidChar { line, char }),
+        };
+        tokens.push((line, token));
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseError {
+    #[error("Unexpected token; line={line}; token={token}; expected={expected}")]
+    UnexpectedToken { line: usize, token: String, expected: &'static str },
+    #[error("Unexpected eof; expected={expected}")]
+    UnexpectedEof { expected: &'static str },
+    #[error("Unclosed comment; line={line}")]
+    UnclosedComment { line: usize },
+    #[error("Invalid character; line={line}; char={char}")]
+    InvalidChar { line: usize, char: char },
+    #[error("Invalid bus width; line={line}; width={width}")]
+    Width { line: usize, width: u8 },
+    #[error("Invalid bus range; line={line}; start={start}; end={end}")]
+    Range { line: usize, start: u8, end: u8 },
+}
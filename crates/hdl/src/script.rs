@@ -0,0 +1,191 @@
+use std::path::{Path, PathBuf};
+
+use shared::tst::{self, Command, OutputColumn, OutputFormat, Simulator, TestError};
+use thiserror::Error;
+
+use crate::{Chip, LoadError, PinError};
+
+/// Runs a HardwareSimulator test script, returning the number of output lines
+/// that matched the compare file.
+///
+/// Parts are resolved from the script's directory, then `dirs`, then the
+/// built-in chips.
+pub fn run_script(path: &Path, dirs: &[PathBuf]) -> Result<usize, TestError<HdlTestError>> {
+    tst::run_script(path, &mut HdlSimulator::new(dirs.to_vec()))
+}
+
+/// Exposes a chip to test scripts via its pins, `time` and the state of its
+/// built-in parts, e.g. `DRegister[]` or `RAM16K[3]`.
+pub struct HdlSimulator {
+    dirs: Vec<PathBuf>,
+    chip: Option<Chip>,
+    time: u64,
+    /// Whether the clock is high, i.e. a `tick` without the matching `tock`.
+    high: bool,
+}
+
+impl HdlSimulator {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        HdlSimulator { dirs, chip: None, time: 0, high: false }
+    }
+
+    fn chip(&self) -> Result<&Chip, HdlTestError> {
+        self.chip.as_ref().ok_or(HdlTestError::NoChip)
+    }
+
+    fn chip_mut(&mut self) -> Result<&mut Chip, HdlTestError> {
+        self.chip.as_mut().ok_or(HdlTestError::NoChip)
+    }
+}
+
+impl Simulator for HdlSimulator {
+    type Error = HdlTestError;
+
+    fn load(&mut self, dir: &Path, file: Option<&str>) -> Result<(), Self::Error> {
+        let file = file.ok_or_else(|| HdlTestError::Unsupported("load".to_owned()))?;
+        self.chip = Some(Chip::load(&dir.join(file), &self.dirs)?);
+        self.time = 0;
+        self.high = false;
+
+        Ok(())
+    }
+
+    fn load_part(&mut self, part: &str, path: &Path) -> Result<(), Self::Error> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| HdlTestError::Io { path: path.to_owned(), err })?;
+        let words = source
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(number, line)| {
+                match line.len() {
+                    16 => u16::from_str_radix(line, 2).ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| HdlTestError::Word { line: number + 1, word: line.to_owned() })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Replace the part's contents, clearing any previous program.
+        let primitive = self
+            .chip_mut()?
+            .part_mut(part)
+            .ok_or_else(|| HdlTestError::UnknownVariable(part.to_owned()))?;
+        let mut index = 0;
+        while primitive.set_state(index, words.get(index).copied().unwrap_or(0)) {
+            index += 1;
+        }
+        if words.len() > index {
+            return Err(HdlTestError::TooLarge { part: part.to_owned(), words: words.len() });
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, variable: &str) -> Result<i64, Self::Error> {
+        if variable == "time" {
+            return Ok(self.time as i64);
+        }
+
+        let chip = self.chip()?;
+        if let (Some(value), Some(width)) = (chip.get(variable), chip.width(variable)) {
+            // Full width buses are shown as signed values.
+            return Ok(match width {
+                16 => i64::from(value as i16),
+                _ => i64::from(value),
+            });
+        }
+
+        let (part, index) = part_state(variable)?;
+        chip.part(part)
+            .and_then(|primitive| primitive.state(index))
+            .map(|value| i64::from(value as i16))
+            .ok_or_else(|| HdlTestError::UnknownVariable(variable.to_owned()))
+    }
+
+    fn set(&mut self, variable: &str, value: i64) -> Result<(), Self::Error> {
+        let chip = self.chip_mut()?;
+        if chip.inputs().iter().any(|(pin, _)| pin == variable) {
+            return Ok(chip.set(variable, value as u16)?);
+        }
+
+        let (part, index) = part_state(variable)?;
+        match chip
+            .part_mut(part)
+            .map(|primitive| primitive.set_state(index, value as u16))
+        {
+            Some(true) => Ok(()),
+            _ => Err(HdlTestError::UnknownVariable(variable.to_owned())),
+        }
+    }
+
+    fn step(&mut self, command: &Command) -> Result<(), Self::Error> {
+        let chip = self.chip.as_mut().ok_or(HdlTestError::NoChip)?;
+        match command {
+            Command::Eval => chip.eval(),
+            Command::Tick => {
+                chip.tick();
+                self.high = true;
+            }
+            Command::Tock => {
+                chip.tock();
+                self.time += 1;
+                self.high = false;
+            }
+            Command::TickTock => {
+                chip.tick();
+                chip.tock();
+                self.time += 1;
+                self.high = false;
+            }
+            _ => return Err(HdlTestError::Unsupported(format!("{command:?}"))),
+        }
+
+        Ok(())
+    }
+
+    fn format(&self, column: &OutputColumn) -> Result<String, Self::Error> {
+        match (column.variable.as_str(), column.format) {
+            // The clock is shown as `3+` between a tick and its tock.
+            ("time", OutputFormat::String) => {
+                let suffix = if self.high { "+" } else { "" };
+
+                Ok(column.string(&format!("{}{suffix}", self.time)))
+            }
+            _ => Ok(column.value(self.get(&column.variable)?)),
+        }
+    }
+}
+
+/// Parses `Part[n]` into the part & state index, `Part[]` is index 0.
+fn part_state(variable: &str) -> Result<(&str, usize), HdlTestError> {
+    variable
+        .strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+        .and_then(|(part, index)| match index {
+            "" => Some((part, 0)),
+            index => Some((part, index.parse().ok()?)),
+        })
+        .ok_or_else(|| HdlTestError::UnknownVariable(variable.to_owned()))
+}
+
+#[derive(Debug, Error)]
+pub enum HdlTestError {
+    #[error("Failed to load chip; err={0}")]
+    Load(#[from] LoadError),
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Invalid word; line={line}; word={word}")]
+    Word { line: usize, word: String },
+    #[error("Program does not fit in part; part={part}; words={words}")]
+    TooLarge { part: String, words: usize },
+    #[error("Invalid pin; err={0}")]
+    Pin(#[from] PinError),
+    #[error("No chip loaded")]
+    NoChip,
+    #[error("Unknown variable; variable={0}")]
+    UnknownVariable(String),
+    #[error("Unsupported command; command={0}")]
+    Unsupported(String),
+}
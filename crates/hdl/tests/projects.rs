@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+/// Scripts that need interaction with the reference simulator's GUI.
+const INTERACTIVE: &[&str] = &["Memory.tst"];
+
+/// Runs every chip test script in `projects/01`–`05` against a scratch copy of
+/// its directory, so the generated `.out` files stay out of the tree.
+#[test]
+fn project_scripts() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects");
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("projects");
+
+    let mut failures = Vec::default();
+    for dir in ["01", "02", "03/a", "03/b", "05"] {
+        let copy = scratch.join(dir);
+        std::fs::create_dir_all(&copy).unwrap();
+        let mut scripts: Vec<PathBuf> = Vec::default();
+        for entry in std::fs::read_dir(projects.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path.file_name().unwrap();
+            std::fs::copy(&path, copy.join(name)).unwrap();
+
+            if path.extension().is_some_and(|ext| ext == "tst")
+                && !INTERACTIVE.iter().any(|script| name == *script)
+            {
+                scripts.push(copy.join(name));
+            }
+        }
+        scripts.sort();
+
+        for script in scripts {
+            if let Err(err) = hdl::run_script(&script, &[]) {
+                failures.push(format!("{}: {err}", script.display()));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
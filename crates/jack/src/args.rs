@@ -5,6 +5,8 @@ use clap::builder::{PossibleValuesParser, TypedValueParser};
 use clap::Parser;
 use strum::{EnumString, VariantNames};
 
+use crate::type_check::TypeCheck;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -17,6 +19,32 @@ pub(crate) struct Args {
     pub(crate) action: Action,
     /// Either a single Jack file or a directory containing Jack files.
     pub(crate) path: PathBuf,
+    /// Where compiled VM code is written, `-` for stdout. Defaults to the `.vm`
+    /// file next to each source file; for a directory this names the
+    /// directory the `.vm` files are written to.
+    #[arg(short, long)]
+    pub(crate) output: Option<PathBuf>,
+    /// Whether type errors are ignored, reported as warnings, or fail the
+    /// compilation.
+    #[arg(
+        long,
+        default_value = "warn",
+        value_parser = PossibleValuesParser::new(TypeCheck::VARIANTS)
+            .map(|s| TypeCheck::from_str(&s).unwrap())
+    )]
+    pub(crate) type_check: TypeCheck,
+    /// The format parse trees are printed in.
+    #[arg(
+        long,
+        default_value = "debug",
+        value_parser = PossibleValuesParser::new(Format::VARIANTS)
+            .map(|s| Format::from_str(&s).unwrap())
+    )]
+    pub(crate) format: Format,
+    /// Compare the tokens or parse tree of each file against the reference
+    /// `{name}T.xml` or `{name}.xml` next to it, ignoring whitespace.
+    #[arg(long)]
+    pub(crate) compare: bool,
 }
 
 #[derive(Debug, Clone, EnumString, strum::VariantNames)]
@@ -26,3 +54,10 @@ pub(crate) enum Action {
     Parse,
     Compile,
 }
+
+#[derive(Debug, Clone, Copy, EnumString, strum::VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum Format {
+    Debug,
+    Xml,
+}
@@ -1,42 +1,41 @@
+use std::cell::RefCell;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use hashbrown::hash_map::Entry;
 use hashbrown::HashMap;
 use thiserror::Error;
 
-use crate::parser::structure::{Class, FieldModifier, Type};
+use crate::parser::structure::{Class, FieldModifier, ReturnType, SubroutineType, Type};
+use crate::tokenizer::Span;
 
+/// Compiles `class` to VM code, appending any warnings (e.g. calls into
+/// classes outside the program) to `warnings`.
 pub(crate) fn compile<'a>(
     vm_symbol_counter: &'static AtomicU64,
+    program: &'a ProgramContext<'a>,
     class: &Class<'a>,
+    warnings: &mut Vec<CompileError<'static>>,
 ) -> Result<Vec<String>, CompileError<'a>> {
     let mut code = Vec::default();
-
-    // Setup the class context before compiling methods.
-    let mut indexes = Indices::default();
-    let mut symbols = HashMap::default();
-    for variable in &class.variables {
-        match symbols.entry(variable.name) {
-            Entry::Occupied(_) => return Err(CompileError::DuplicateSymbol(variable.name)),
-            Entry::Vacant(entry) => {
-                let (category, index) = match variable.modifier {
-                    FieldModifier::Field => (SymbolLocation::This, indexes.next_field()),
-                    FieldModifier::Static => (SymbolLocation::Static, indexes.next_static()),
-                };
-
-                entry.insert(SymbolEntry {
-                    symbol_type: variable.var_type,
-                    location: category,
-                    index,
-                })
-            }
-        };
-    }
-    let context = ClassContext { name: class.name, symbols, vm_symbols: vm_symbol_counter };
+    let context = ClassContext::new(vm_symbol_counter, program, class)?;
 
     // Generate the code for each subroutine in the class.
     for subroutine in &class.subroutines {
-        code.extend(subroutine.compile(&context)?);
+        let name = format!("{}.{}", class.name, subroutine.name);
+        let res = subroutine.compile(&context);
+        warnings.extend(
+            context
+                .warnings
+                .take()
+                .into_iter()
+                .map(|err| CompileError::Subroutine {
+                    subroutine: name.clone(),
+                    err: Box::new(err),
+                }),
+        );
+        code.extend(
+            res.map_err(|err| CompileError::Subroutine { subroutine: name, err: Box::new(err) })?,
+        );
     }
 
     Ok(code)
@@ -44,12 +43,191 @@ pub(crate) fn compile<'a>(
 
 #[derive(Debug, Error)]
 pub(crate) enum CompileError<'a> {
-    #[error("Duplicate symbol; symbol={0}")]
-    DuplicateSymbol(&'a str),
-    #[error("Invalid callee; callee={0}")]
-    InvalidCallee(&'a str),
-    #[error("Unknown symbol; symbol={0}")]
-    UnknownSymbol(&'a str),
+    #[error("Duplicate symbol; symbol={symbol}")]
+    DuplicateSymbol { symbol: &'a str, span: Span },
+    #[error("Invalid callee; callee={callee}")]
+    InvalidCallee { callee: &'a str, span: Span },
+    #[error("Unknown symbol; symbol={symbol}")]
+    UnknownSymbol { symbol: &'a str, span: Span },
+    #[error("Duplicate class; class={class}")]
+    DuplicateClass { class: &'a str, span: Span },
+    #[error("Unknown class; class={class}")]
+    UnknownClass { class: String, span: Span },
+    #[error("Unknown subroutine; subroutine={subroutine}")]
+    UnknownSubroutine { subroutine: String, span: Span },
+    #[error("Method called without an object; subroutine={subroutine}")]
+    MethodWithoutObject { subroutine: String, span: Span },
+    #[error("Function called on an object; subroutine={subroutine}")]
+    NotAMethod { subroutine: String, span: Span },
+    #[error(
+        "Invalid argument count; subroutine={subroutine}; expected={expected}; actual={actual}"
+    )]
+    ArgumentCount { subroutine: String, expected: usize, actual: usize, span: Span },
+    #[error(
+        "Mismatched assignment type; variable={variable}; expected={expected}; actual={actual}"
+    )]
+    AssignmentType { variable: String, expected: String, actual: String, span: Span },
+    #[error("Non-boolean condition; actual={actual}")]
+    ConditionType { actual: String, span: Span },
+    #[error("Mismatched return type; expected={expected}; actual={actual}")]
+    ReturnType { expected: String, actual: String, span: Span },
+    #[error("Value returned from void subroutine")]
+    VoidReturn { span: Span },
+    #[error("Missing return value; expected={expected}")]
+    MissingReturnValue { expected: String, span: Span },
+    #[error(
+        "Mismatched argument type; subroutine={subroutine}; argument={argument}; \
+         expected={expected}; actual={actual}"
+    )]
+    ArgumentType {
+        subroutine: String,
+        argument: usize,
+        expected: String,
+        actual: String,
+        span: Span,
+    },
+    #[error("Void subroutine used as a value; subroutine={subroutine}")]
+    VoidValue { subroutine: String, span: Span },
+    #[error("{err}; in={subroutine}")]
+    Subroutine { subroutine: String, err: Box<CompileError<'a>> },
+}
+
+impl CompileError<'_> {
+    /// The offending region of source.
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Self::DuplicateSymbol { span, .. }
+            | Self::InvalidCallee { span, .. }
+            | Self::UnknownSymbol { span, .. }
+            | Self::DuplicateClass { span, .. }
+            | Self::UnknownClass { span, .. }
+            | Self::UnknownSubroutine { span, .. }
+            | Self::MethodWithoutObject { span, .. }
+            | Self::NotAMethod { span, .. }
+            | Self::ArgumentCount { span, .. }
+            | Self::AssignmentType { span, .. }
+            | Self::ConditionType { span, .. }
+            | Self::ReturnType { span, .. }
+            | Self::VoidReturn { span }
+            | Self::MissingReturnValue { span, .. }
+            | Self::ArgumentType { span, .. }
+            | Self::VoidValue { span, .. } => *span,
+            Self::Subroutine { err, .. } => err.span(),
+        }
+    }
+
+    pub(crate) fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::DuplicateSymbol { .. } => Some("rename one of the declarations"),
+            Self::InvalidCallee { .. } => Some("only variables of a class type have methods"),
+            Self::UnknownSymbol { .. } => {
+                Some("declare the variable with `var`, `field` or `static`")
+            }
+            Self::DuplicateClass { .. } => Some("each class must be defined in exactly one file"),
+            Self::UnknownClass { .. } => Some(
+                "compile the directory containing the class to check the call, it is assumed to \
+                 be compiled separately",
+            ),
+            Self::MethodWithoutObject { .. } => {
+                Some("call the method on an object, e.g. `object.method()`")
+            }
+            Self::NotAMethod { .. } => {
+                Some("call the function on its class, e.g. `Class.function()`")
+            }
+            Self::VoidReturn { .. } => Some("use `return;` in void subroutines"),
+            Self::MissingReturnValue { .. } => Some("only void subroutines may use `return;`"),
+            Self::VoidValue { .. } => Some("call void subroutines with `do`"),
+            Self::UnknownSubroutine { .. }
+            | Self::ArgumentCount { .. }
+            | Self::AssignmentType { .. }
+            | Self::ConditionType { .. }
+            | Self::ReturnType { .. }
+            | Self::ArgumentType { .. } => None,
+            Self::Subroutine { err, .. } => err.help(),
+        }
+    }
+}
+
+/// The subroutine signatures of every class in the program, including the OS.
+pub(crate) struct ProgramContext<'a> {
+    classes: HashMap<&'a str, HashMap<&'a str, SubroutineSignature<'a>>>,
+}
+
+impl<'a> ProgramContext<'a> {
+    pub(crate) fn new(classes: &[Class<'a>]) -> Result<Self, CompileError<'a>> {
+        // Program classes replace the OS classes they implement.
+        let mut program = ProgramContext { classes: HashMap::default() };
+        for class in crate::os::classes() {
+            program
+                .classes
+                .insert(class.name, Self::signatures(&class)?);
+        }
+        let mut defined = HashMap::new();
+        for class in classes {
+            if defined.insert(class.name, ()).is_some() {
+                return Err(CompileError::DuplicateClass { class: class.name, span: class.span });
+            }
+            program.classes.insert(class.name, Self::signatures(class)?);
+        }
+
+        Ok(program)
+    }
+
+    fn signatures(
+        class: &Class<'a>,
+    ) -> Result<HashMap<&'a str, SubroutineSignature<'a>>, CompileError<'a>> {
+        let mut signatures = HashMap::default();
+        for subroutine in &class.subroutines {
+            let signature = SubroutineSignature {
+                subroutine_type: subroutine.subroutine_type,
+                return_type: subroutine.return_type,
+                parameters: subroutine
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.parameter_type)
+                    .collect(),
+            };
+            if signatures.insert(subroutine.name, signature).is_some() {
+                return Err(CompileError::DuplicateSymbol {
+                    symbol: subroutine.name,
+                    span: subroutine.span,
+                });
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    /// Looks up `class.subroutine`, erroring at `span` if the subroutine is not
+    /// defined by its class.
+    ///
+    /// Returns `None` for classes outside the program, which may be compiled
+    /// separately (e.g. the OS is compiled apart from the `Main` calling it).
+    pub(crate) fn signature(
+        &self,
+        class: &str,
+        subroutine: &str,
+        span: Span,
+    ) -> Result<Option<&SubroutineSignature<'a>>, CompileError<'static>> {
+        let Some(signatures) = self.classes.get(class) else {
+            return Ok(None);
+        };
+
+        signatures
+            .get(subroutine)
+            .map(Some)
+            .ok_or_else(|| CompileError::UnknownSubroutine {
+                subroutine: format!("{class}.{subroutine}"),
+                span,
+            })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SubroutineSignature<'a> {
+    pub(crate) subroutine_type: SubroutineType,
+    pub(crate) return_type: ReturnType<'a>,
+    pub(crate) parameters: Vec<Type<'a>>,
 }
 
 #[derive(Debug, Default)]
@@ -77,15 +255,66 @@ impl Indices {
 pub(crate) struct ClassContext<'a> {
     pub(crate) name: &'a str,
     pub(crate) symbols: HashMap<&'a str, SymbolEntry<'a>>,
+    pub(crate) program: &'a ProgramContext<'a>,
     pub(crate) vm_symbols: &'static AtomicU64,
+    /// Warnings raised while compiling the current subroutine.
+    pub(crate) warnings: RefCell<Vec<CompileError<'static>>>,
 }
 
 impl<'a> ClassContext<'a> {
+    /// Builds the class level symbol table.
+    pub(crate) fn new(
+        vm_symbols: &'static AtomicU64,
+        program: &'a ProgramContext<'a>,
+        class: &Class<'a>,
+    ) -> Result<Self, CompileError<'a>> {
+        let mut indexes = Indices::default();
+        let mut symbols = HashMap::default();
+        let variables = class
+            .variables
+            .iter()
+            .flat_map(|variable| variable.names.iter().map(move |name| (variable, name)));
+        for (variable, &(name, span)) in variables {
+            match symbols.entry(name) {
+                Entry::Occupied(_) => {
+                    return Err(CompileError::DuplicateSymbol { symbol: name, span })
+                }
+                Entry::Vacant(entry) => {
+                    let (category, index) = match variable.modifier {
+                        FieldModifier::Field => (SymbolLocation::This, indexes.next_field()),
+                        FieldModifier::Static => (SymbolLocation::Static, indexes.next_static()),
+                    };
+
+                    entry.insert(SymbolEntry {
+                        symbol_type: variable.var_type,
+                        location: category,
+                        index,
+                    })
+                }
+            };
+        }
+
+        Ok(ClassContext {
+            name: class.name,
+            symbols,
+            program,
+            vm_symbols,
+            warnings: RefCell::default(),
+        })
+    }
+
     pub(crate) fn next_label(&self) -> u64 {
         self.vm_symbols.fetch_add(1, Ordering::Relaxed)
     }
 }
 
+/// The symbols of the subroutine being compiled.
+pub(crate) struct SubroutineContext<'a> {
+    pub(crate) subroutine_type: SubroutineType,
+    pub(crate) return_type: ReturnType<'a>,
+    pub(crate) symbols: HashMap<&'a str, SymbolEntry<'a>>,
+}
+
 pub(crate) struct SymbolEntry<'a> {
     pub(crate) symbol_type: Type<'a>,
     pub(crate) location: SymbolLocation,
@@ -110,3 +339,123 @@ pub(crate) enum SymbolLocation {
     Local,
     Argument,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::Tokenizer;
+
+    /// Compiles `class Main { subroutines }`, returning the error or warnings &
+    /// the source each points at.
+    fn compile(subroutines: &str) -> Result<Vec<(String, String)>, (String, String)> {
+        static LABELS: AtomicU64 = AtomicU64::new(0);
+
+        let source = format!("class Main {{ {subroutines} }}");
+        let classes = [Class::parse(&mut Tokenizer::new(&source), &mut Vec::default()).unwrap()];
+        let program = ProgramContext::new(&classes).unwrap();
+        let describe = |err: &CompileError| {
+            let err = match err {
+                CompileError::Subroutine { err, .. } => err,
+                err => err,
+            };
+            (err.to_string(), source[err.span().start..err.span().end].to_owned())
+        };
+
+        let mut warnings = Vec::default();
+        super::compile(&LABELS, &program, &classes[0], &mut warnings)
+            .map(|_| warnings.iter().map(describe).collect())
+            .map_err(|err| describe(&err))
+    }
+
+    fn error(message: &str, source: &str) -> Result<Vec<(String, String)>, (String, String)> {
+        Err((message.to_owned(), source.to_owned()))
+    }
+
+    #[test]
+    fn unknown_class() {
+        let subroutines = "
+            function void main() {
+                var Foo foo;
+                do Foo.bar();
+                do foo.baz(1);
+                return;
+            }
+        ";
+
+        // Classes outside the program are assumed to be compiled separately.
+        let warnings = ["Foo.bar", "foo.baz"]
+            .map(|callee| ("Unknown class; class=Foo".to_owned(), callee.to_owned()));
+        assert_eq!(compile(subroutines), Ok(warnings.to_vec()));
+    }
+
+    #[test]
+    fn unknown_subroutine() {
+        let subroutines = "function void main() { do Main.missing(); return; }";
+        assert_eq!(
+            compile(subroutines),
+            error("Unknown subroutine; subroutine=Main.missing", "Main.missing")
+        );
+
+        let subroutines = "function void main() { do Output.missing(); return; }";
+        assert_eq!(
+            compile(subroutines),
+            error("Unknown subroutine; subroutine=Output.missing", "Output.missing")
+        );
+    }
+
+    #[test]
+    fn argument_count() {
+        let subroutines = "
+            function void main() { do Main.f(1); return; }
+            function void f() { return; }
+        ";
+        assert_eq!(
+            compile(subroutines),
+            error("Invalid argument count; subroutine=Main.f; expected=0; actual=1", "Main.f")
+        );
+
+        let subroutines = "function void main() { do Math.sqrt(1, 2); return; }";
+        assert_eq!(
+            compile(subroutines),
+            error(
+                "Invalid argument count; subroutine=Math.sqrt; expected=1; actual=2",
+                "Math.sqrt"
+            )
+        );
+    }
+
+    #[test]
+    fn method_without_object() {
+        let subroutines = "
+            function void main() { do Main.m(); return; }
+            method void m() { return; }
+        ";
+        assert_eq!(
+            compile(subroutines),
+            error("Method called without an object; subroutine=Main.m", "Main.m")
+        );
+
+        // Functions have no `this` to call the method on.
+        let subroutines = "
+            function void main() { do m(); return; }
+            method void m() { return; }
+        ";
+        assert_eq!(
+            compile(subroutines),
+            error("Method called without an object; subroutine=Main.m", "m")
+        );
+    }
+
+    #[test]
+    fn not_a_method() {
+        let subroutines = "
+            function void main() { var Main main; do main.f(); return; }
+            function void f() { return; }
+        ";
+
+        assert_eq!(
+            compile(subroutines),
+            error("Function called on an object; subroutine=Main.f", "main.f")
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// Compares `actual` against the reference file at `path` line by line,
+/// ignoring all whitespace & blank lines, returning the number of lines
+/// compared.
+pub(crate) fn compare(path: &Path, actual: &str) -> Result<usize, CompareError> {
+    let expected = std::fs::read_to_string(path)
+        .map_err(|err| CompareError::Io { path: path.to_owned(), err })?;
+
+    let mut expected = lines(&expected);
+    let mut actual = lines(actual).map(|(_, line)| line);
+    let mut compared = 0;
+    loop {
+        match (expected.next(), actual.next()) {
+            (None, None) => return Ok(compared),
+            (Some((line, expected)), actual) if Some(&expected) != actual.as_ref() => {
+                return Err(CompareError::Mismatch {
+                    line,
+                    expected,
+                    actual: actual.unwrap_or_default(),
+                })
+            }
+            (Some(_), _) => compared += 1,
+            (None, Some(actual)) => return Err(CompareError::Trailing { actual }),
+        }
+    }
+}
+
+/// The non-blank lines of `text` with their whitespace removed, alongside
+/// their line numbers.
+fn lines(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.split_whitespace().collect::<String>()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum CompareError {
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Comparison failure; line={line}\nexpected: {expected}\n  actual: {actual}")]
+    Mismatch { line: usize, expected: String, actual: String },
+    #[error("Comparison failure; unexpected trailing output\n  actual: {actual}")]
+    Trailing { actual: String },
+}
@@ -0,0 +1,240 @@
+use std::fmt::{Display, Write};
+use std::path::Path;
+
+use crate::tokenizer::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Error,
+    Warning,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Renders a rustc style diagnostic, underlining `span` within `source`.
+///
+/// A `span` of `None` points at the end of the file.
+pub(crate) fn render(
+    level: Level,
+    path: &Path,
+    source: &str,
+    message: impl Display,
+    span: Option<Span>,
+    help: Option<&str>,
+) -> String {
+    let span = span.unwrap_or_else(|| eof(source));
+
+    // Extract the first line of the span, multi-line spans are only underlined
+    // up to the end of their first line.
+    let line_start = span.start - (span.column - 1);
+    let line_end = source[line_start..]
+        .find('\n')
+        .map_or(source.len(), |end| line_start + end);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+    let carets = source
+        .get(span.start..span.end.min(line_start + line.len()))
+        .map_or(0, |underlined| underlined.chars().count())
+        .max(1);
+
+    // Preserve tabs so the carets line up with the source line.
+    let padding: String = source[line_start..span.start]
+        .chars()
+        .map(|char| if char == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    let number = span.line.to_string();
+    let gutter = " ".repeat(number.len());
+    let mut out = String::default();
+    writeln!(out, "{level}: {message}").unwrap();
+    writeln!(out, "{gutter}--> {}:{}:{}", path.display(), span.line, span.column).unwrap();
+    writeln!(out, "{gutter} |").unwrap();
+    writeln!(out, "{number} | {line}").unwrap();
+    writeln!(out, "{gutter} | {padding}{}", "^".repeat(carets)).unwrap();
+    if let Some(help) = help {
+        writeln!(out, "{gutter} |").unwrap();
+        writeln!(out, "{gutter} = help: {help}").unwrap();
+    }
+
+    out
+}
+
+/// The span just past the last non-whitespace character of `source`.
+fn eof(source: &str) -> Span {
+    let end = source.trim_end().len();
+    let line_start = source[..end].rfind('\n').map_or(0, |newline| newline + 1);
+
+    Span {
+        start: end,
+        end: end + 1,
+        line: source[..end].matches('\n').count() + 1,
+        column: end - line_start + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The span of the first `needle` in `source`.
+    fn span(source: &str, needle: &str) -> Option<Span> {
+        let start = source.find(needle).unwrap();
+        let line_start = source[..start].rfind('\n').map_or(0, |newline| newline + 1);
+
+        Some(Span {
+            start,
+            end: start + needle.len(),
+            line: source[..start].matches('\n').count() + 1,
+            column: start - line_start + 1,
+        })
+    }
+
+    fn render(source: &str, span: Option<Span>, help: Option<&str>) -> String {
+        super::render(Level::Error, Path::new("Main.jack"), source, "Oops", span, help)
+    }
+
+    #[test]
+    fn line_and_column() {
+        let source = "class Main {\n    let x = y;\n}\n";
+
+        assert_eq!(
+            render(source, span(source, "y"), None),
+            "\
+error: Oops
+ --> Main.jack:2:13
+  |
+2 |     let x = y;
+  |             ^
+"
+        );
+    }
+
+    #[test]
+    fn caret_width() {
+        let source = "let value = 32768;\n";
+
+        assert_eq!(
+            render(source, span(source, "32768"), None),
+            "\
+error: Oops
+ --> Main.jack:1:13
+  |
+1 | let value = 32768;
+  |             ^^^^^
+"
+        );
+    }
+
+    #[test]
+    fn help() {
+        let source = "let x = 1\n";
+
+        assert_eq!(
+            super::render(
+                Level::Warning,
+                Path::new("Main.jack"),
+                source,
+                "Oops",
+                span(source, "1"),
+                Some("add a `;`")
+            ),
+            "\
+warning: Oops
+ --> Main.jack:1:9
+  |
+1 | let x = 1
+  |         ^
+  |
+  = help: add a `;`
+"
+        );
+    }
+
+    #[test]
+    fn multi_digit_gutter() {
+        let source = format!("{}let x = y;\n", "\n".repeat(99));
+
+        assert_eq!(
+            render(&source, span(&source, "y"), None),
+            "\
+error: Oops
+   --> Main.jack:100:9
+    |
+100 | let x = y;
+    |         ^
+"
+        );
+    }
+
+    #[test]
+    fn tabs() {
+        let source = "\tlet x =\ty;\n";
+
+        assert_eq!(
+            render(source, span(source, "y"), None),
+            "\
+error: Oops
+ --> Main.jack:1:10
+  |
+1 | \tlet x =\ty;
+  | \t       \t^
+"
+        );
+    }
+
+    #[test]
+    fn multi_byte_characters() {
+        // Carets count characters, not bytes.
+        let source = "let s = \"é\u{e8}\";\n";
+
+        assert_eq!(
+            render(source, span(source, "é\u{e8}"), None),
+            "\
+error: Oops
+ --> Main.jack:1:10
+  |
+1 | let s = \"é\u{e8}\";
+  |          ^^
+"
+        );
+    }
+
+    #[test]
+    fn multi_line_span() {
+        // Only the first line is underlined.
+        let source = "let s = \"abc\ndef\";\n";
+
+        assert_eq!(
+            render(source, span(source, "\"abc\ndef\""), None),
+            "\
+error: Oops
+ --> Main.jack:1:9
+  |
+1 | let s = \"abc
+  |         ^^^^
+"
+        );
+    }
+
+    #[test]
+    fn end_of_file() {
+        let source = "class Main {\n    function void main() {\n\n";
+
+        assert_eq!(
+            render(source, None, None),
+            "\
+error: Oops
+ --> Main.jack:2:27
+  |
+2 |     function void main() {
+  |                           ^
+"
+        );
+    }
+}
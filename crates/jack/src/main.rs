@@ -2,82 +2,338 @@ use std::sync::atomic::AtomicU64;
 
 mod args;
 mod code_gen;
+mod compare;
+mod diagnostic;
+mod os;
 mod parser;
 mod tokenizer;
+mod type_check;
 
 fn main() -> std::process::ExitCode {
-    use std::fmt::Display;
     use std::io::{BufWriter, Write};
+    use std::path::{Path, PathBuf};
     use std::process::ExitCode;
 
     use clap::Parser as _;
 
-    use crate::args::Action;
+    use crate::args::{Action, Format};
+    use crate::code_gen::{CompileError, ProgramContext};
+    use crate::diagnostic::Level;
+    use crate::parser::error::ParseError;
     use crate::parser::structure::Class;
+    use crate::parser::xml::XmlWriter;
     use crate::tokenizer::Tokenizer;
+    use crate::type_check::TypeCheck;
 
-    fn print_error(tokenizer: Tokenizer, err: impl Display) {
-        eprintln!("Failed to parse provided source file, the next two unparsed lines are:");
-        for line in tokenizer.remaining().lines().take(3) {
-            eprintln!("==> {line}");
+    fn print_parse_error(path: &Path, source: &str, err: &ParseError) {
+        let diagnostic =
+            diagnostic::render(Level::Error, path, source, err, err.span(), err.help());
+        eprintln!("{diagnostic}");
+    }
+
+    fn print_compile_error(level: Level, path: &Path, source: &str, err: &CompileError) {
+        let diagnostic = diagnostic::render(level, path, source, err, Some(err.span()), err.help());
+        eprintln!("{diagnostic}");
+    }
+
+    /// Compares `output` against the `{name}{suffix}.xml` reference file next
+    /// to `path`, returning whether they match.
+    fn compare_output(path: &Path, suffix: &str, output: &[u8]) -> bool {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let reference = path.with_file_name(format!("{name}{suffix}.xml"));
+        match compare::compare(&reference, &String::from_utf8_lossy(output)) {
+            Ok(lines) => {
+                println!(
+                    "Comparison succeeded; path={}; compared_lines={lines}",
+                    reference.display()
+                );
+
+                true
+            }
+            Err(err) => {
+                eprintln!("Failed to compare {}\n\nError: {err}", reference.display());
+
+                false
+            }
         }
-        eprintln!("\nError: {err}");
+    }
+
+    /// Resolves the path argument to the Jack files it refers to.
+    fn source_files(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        match path.is_dir() {
+            true => jack_files(path),
+            false => Ok(vec![path.to_owned()]),
+        }
+    }
+
+    /// Lists the Jack files in a directory.
+    fn jack_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files = Vec::default();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "jack") {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        Ok(files)
     }
 
     // Parse command line args.
     let args = args::Args::parse();
 
-    // Read the source file into memory.
-    let source = std::fs::read_to_string(&args.path).unwrap();
+    // Find the source files.
+    let paths = match source_files(&args.path) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("Failed to read directory; path={}; err={err}", args.path.display());
 
-    // Tokenize the source file.
-    let mut tokenizer = Tokenizer::new(&source);
+            return ExitCode::FAILURE;
+        }
+    };
+    if paths.is_empty() {
+        eprintln!("No Jack files found; path={}", args.path.display());
 
-    // Execute requested action.
+        return ExitCode::FAILURE;
+    }
+
+    // Labels must be unique across all classes of the program.
+    let vm_symbols = Box::leak(Box::new(AtomicU64::new(0)));
+
+    // Read the source files into memory.
+    let mut failed = false;
+    let mut sources = Vec::default();
+    for path in &paths {
+        match std::fs::read_to_string(path) {
+            Ok(source) => sources.push((path, source)),
+            Err(err) => {
+                eprintln!("Failed to read file; path={}; err={err}", path.display());
+                failed = true;
+            }
+        }
+    }
+
+    // Execute requested action, continuing past failures so all errors are
+    // reported.
     match args.action {
         Action::Tokenize => {
-            let stdout = std::io::stdout().lock();
-            let mut output = BufWriter::new(stdout);
-            writeln!(output, "<tokens>").unwrap();
-            while let Some(token) = tokenizer.next() {
-                token.unwrap().write_xml(&mut output);
-                writeln!(output).unwrap();
+            for (path, source) in &sources {
+                let mut tokenizer = Tokenizer::new(source);
+                let mut output = Vec::default();
+                writeln!(output, "<tokens>").unwrap();
+                while let Some(token) = tokenizer.next() {
+                    match token {
+                        Ok(token) => token.write_xml(&mut output),
+                        // Invalid source is skipped, so keep going to report every error.
+                        Err(err) => {
+                            print_parse_error(path, source, &err.into());
+                            failed = true;
+
+                            continue;
+                        }
+                    }
+                    writeln!(output).unwrap();
+                }
+                writeln!(output, "</tokens>").unwrap();
+
+                // Print the tokens or check them against the `T.xml` reference.
+                match args.compare {
+                    true => failed |= !compare_output(path, "T", &output),
+                    false => std::io::stdout().write_all(&output).unwrap(),
+                }
             }
-            writeln!(output, "</tokens>").unwrap();
         }
-        Action::Parse => match Class::parse(&mut tokenizer) {
-            Ok(class) => println!("{class:#?}"),
-            Err(err) => {
-                print_error(tokenizer, err);
+        Action::Parse => {
+            for (path, source) in &sources {
+                let mut errors = Vec::default();
+                let class = Class::parse(&mut Tokenizer::new(source), &mut errors);
+                for err in &errors {
+                    print_parse_error(path, source, err);
+                }
+                failed |= !errors.is_empty();
 
-                return ExitCode::FAILURE;
+                // Print whatever could be parsed, even around syntax errors.
+                let class = match class {
+                    Ok(class) => class,
+                    Err(err) => {
+                        print_parse_error(path, source, &err);
+                        failed = true;
+
+                        continue;
+                    }
+                };
+
+                // Parse trees are always compared as XML.
+                match (args.compare, args.format) {
+                    (true, _) => {
+                        let mut xml = XmlWriter::new(Vec::default());
+                        class.write_xml(&mut xml);
+                        failed |= !compare_output(path, "", &xml.into_inner());
+                    }
+                    (false, Format::Xml) => {
+                        let stdout = std::io::stdout().lock();
+                        class.write_xml(&mut XmlWriter::new(BufWriter::new(stdout)));
+                    }
+                    (false, Format::Debug) => println!("{class:#?}"),
+                }
             }
-        },
+        }
         Action::Compile => {
-            let class = match Class::parse(&mut tokenizer) {
-                Ok(class) => class,
+            // Parse every class up front so calls can be checked across classes.
+            let mut classes = Vec::default();
+            let mut class_sources = Vec::default();
+            let mut syntax_errors = Vec::default();
+            for (path, source) in &sources {
+                let mut errors = Vec::default();
+                let class = Class::parse(&mut Tokenizer::new(source), &mut errors);
+                for err in &errors {
+                    print_parse_error(path, source, err);
+                }
+                match class {
+                    Ok(class) => {
+                        classes.push(class);
+                        class_sources.push((*path, source));
+                        syntax_errors.push(!errors.is_empty());
+                    }
+                    Err(err) => {
+                        print_parse_error(path, source, &err);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                return ExitCode::FAILURE;
+            }
+
+            // A single file may call the other classes in its directory, so
+            // declare them without compiling them.
+            let siblings = match args.path.is_dir() {
+                true => Vec::default(),
+                false => args
+                    .path
+                    .parent()
+                    .and_then(|dir| jack_files(dir).ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|path| path.file_name() != args.path.file_name())
+                    .filter_map(|path| std::fs::read_to_string(path).ok())
+                    .collect(),
+            };
+            let mut declarations: Vec<_> = siblings
+                .iter()
+                .filter_map(|source| {
+                    Class::parse(&mut Tokenizer::new(source), &mut Vec::default()).ok()
+                })
+                .collect();
+            let compiled = classes.len();
+            classes.append(&mut declarations);
+
+            let program = match ProgramContext::new(&classes) {
+                Ok(program) => program,
                 Err(err) => {
-                    print_error(tokenizer, err);
+                    // Program errors point at the name of a class or subroutine, so
+                    // find the file declaring it.
+                    let span = err.span();
+                    let declares = |class: &Class| {
+                        class.span == span
+                            || class
+                                .subroutines
+                                .iter()
+                                .any(|subroutine| subroutine.span == span)
+                    };
+                    match class_sources
+                        .iter()
+                        .zip(&classes)
+                        .rev()
+                        .find(|(_, class)| declares(class))
+                    {
+                        Some(((path, source), _)) => {
+                            print_compile_error(Level::Error, path, source, &err);
+                        }
+                        None => eprintln!("error: {err}"),
+                    }
 
                     return ExitCode::FAILURE;
                 }
             };
 
-            let vm_symbols = Box::leak(Box::new(AtomicU64::new(0)));
-            match code_gen::compile(vm_symbols, &class) {
-                Ok(code) => {
-                    for code in code {
-                        println!("{code}");
-                    }
+            let files = class_sources.into_iter().zip(syntax_errors);
+            for (((path, source), syntax_errors), class) in files.zip(&classes[..compiled]) {
+                // Report type errors before generating code.
+                let type_errors = match args.type_check {
+                    TypeCheck::Off => Vec::default(),
+                    TypeCheck::Warn | TypeCheck::Deny => match type_check::check(&program, class) {
+                        Ok(type_errors) => type_errors,
+                        // The class cannot be compiled either, so report it once here.
+                        Err(err) => {
+                            print_compile_error(Level::Error, path, source, &err);
+                            failed = true;
+
+                            continue;
+                        }
+                    },
+                };
+                let level = match args.type_check {
+                    TypeCheck::Deny => Level::Error,
+                    _ => Level::Warning,
+                };
+                for err in &type_errors {
+                    print_compile_error(level, path, source, err);
                 }
-                Err(err) => {
-                    print_error(tokenizer, err);
+                if args.type_check == TypeCheck::Deny && !type_errors.is_empty() {
+                    failed = true;
 
-                    return ExitCode::FAILURE;
+                    continue;
+                }
+
+                // Partially parsed classes are only checked, never compiled.
+                if syntax_errors {
+                    failed = true;
+
+                    continue;
+                }
+
+                let mut warnings = Vec::default();
+                let res = code_gen::compile(vm_symbols, &program, class, &mut warnings);
+                for warning in &warnings {
+                    print_compile_error(Level::Warning, path, source, warning);
+                }
+                let code = match res {
+                    Ok(code) => code,
+                    Err(err) => {
+                        print_compile_error(Level::Error, path, source, &err);
+                        failed = true;
+
+                        continue;
+                    }
+                };
+
+                // Each class is written to its own `.vm` file unless told otherwise.
+                let mut contents = code.join("\n");
+                contents.push('\n');
+                let output = match &args.output {
+                    Some(output) if output.as_os_str() == "-" => {
+                        print!("{contents}");
+
+                        continue;
+                    }
+                    Some(output) if args.path.is_dir() => {
+                        output.join(path.file_name().unwrap()).with_extension("vm")
+                    }
+                    Some(output) => output.clone(),
+                    None => path.with_extension("vm"),
+                };
+                if let Err(err) = shared::fs::write_atomic(&output, contents) {
+                    eprintln!("Failed to write file; path={}; err={err}", output.display());
+                    failed = true;
                 }
             }
         }
     }
 
-    ExitCode::SUCCESS
+    match failed {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
 }
@@ -1,15 +1,68 @@
 use thiserror::Error;
 
-use crate::tokenizer::{SourceToken, TokenizeError};
+use crate::tokenizer::{SourceToken, Span, TokenizeError};
 
 #[derive(Debug, Error)]
 pub(crate) enum ParseError<'a> {
     #[error("Invalid token; err={0}")]
     InvalidToken(#[from] TokenizeError),
-    #[error("Unexpected token; token={0:?}")]
+    #[error("Unexpected token; token={}", .0.source)]
     UnexpectedToken(SourceToken<'a>),
     #[error("Unexpected eof")]
     UnexpectedEof,
     #[error("Trailing comma")]
-    TrailingComma,
+    TrailingComma(Span),
+    #[error("Unexpected token after class; token={}", .0.source)]
+    TrailingToken(SourceToken<'a>),
+}
+
+impl ParseError<'_> {
+    /// The offending region of source, `None` if the error is at the end of the
+    /// file.
+    pub(crate) fn span(&self) -> Option<Span> {
+        match self {
+            Self::InvalidToken(err) => Some(err.span()),
+            Self::UnexpectedToken(token) => Some(token.span),
+            Self::UnexpectedEof => None,
+            Self::TrailingComma(span) => Some(*span),
+            Self::TrailingToken(token) => Some(token.span),
+        }
+    }
+
+    /// Whether parsing can resume after this error, errors at the end of the
+    /// token stream are fatal.
+    pub(crate) fn is_recoverable(&self) -> bool {
+        match self {
+            Self::UnexpectedEof => false,
+            Self::InvalidToken(_)
+            | Self::UnexpectedToken(_)
+            | Self::TrailingComma(_)
+            | Self::TrailingToken(_) => true,
+        }
+    }
+
+    pub(crate) fn help(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidToken(TokenizeError::UnclosedComment(_)) => {
+                Some("close the comment with `*/`")
+            }
+            Self::InvalidToken(TokenizeError::UnexpectedCharacter { .. }) => None,
+            Self::InvalidToken(TokenizeError::IntegerOverflow(_)) => {
+                Some("integer constants range from 0 to 32767")
+            }
+            Self::InvalidToken(TokenizeError::UnclosedString(_)) => {
+                Some("close the string with `\"`")
+            }
+            Self::InvalidToken(TokenizeError::NewlineInString(_)) => {
+                Some("string constants cannot span multiple lines")
+            }
+            Self::InvalidToken(TokenizeError::InvalidStringCharacter { .. }) => {
+                Some("string constants may only contain printable ASCII characters")
+            }
+            Self::TrailingToken(_) => Some("each file must contain exactly one class"),
+            Self::UnexpectedToken(_) => None,
+            Self::UnexpectedEof => Some("check for a missing `}` or `;`"),
+            Self::TrailingComma(_) => Some("remove the trailing comma"),
+        }
+    }
 }
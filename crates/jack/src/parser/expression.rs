@@ -1,53 +1,98 @@
-use hashbrown::HashMap;
+use std::fmt::Display;
+use std::io::Write;
 
-use crate::code_gen::{ClassContext, CompileError, SymbolEntry, SymbolLocation};
+use crate::code_gen::{ClassContext, CompileError, SubroutineContext, SubroutineSignature};
 use crate::parser::error::ParseError;
-use crate::parser::structure::Type;
+use crate::parser::structure::{SubroutineType, Type};
 use crate::parser::utils::{check_next, eat, peek};
-use crate::tokenizer::{Keyword, Symbol, Token, Tokenizer};
+use crate::parser::xml::XmlWriter;
+use crate::tokenizer::{Keyword, SourceToken, Span, Symbol, Token, Tokenizer};
+use crate::type_check::ExpressionType;
 
 #[derive(Debug)]
 pub(crate) struct Expression<'a> {
+    pub(crate) span: Span,
     term: Box<Term<'a>>,
-    op: Option<(Op, Box<Term<'a>>)>,
+    /// The trailing `(op term)*` chain, applied left to right.
+    ops: Vec<(Op, Term<'a>)>,
 }
 
 impl<'a> Expression<'a> {
     pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+        let start = tokenizer.peek_0().and_then(Result::ok).map(|st| st.span);
         let term = Box::new(Term::parse(tokenizer)?);
 
-        // Eat the op if one exists.
-        let op = match peek(tokenizer) {
-            Some(Token::Symbol(
-                Symbol::Plus
-                | Symbol::Minus
-                | Symbol::Asterisk
-                | Symbol::ForwardSlash
-                | Symbol::Ampersand
-                | Symbol::Pipe
-                | Symbol::LeftAngleBracket
-                | Symbol::RightAngleBracket
-                | Symbol::Equals,
-            )) => Some((Op::parse(tokenizer)?, Box::new(Term::parse(tokenizer)?))),
-            _ => None,
-        };
+        // Eat ops until the expression ends.
+        let mut ops = Vec::default();
+        while let Some(Token::Symbol(
+            Symbol::Plus
+            | Symbol::Minus
+            | Symbol::Asterisk
+            | Symbol::ForwardSlash
+            | Symbol::Ampersand
+            | Symbol::Pipe
+            | Symbol::LeftAngleBracket
+            | Symbol::RightAngleBracket
+            | Symbol::Equals,
+        )) = peek(tokenizer)
+        {
+            ops.push((Op::parse(tokenizer)?, Term::parse(tokenizer)?));
+        }
+
+        // The first token was consumed by the term, so it must exist.
+        let span = start.unwrap().to(tokenizer.previous());
 
-        Ok(Expression { term, op })
+        Ok(Expression { span, term, ops })
     }
 
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         let mut code = self.term.compile(class, subroutine)?;
-        if let Some((op, term)) = &self.op {
+        for (op, term) in &self.ops {
             code.extend(term.compile(class, subroutine)?);
             code.push(op.compile());
         }
 
         Ok(code)
     }
+
+    pub(crate) fn check<'c>(
+        &'c self,
+        class: &'c ClassContext,
+        subroutine: &'c SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) -> ExpressionType<'c> {
+        let mut left = self.term.check(class, subroutine, errors);
+        for (op, term) in &self.ops {
+            let right = term.check(class, subroutine, errors);
+            left = match op {
+                Op::Plus | Op::Minus | Op::Multiply | Op::Divide => ExpressionType::Int,
+                Op::Lt | Op::Gt | Op::Equals => ExpressionType::Boolean,
+                // Bitwise ops double as logical ops.
+                Op::BitAnd | Op::BitOr
+                    if left == ExpressionType::Boolean || right == ExpressionType::Boolean =>
+                {
+                    ExpressionType::Boolean
+                }
+                Op::BitAnd | Op::BitOr => ExpressionType::Int,
+            };
+        }
+
+        left
+    }
+
+    pub(crate) fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("expression");
+        self.term.write_xml(xml);
+        for (op, term) in &self.ops {
+            xml.symbol(op.symbol());
+            term.write_xml(xml);
+        }
+        xml.close("expression");
+    }
 }
 
 #[derive(Debug)]
@@ -58,7 +103,7 @@ pub(crate) enum Term<'a> {
     False,
     Null,
     This,
-    Variable(&'a str),
+    Variable(&'a str, Span),
     VariableIndex(VariableIndex<'a>),
     Expression(Expression<'a>),
     UnaryOp { op: UnaryOp, term: Box<Self> },
@@ -76,7 +121,10 @@ impl<'a> Term<'a> {
 
         Ok(match st.token {
             Token::IntegerConstant(integer) => simple_term(Term::IntegerConstant(integer)),
-            Token::StringConstant => simple_term(Term::StringConstant(st.source)),
+            Token::StringConstant => {
+                // Strip the surrounding quotes.
+                simple_term(Term::StringConstant(&st.source[1..st.source.len() - 1]))
+            }
             Token::Keyword(Keyword::True) => simple_term(Term::True),
             Token::Keyword(Keyword::False) => simple_term(Term::False),
             Token::Keyword(Keyword::Null) => simple_term(Term::Null),
@@ -112,35 +160,34 @@ impl<'a> Term<'a> {
                     _ => {
                         tokenizer.next().unwrap().unwrap();
 
-                        Term::Variable(st.source)
+                        Term::Variable(st.source, st.span)
                     }
                 }
             }
-            _ => return Err(ParseError::UnexpectedToken(tokenizer.next().unwrap().unwrap())),
+            _ => return Err(ParseError::UnexpectedToken(st)),
         })
     }
 
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         match self {
             Self::IntegerConstant(integer) => Ok(vec![format!("push constant {integer}")]),
             Self::StringConstant(string) => {
                 let mut code = vec![
-                    format!("push constant {}", string.len()),
+                    format!("push constant {}", string.chars().count()),
                     "call String.new 1".to_string(),
-                    "pop temp 0".to_string(),
                 ];
+                // `appendChar` returns the string, leaving it on the stack for
+                // the next call.
                 for char in string.chars() {
                     code.extend([
-                        "push temp 0".to_string(),
-                        format!("push constant {}", u8::try_from(char).unwrap()),
+                        format!("push constant {}", u32::from(char)),
                         "call String.appendChar 2".to_string(),
                     ]);
                 }
-                code.push("push temp 0".to_string());
 
                 Ok(code)
             }
@@ -155,16 +202,18 @@ impl<'a> Term<'a> {
             Self::False => Ok(vec!["push constant 0".to_string()]),
             Self::Null => Ok(vec!["push constant 0".to_string()]),
             Self::This => Ok(vec!["push pointer 0".to_string()]),
-            Self::Variable(var) => Ok(vec![subroutine
+            Self::Variable(var, span) => Ok(vec![subroutine
+                .symbols
                 .get(var)
                 .or_else(|| class.symbols.get(var))
-                .ok_or(CompileError::UnknownSymbol(var))?
+                .ok_or(CompileError::UnknownSymbol { symbol: var, span: *span })?
                 .compile_push()]),
             Self::VariableIndex(idx) => {
                 let symbol = subroutine
+                    .symbols
                     .get(idx.var)
                     .or_else(|| class.symbols.get(idx.var))
-                    .ok_or(CompileError::UnknownSymbol(idx.var))?;
+                    .ok_or(CompileError::UnknownSymbol { symbol: idx.var, span: idx.span })?;
 
                 // [symbol]
                 let mut code = vec![symbol.compile_push()];
@@ -184,22 +233,104 @@ impl<'a> Term<'a> {
             Self::SubroutineCall(call) => call.compile(class, subroutine),
         }
     }
+
+    pub(crate) fn check<'c>(
+        &'c self,
+        class: &'c ClassContext,
+        subroutine: &'c SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) -> ExpressionType<'c> {
+        match self {
+            Self::IntegerConstant(_) => ExpressionType::Int,
+            Self::StringConstant(_) => ExpressionType::Class("String"),
+            Self::True | Self::False => ExpressionType::Boolean,
+            Self::Null => ExpressionType::Null,
+            Self::This => ExpressionType::Class(class.name),
+            Self::Variable(var, _) => subroutine
+                .symbols
+                .get(var)
+                .or_else(|| class.symbols.get(var))
+                .map_or(ExpressionType::Unknown, |symbol| symbol.symbol_type.into()),
+            Self::VariableIndex(idx) => {
+                idx.index.check(class, subroutine, errors);
+
+                ExpressionType::Unknown
+            }
+            Self::Expression(expression) => expression.check(class, subroutine, errors),
+            Self::UnaryOp { op: UnaryOp::Negate, term } => {
+                term.check(class, subroutine, errors);
+
+                ExpressionType::Int
+            }
+            Self::UnaryOp { op: UnaryOp::Not, term } => {
+                match term.check(class, subroutine, errors) {
+                    operand @ (ExpressionType::Boolean | ExpressionType::Unknown) => operand,
+                    _ => ExpressionType::Int,
+                }
+            }
+            Self::SubroutineCall(call) => match call.check(class, subroutine, errors) {
+                ExpressionType::Void => {
+                    errors.push(CompileError::VoidValue {
+                        subroutine: call.to_string(),
+                        span: call.span,
+                    });
+
+                    ExpressionType::Unknown
+                }
+                returned => returned,
+            },
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("term");
+        match self {
+            Self::IntegerConstant(integer) => xml.integer_constant(*integer),
+            Self::StringConstant(string) => xml.string_constant(string),
+            Self::True => xml.keyword(Keyword::True),
+            Self::False => xml.keyword(Keyword::False),
+            Self::Null => xml.keyword(Keyword::Null),
+            Self::This => xml.keyword(Keyword::This),
+            Self::Variable(var, _) => xml.identifier(var),
+            Self::VariableIndex(idx) => {
+                xml.identifier(idx.var);
+                xml.symbol(Symbol::LeftBracket);
+                idx.index.write_xml(xml);
+                xml.symbol(Symbol::RightBracket);
+            }
+            Self::Expression(expression) => {
+                xml.symbol(Symbol::LeftParen);
+                expression.write_xml(xml);
+                xml.symbol(Symbol::RightParen);
+            }
+            Self::UnaryOp { op, term } => {
+                xml.symbol(match op {
+                    UnaryOp::Negate => Symbol::Minus,
+                    UnaryOp::Not => Symbol::Tilde,
+                });
+                term.write_xml(xml);
+            }
+            Self::SubroutineCall(call) => call.write_xml(xml),
+        }
+        xml.close("term");
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct VariableIndex<'a> {
     var: &'a str,
+    span: Span,
     index: Expression<'a>,
 }
 
 impl<'a> VariableIndex<'a> {
     fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
-        let var = eat!(tokenizer, Token::Identifier)?;
+        let SourceToken { source: var, span, .. } = eat!(tokenizer, Token::Identifier)?;
         eat!(tokenizer, Token::Symbol(Symbol::LeftBracket))?;
         let index = Expression::parse(tokenizer)?;
         eat!(tokenizer, Token::Symbol(Symbol::RightBracket))?;
 
-        Ok(Self { var, index })
+        Ok(Self { var, span, index })
     }
 }
 
@@ -207,6 +338,8 @@ impl<'a> VariableIndex<'a> {
 pub(crate) struct SubroutineCall<'a> {
     var: Option<&'a str>,
     subroutine: &'a str,
+    /// The span of the callee's name, including any qualifier.
+    span: Span,
     arguments: Vec<Expression<'a>>,
 }
 
@@ -223,10 +356,12 @@ impl<'a> SubroutineCall<'a> {
                 eat!(tokenizer, Token::Symbol(Symbol::Dot))?;
                 let subroutine = eat!(tokenizer, Token::Identifier)?;
 
-                (Some(first_identifier), subroutine)
+                (Some(first_identifier.source), subroutine)
             }
             false => (None, first_identifier),
         };
+        let span = first_identifier.span.to(subroutine.span);
+        let subroutine = subroutine.source;
 
         // Eat all the arguments.
         eat!(tokenizer, Token::Symbol(Symbol::LeftParen))?;
@@ -239,38 +374,91 @@ impl<'a> SubroutineCall<'a> {
         }
         eat!(tokenizer, Token::Symbol(Symbol::RightParen))?;
 
-        Ok(SubroutineCall { var, subroutine, arguments })
+        Ok(SubroutineCall { var, subroutine, span, arguments })
     }
 
-    pub(crate) fn compile(
+    /// Resolves the callee to its VM name & signature, along with the push of
+    /// the object being operated on if it is a method.
+    ///
+    /// Calls into classes outside the program have no signature, they are
+    /// assumed to be methods when called on a variable & functions otherwise.
+    fn resolve<'c>(
         &self,
-        class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
-    ) -> Result<Vec<String>, CompileError<'a>> {
-        // Push the object being operated on if necessary.
+        class: &'c ClassContext,
+        subroutine: &SubroutineContext,
+    ) -> Result<(String, Option<&'c SubroutineSignature<'c>>, Option<String>), CompileError<'a>>
+    {
         let (class_name, push_this) = match self.var {
-            Some(var) => {
-                let push_this = subroutine.get(var).or_else(|| {
-                    class
-                        .symbols
-                        .get(var)
-                        .filter(|symbol| symbol.location == SymbolLocation::This)
-                });
-
-                match push_this {
-                    Some(symbol) => {
-                        let class_name = match symbol.symbol_type {
-                            Type::Class(name) => name,
-                            _ => return Err(CompileError::InvalidCallee(var)),
-                        };
+            Some(var) => match subroutine
+                .symbols
+                .get(var)
+                .or_else(|| class.symbols.get(var))
+            {
+                Some(symbol) => {
+                    let Type::Class(class_name) = symbol.symbol_type else {
+                        return Err(CompileError::InvalidCallee { callee: var, span: self.span });
+                    };
 
-                        (class_name, Some(symbol.compile_push()))
-                    }
-                    None => (var, None),
+                    (class_name, Some(symbol.compile_push()))
                 }
-            }
+                None => (var, None),
+            },
             None => (class.name, Some("push pointer 0".to_string())),
         };
+        let name = format!("{class_name}.{}", self.subroutine);
+        let Some(signature) = class
+            .program
+            .signature(class_name, self.subroutine, self.span)?
+        else {
+            return Ok((name, None, push_this));
+        };
+        let push_this = match (signature.subroutine_type, push_this) {
+            // Unqualified method calls need a `this` to operate on.
+            (SubroutineType::Method, Some(_))
+                if self.var.is_none() && subroutine.subroutine_type == SubroutineType::Function =>
+            {
+                return Err(CompileError::MethodWithoutObject {
+                    subroutine: name,
+                    span: self.span,
+                });
+            }
+            (SubroutineType::Method, Some(push_this)) => Some(push_this),
+            (SubroutineType::Method, None) => {
+                return Err(CompileError::MethodWithoutObject { subroutine: name, span: self.span })
+            }
+            (_, Some(_)) if self.var.is_some() => {
+                return Err(CompileError::NotAMethod { subroutine: name, span: self.span })
+            }
+            (..) => None,
+        };
+        if self.arguments.len() != signature.parameters.len() {
+            return Err(CompileError::ArgumentCount {
+                subroutine: name,
+                expected: signature.parameters.len(),
+                actual: self.arguments.len(),
+                span: self.span,
+            });
+        }
+
+        Ok((name, Some(signature), push_this))
+    }
+
+    pub(crate) fn compile(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+    ) -> Result<Vec<String>, CompileError<'a>> {
+        let (name, signature, push_this) = self.resolve(class, subroutine)?;
+        if signature.is_none() {
+            let (class_name, _) = name.split_once('.').unwrap();
+            class
+                .warnings
+                .borrow_mut()
+                .push(CompileError::UnknownClass {
+                    class: class_name.to_string(),
+                    span: self.span,
+                });
+        }
 
         // Push all the arguments.
         let method = push_this.is_some();
@@ -280,14 +468,75 @@ impl<'a> SubroutineCall<'a> {
         }
 
         // Append the function call.
-        code.push(format!(
-            "call {class_name}.{} {}",
-            self.subroutine,
-            self.arguments.len() + usize::from(method)
-        ));
+        code.push(format!("call {name} {}", self.arguments.len() + usize::from(method)));
 
         Ok(code)
     }
+
+    pub(crate) fn check<'c>(
+        &'c self,
+        class: &'c ClassContext,
+        subroutine: &'c SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) -> ExpressionType<'c> {
+        let arguments: Vec<_> = self
+            .arguments
+            .iter()
+            .map(|argument| argument.check(class, subroutine, errors))
+            .collect();
+
+        // Unresolved calls are reported by code generation.
+        let Ok((name, Some(signature), _)) = self.resolve(class, subroutine) else {
+            return ExpressionType::Unknown;
+        };
+        let parameters = self
+            .arguments
+            .iter()
+            .zip(arguments)
+            .zip(&signature.parameters);
+        for (i, ((argument, actual), expected)) in parameters.enumerate() {
+            if !actual.assignable_to(*expected) {
+                errors.push(CompileError::ArgumentType {
+                    subroutine: name.clone(),
+                    argument: i,
+                    expected: ExpressionType::from(*expected).to_string(),
+                    actual: actual.to_string(),
+                    span: argument.span,
+                });
+            }
+        }
+
+        signature.return_type.into()
+    }
+
+    /// Writes the call's tokens, which the XML inlines into the enclosing
+    /// element.
+    pub(crate) fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        if let Some(var) = self.var {
+            xml.identifier(var);
+            xml.symbol(Symbol::Dot);
+        }
+        xml.identifier(self.subroutine);
+        xml.symbol(Symbol::LeftParen);
+        xml.open("expressionList");
+        for (i, argument) in self.arguments.iter().enumerate() {
+            if i > 0 {
+                xml.symbol(Symbol::Comma);
+            }
+            argument.write_xml(xml);
+        }
+        xml.close("expressionList");
+        xml.symbol(Symbol::RightParen);
+    }
+}
+
+impl Display for SubroutineCall<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.var {
+            Some(var) => write!(f, "{var}.{}", self.subroutine),
+            None => write!(f, "{}", self.subroutine),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -334,6 +583,20 @@ impl Op {
         }
         .to_string()
     }
+
+    fn symbol(&self) -> Symbol {
+        match self {
+            Self::Plus => Symbol::Plus,
+            Self::Minus => Symbol::Minus,
+            Self::Multiply => Symbol::Asterisk,
+            Self::Divide => Symbol::ForwardSlash,
+            Self::BitAnd => Symbol::Ampersand,
+            Self::BitOr => Symbol::Pipe,
+            Self::Lt => Symbol::LeftAngleBracket,
+            Self::Gt => Symbol::RightAngleBracket,
+            Self::Equals => Symbol::Equals,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -4,3 +4,4 @@ pub(crate) mod expression;
 pub(crate) mod statement;
 pub(crate) mod structure;
 pub(crate) mod utils;
+pub(crate) mod xml;
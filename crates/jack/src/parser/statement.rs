@@ -1,10 +1,13 @@
-use hashbrown::HashMap;
+use std::io::Write;
 
-use crate::code_gen::{ClassContext, CompileError, SymbolEntry};
+use crate::code_gen::{ClassContext, CompileError, SubroutineContext};
 use crate::parser::error::ParseError;
 use crate::parser::expression::{Expression, SubroutineCall};
-use crate::parser::utils::{check_next, eat};
-use crate::tokenizer::{Keyword, Symbol, Token, Tokenizer};
+use crate::parser::structure::{ReturnType, Type};
+use crate::parser::utils::{check_next, eat, synchronize_statement};
+use crate::parser::xml::XmlWriter;
+use crate::tokenizer::{Keyword, SourceToken, Span, Symbol, Token, Tokenizer};
+use crate::type_check::ExpressionType;
 
 #[derive(Debug)]
 pub(crate) enum Statement<'a> {
@@ -13,15 +16,62 @@ pub(crate) enum Statement<'a> {
     While(WhileStatement<'a>),
     Do(DoStatement<'a>),
     Return(ReturnStatement<'a>),
+    /// A statement that failed to parse, spanning the source skipped over.
+    Error(Span),
+}
+
+/// Parses statements up to the next `}`, recording recoverable syntax errors in
+/// `errors` and resuming at the following statement.
+pub(crate) fn parse_statements<'a>(
+    tokenizer: &mut Tokenizer<'a>,
+    errors: &mut Vec<ParseError<'a>>,
+) -> Result<Vec<Statement<'a>>, ParseError<'a>> {
+    let mut statements = Vec::default();
+    while !check_next(tokenizer, Token::Symbol(Symbol::RightBrace)) {
+        let start = match tokenizer.peek_0().ok_or(ParseError::UnexpectedEof)? {
+            Ok(st) => st.span,
+            Err(err) => err.span(),
+        };
+        let previous = tokenizer.previous();
+        match Statement::parse(tokenizer, errors) {
+            Ok(statement) => statements.push(statement),
+            Err(err) if err.is_recoverable() => {
+                errors.push(err);
+                // Skip tokens that cannot start a statement but would stop the
+                // synchronization (e.g. a misplaced `var`).
+                if tokenizer.previous() == previous {
+                    tokenizer.next();
+                }
+                synchronize_statement(tokenizer, errors);
+                statements.push(Statement::Error(start.to(tokenizer.previous())));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(statements)
+}
+
+pub(crate) fn write_statements_xml(statements: &[Statement], xml: &mut XmlWriter<impl Write>) {
+    xml.open("statements");
+    for statement in statements {
+        statement.write_xml(xml);
+    }
+    xml.close("statements");
 }
 
 impl<'a> Statement<'a> {
-    pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    pub(crate) fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         let st = tokenizer.peek_0().ok_or(ParseError::UnexpectedEof)??;
         match st.token {
             Token::Keyword(Keyword::Let) => LetStatement::parse(tokenizer).map(Self::Let),
-            Token::Keyword(Keyword::If) => IfStatement::parse(tokenizer).map(Self::If),
-            Token::Keyword(Keyword::While) => WhileStatement::parse(tokenizer).map(Self::While),
+            Token::Keyword(Keyword::If) => IfStatement::parse(tokenizer, errors).map(Self::If),
+            Token::Keyword(Keyword::While) => {
+                WhileStatement::parse(tokenizer, errors).map(Self::While)
+            }
             Token::Keyword(Keyword::Do) => DoStatement::parse(tokenizer).map(Self::Do),
             Token::Keyword(Keyword::Return) => ReturnStatement::parse(tokenizer).map(Self::Return),
             _ => Err(ParseError::UnexpectedToken(st)),
@@ -31,7 +81,7 @@ impl<'a> Statement<'a> {
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         match self {
             Self::Let(stmt) => stmt.compile(class, subroutine),
@@ -39,6 +89,35 @@ impl<'a> Statement<'a> {
             Self::While(stmt) => stmt.compile(class, subroutine),
             Self::Do(stmt) => stmt.compile(class, subroutine),
             Self::Return(stmt) => stmt.compile(class, subroutine),
+            // Files with syntax errors are rejected before code generation.
+            Self::Error(_) => Ok(Vec::default()),
+        }
+    }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        match self {
+            Self::Let(stmt) => stmt.check(class, subroutine, errors),
+            Self::If(stmt) => stmt.check(class, subroutine, errors),
+            Self::While(stmt) => stmt.check(class, subroutine, errors),
+            Self::Do(stmt) => stmt.check(class, subroutine, errors),
+            Self::Return(stmt) => stmt.check(class, subroutine, errors),
+            Self::Error(_) => {}
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        match self {
+            Self::Let(stmt) => stmt.write_xml(xml),
+            Self::If(stmt) => stmt.write_xml(xml),
+            Self::While(stmt) => stmt.write_xml(xml),
+            Self::Do(stmt) => stmt.write_xml(xml),
+            Self::Return(stmt) => stmt.write_xml(xml),
+            Self::Error(_) => {}
         }
     }
 }
@@ -46,6 +125,7 @@ impl<'a> Statement<'a> {
 #[derive(Debug)]
 pub(crate) struct LetStatement<'a> {
     pub(crate) var_name: &'a str,
+    pub(crate) span: Span,
     pub(crate) index: Option<Expression<'a>>,
     pub(crate) expression: Expression<'a>,
 }
@@ -53,7 +133,7 @@ pub(crate) struct LetStatement<'a> {
 impl<'a> LetStatement<'a> {
     pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
         eat!(tokenizer, Token::Keyword(Keyword::Let))?;
-        let var_name = eat!(tokenizer, Token::Identifier)?;
+        let SourceToken { source: var_name, span, .. } = eat!(tokenizer, Token::Identifier)?;
 
         // Handle index case.
         let index = match check_next(tokenizer, Token::Symbol(Symbol::LeftBracket)) {
@@ -72,13 +152,13 @@ impl<'a> LetStatement<'a> {
         let expression = Expression::parse(tokenizer)?;
         eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
 
-        Ok(LetStatement { var_name, index, expression })
+        Ok(LetStatement { var_name, span, index, expression })
     }
 
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         // Compute the right hand side of the assignment.
         //
@@ -87,9 +167,10 @@ impl<'a> LetStatement<'a> {
 
         // Compute the region in memory to store the expression result.
         let symbol = subroutine
+            .symbols
             .get(self.var_name)
             .or_else(|| class.symbols.get(self.var_name))
-            .ok_or(CompileError::UnknownSymbol(self.var_name))?;
+            .ok_or(CompileError::UnknownSymbol { symbol: self.var_name, span: self.span })?;
         match &self.index {
             Some(expression) => {
                 // [RHS, symbol]
@@ -111,17 +192,65 @@ impl<'a> LetStatement<'a> {
 
         Ok(code)
     }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        let actual = self.expression.check(class, subroutine, errors);
+
+        // Array elements are untyped, so only whole variables are checked.
+        if let Some(index) = &self.index {
+            index.check(class, subroutine, errors);
+
+            return;
+        }
+        let symbol = subroutine
+            .symbols
+            .get(self.var_name)
+            .or_else(|| class.symbols.get(self.var_name));
+        if let Some(symbol) = symbol {
+            if !actual.assignable_to(symbol.symbol_type) {
+                errors.push(CompileError::AssignmentType {
+                    variable: self.var_name.to_string(),
+                    expected: ExpressionType::from(symbol.symbol_type).to_string(),
+                    actual: actual.to_string(),
+                    span: self.span.to(self.expression.span),
+                });
+            }
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("letStatement");
+        xml.keyword(Keyword::Let);
+        xml.identifier(self.var_name);
+        if let Some(index) = &self.index {
+            xml.symbol(Symbol::LeftBracket);
+            index.write_xml(xml);
+            xml.symbol(Symbol::RightBracket);
+        }
+        xml.symbol(Symbol::Equals);
+        self.expression.write_xml(xml);
+        xml.symbol(Symbol::Semicolon);
+        xml.close("letStatement");
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct IfStatement<'a> {
     pub(crate) condition: Expression<'a>,
     pub(crate) if_statements: Vec<Statement<'a>>,
-    pub(crate) else_statements: Vec<Statement<'a>>,
+    pub(crate) else_statements: Option<Vec<Statement<'a>>>,
 }
 
 impl<'a> IfStatement<'a> {
-    pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    pub(crate) fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         // Eat the condition expression.
         eat!(tokenizer, Token::Keyword(Keyword::If))?;
         eat!(tokenizer, Token::Symbol(Symbol::LeftParen))?;
@@ -130,20 +259,15 @@ impl<'a> IfStatement<'a> {
 
         // Eat the braces & all statements
         eat!(tokenizer, Token::Symbol(Symbol::LeftBrace))?;
-        let mut if_statements = Vec::default();
-        while !check_next(tokenizer, Token::Symbol(Symbol::RightBrace)) {
-            if_statements.push(Statement::parse(tokenizer)?);
-        }
+        let if_statements = parse_statements(tokenizer, errors)?;
         eat!(tokenizer, Token::Symbol(Symbol::RightBrace))?;
 
         // Maybe eat the else statements.
-        let mut else_statements = Vec::default();
+        let mut else_statements = None;
         if check_next(tokenizer, Token::Keyword(Keyword::Else)) {
             eat!(tokenizer, Token::Keyword(Keyword::Else))?;
             eat!(tokenizer, Token::Symbol(Symbol::LeftBrace))?;
-            while !check_next(tokenizer, Token::Symbol(Symbol::RightBrace)) {
-                else_statements.push(Statement::parse(tokenizer)?);
-            }
+            else_statements = Some(parse_statements(tokenizer, errors)?);
             eat!(tokenizer, Token::Symbol(Symbol::RightBrace))?;
         }
 
@@ -153,7 +277,7 @@ impl<'a> IfStatement<'a> {
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         let label0 = class.next_label();
         let label1 = class.next_label();
@@ -166,13 +290,47 @@ impl<'a> IfStatement<'a> {
         }
         code.push(format!("goto L{label1}"));
         code.push(format!("label L{label0}"));
-        for stmt in &self.else_statements {
+        for stmt in self.else_statements.iter().flatten() {
             code.extend(stmt.compile(class, subroutine)?);
         }
         code.push(format!("label L{label1}"));
 
         Ok(code)
     }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        check_condition(&self.condition, class, subroutine, errors);
+        for stmt in self
+            .if_statements
+            .iter()
+            .chain(self.else_statements.iter().flatten())
+        {
+            stmt.check(class, subroutine, errors);
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("ifStatement");
+        xml.keyword(Keyword::If);
+        xml.symbol(Symbol::LeftParen);
+        self.condition.write_xml(xml);
+        xml.symbol(Symbol::RightParen);
+        xml.symbol(Symbol::LeftBrace);
+        write_statements_xml(&self.if_statements, xml);
+        xml.symbol(Symbol::RightBrace);
+        if let Some(else_statements) = &self.else_statements {
+            xml.keyword(Keyword::Else);
+            xml.symbol(Symbol::LeftBrace);
+            write_statements_xml(else_statements, xml);
+            xml.symbol(Symbol::RightBrace);
+        }
+        xml.close("ifStatement");
+    }
 }
 
 #[derive(Debug)]
@@ -182,7 +340,10 @@ pub(crate) struct WhileStatement<'a> {
 }
 
 impl<'a> WhileStatement<'a> {
-    pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    pub(crate) fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         // Eat the condition expression.
         eat!(tokenizer, Token::Keyword(Keyword::While))?;
         eat!(tokenizer, Token::Symbol(Symbol::LeftParen))?;
@@ -191,10 +352,7 @@ impl<'a> WhileStatement<'a> {
 
         // Eat the brace & all statements.
         eat!(tokenizer, Token::Symbol(Symbol::LeftBrace))?;
-        let mut statements = Vec::default();
-        while !check_next(tokenizer, Token::Symbol(Symbol::RightBrace)) {
-            statements.push(Statement::parse(tokenizer)?);
-        }
+        let statements = parse_statements(tokenizer, errors)?;
         eat!(tokenizer, Token::Symbol(Symbol::RightBrace))?;
 
         Ok(WhileStatement { condition, statements })
@@ -203,7 +361,7 @@ impl<'a> WhileStatement<'a> {
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         let label0 = class.next_label();
         let label1 = class.next_label();
@@ -220,6 +378,30 @@ impl<'a> WhileStatement<'a> {
 
         Ok(code)
     }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        check_condition(&self.condition, class, subroutine, errors);
+        for statement in &self.statements {
+            statement.check(class, subroutine, errors);
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("whileStatement");
+        xml.keyword(Keyword::While);
+        xml.symbol(Symbol::LeftParen);
+        self.condition.write_xml(xml);
+        xml.symbol(Symbol::RightParen);
+        xml.symbol(Symbol::LeftBrace);
+        write_statements_xml(&self.statements, xml);
+        xml.symbol(Symbol::RightBrace);
+        xml.close("whileStatement");
+    }
 }
 
 #[derive(Debug)]
@@ -239,36 +421,56 @@ impl<'a> DoStatement<'a> {
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         let mut code = self.call.compile(class, subroutine)?;
         code.push("pop temp 0".to_string());
 
         Ok(code)
     }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        // The return value is discarded, so `void` is fine here.
+        self.call.check(class, subroutine, errors);
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("doStatement");
+        xml.keyword(Keyword::Do);
+        self.call.write_xml(xml);
+        xml.symbol(Symbol::Semicolon);
+        xml.close("doStatement");
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct ReturnStatement<'a> {
+    /// The span of the `return` keyword.
+    pub(crate) span: Span,
     pub(crate) return_value: Option<Expression<'a>>,
 }
 
 impl<'a> ReturnStatement<'a> {
     pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
-        eat!(tokenizer, Token::Keyword(Keyword::Return))?;
+        let span = eat!(tokenizer, Token::Keyword(Keyword::Return))?.span;
         let return_value = match check_next(tokenizer, Token::Symbol(Symbol::Semicolon)) {
             true => None,
             false => Some(Expression::parse(tokenizer)?),
         };
         eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
 
-        Ok(ReturnStatement { return_value })
+        Ok(ReturnStatement { span, return_value })
     }
 
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         let mut code = match &self.return_value {
             Some(expression) => expression.compile(class, subroutine)?,
@@ -278,4 +480,137 @@ impl<'a> ReturnStatement<'a> {
 
         Ok(code)
     }
+
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        let expected = match subroutine.return_type {
+            ReturnType::Void => None,
+            ReturnType::Int => Some(Type::Int),
+            ReturnType::Char => Some(Type::Char),
+            ReturnType::Boolean => Some(Type::Boolean),
+            ReturnType::Class(class) => Some(Type::Class(class)),
+        };
+
+        match (&self.return_value, expected) {
+            (Some(expression), None) => {
+                expression.check(class, subroutine, errors);
+                errors.push(CompileError::VoidReturn { span: expression.span });
+            }
+            (Some(expression), Some(expected)) => {
+                let actual = expression.check(class, subroutine, errors);
+                if !actual.assignable_to(expected) {
+                    errors.push(CompileError::ReturnType {
+                        expected: ExpressionType::from(expected).to_string(),
+                        actual: actual.to_string(),
+                        span: expression.span,
+                    });
+                }
+            }
+            (None, Some(expected)) => errors.push(CompileError::MissingReturnValue {
+                expected: ExpressionType::from(expected).to_string(),
+                span: self.span,
+            }),
+            (None, None) => {}
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("returnStatement");
+        xml.keyword(Keyword::Return);
+        if let Some(return_value) = &self.return_value {
+            return_value.write_xml(xml);
+        }
+        xml.symbol(Symbol::Semicolon);
+        xml.close("returnStatement");
+    }
+}
+
+/// Conditions must be booleans, though untyped array elements are accepted.
+fn check_condition(
+    condition: &Expression,
+    class: &ClassContext,
+    subroutine: &SubroutineContext,
+    errors: &mut Vec<CompileError<'static>>,
+) {
+    let actual = condition.check(class, subroutine, errors);
+    if !matches!(actual, ExpressionType::Boolean | ExpressionType::Unknown) {
+        errors
+            .push(CompileError::ConditionType { actual: actual.to_string(), span: condition.span });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses `statements` up to a closing `}`, returning the kind of each
+    /// statement (the skipped source for errors) & each error with the source
+    /// it points at.
+    fn parse(statements: &str) -> (Vec<String>, Vec<(String, String)>) {
+        let source = format!("{statements} }}");
+        let mut tokenizer = Tokenizer::new(&source);
+        let mut errors = Vec::default();
+        let statements = parse_statements(&mut tokenizer, &mut errors).unwrap();
+
+        let text = |span: Span| source[span.start..span.end].to_owned();
+        let statements = statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Let(_) => "let".to_owned(),
+                Statement::If(_) => "if".to_owned(),
+                Statement::While(_) => "while".to_owned(),
+                Statement::Do(_) => "do".to_owned(),
+                Statement::Return(_) => "return".to_owned(),
+                Statement::Error(span) => format!("error: {}", text(*span)),
+            })
+            .collect();
+        let errors = errors
+            .iter()
+            .map(|err| (err.to_string(), err.span().map(text).unwrap_or_default()))
+            .collect();
+
+        (statements, errors)
+    }
+
+    #[test]
+    fn misplaced_var() {
+        let (statements, errors) = parse("let x = 1; var int y; let y = 2; return;");
+
+        assert_eq!(statements, ["let", "error: var int y;", "let", "return"]);
+        assert_eq!(errors, [("Unexpected token; token=var".to_owned(), "var".to_owned())]);
+    }
+
+    #[test]
+    fn invalid_tokens() {
+        let (statements, errors) = parse("let x = @; let y = ; let z = 99999; do f(); return;");
+
+        assert_eq!(
+            statements,
+            ["error: let x = @;", "error: let y = ;", "error: let z = 99999;", "do", "return"]
+        );
+        let expected = [
+            ("Invalid token; err=Unexpected character; char=@", "@"),
+            ("Unexpected token; token=;", ";"),
+            ("Invalid token; err=Integer literal out of range", "99999"),
+        ];
+        assert_eq!(errors, expected.map(|(err, source)| (err.to_owned(), source.to_owned())));
+    }
+
+    #[test]
+    fn invalid_tokens_while_synchronizing() {
+        // The `@` is skipped while recovering from the unexpected `2`.
+        let (statements, errors) = parse("let x = 1 2 @; if (x) { let y = \"\u{e9}\"; } return;");
+
+        assert_eq!(statements, ["error: let x = 1 2 @;", "if", "return"]);
+        let expected = [
+            ("Unexpected token; token=2", "2"),
+            ("Invalid token; err=Unexpected character; char=@", "@"),
+            ("Invalid token; err=Invalid character in string literal; char='\u{e9}'", "\u{e9}"),
+        ];
+        assert_eq!(errors, expected.map(|(err, source)| (err.to_owned(), source.to_owned())));
+    }
 }
@@ -1,92 +1,159 @@
+use std::io::Write;
+
 use hashbrown::hash_map::Entry;
 use hashbrown::HashMap;
 
-use crate::code_gen::{ClassContext, CompileError, SymbolEntry, SymbolLocation};
+use crate::code_gen::{ClassContext, CompileError, SubroutineContext, SymbolEntry, SymbolLocation};
 use crate::parser::error::ParseError;
-use crate::parser::statement::Statement;
-use crate::parser::utils::{check_next, eat, peek};
-use crate::tokenizer::{Keyword, SourceToken, Symbol, Token, Tokenizer};
+use crate::parser::statement::{parse_statements, write_statements_xml, Statement};
+use crate::parser::utils::{check_next, eat, synchronize_class, synchronize_statement};
+use crate::parser::xml::XmlWriter;
+use crate::tokenizer::{Keyword, SourceToken, Span, Symbol, Token, Tokenizer};
 
 #[derive(Debug)]
 pub(crate) struct Class<'a> {
     pub(crate) name: &'a str,
+    pub(crate) span: Span,
     pub(crate) variables: Vec<ClassVariableDeclaration<'a>>,
     pub(crate) subroutines: Vec<SubroutineDeclaration<'a>>,
 }
 
 impl<'a> Class<'a> {
-    pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    /// Parses a class, recording recoverable syntax errors in `errors` and
+    /// returning the declarations that could be parsed around them.
+    pub(crate) fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         // All Jack files must contain exactly one class, so lets start by eating the
         // beginning of the class declaration.
         eat!(tokenizer, Token::Keyword(Keyword::Class))?;
         let class_name = eat!(tokenizer, Token::Identifier)?;
         eat!(tokenizer, Token::Symbol(Symbol::LeftBrace))?;
 
-        // Eat all the class variables.
+        // Eat all the class variables & subroutines.
         let mut variables = Vec::default();
-        while matches!(peek(tokenizer), Some(Token::Keyword(Keyword::Static | Keyword::Field))) {
-            // Eat the modifier.
-            let modifier = tokenizer.next().unwrap().unwrap();
-            let modifier = match modifier.token {
-                Token::Keyword(Keyword::Static) => FieldModifier::Static,
-                Token::Keyword(Keyword::Field) => FieldModifier::Field,
-                _ => unreachable!(),
+        let mut subroutines = Vec::default();
+        loop {
+            let st = match tokenizer.peek_0().ok_or(ParseError::UnexpectedEof)? {
+                Ok(st) => st,
+                Err(err) => {
+                    errors.push(err.into());
+                    synchronize_class(tokenizer, errors);
+
+                    continue;
+                }
             };
-
-            // Eat the type.
-            let SourceToken { source, token } =
-                tokenizer.next().ok_or(ParseError::UnexpectedEof)??;
-            let var_type = match token {
-                Token::Keyword(Keyword::Int) => Type::Int,
-                Token::Keyword(Keyword::Char) => Type::Char,
-                Token::Keyword(Keyword::Boolean) => Type::Boolean,
-                Token::Identifier => Type::Class(source),
-                _ => return Err(ParseError::UnexpectedToken(SourceToken { source, token })),
+            let member = match st.token {
+                Token::Keyword(Keyword::Static | Keyword::Field) => {
+                    // Class variables must precede the subroutines.
+                    if !subroutines.is_empty() {
+                        errors.push(ParseError::UnexpectedToken(st));
+                    }
+
+                    ClassVariableDeclaration::parse(tokenizer)
+                        .map(|declaration| variables.push(declaration))
+                }
+                Token::Keyword(Keyword::Constructor | Keyword::Function | Keyword::Method) => {
+                    SubroutineDeclaration::parse(tokenizer, errors)
+                        .map(|subroutine| subroutines.push(subroutine))
+                }
+                Token::Symbol(Symbol::RightBrace) => break,
+                _ => Err(ParseError::UnexpectedToken(st)),
             };
 
-            // Eat the first variable name.
-            let name = eat!(tokenizer, Token::Identifier)?;
-            variables.push(ClassVariableDeclaration { modifier, var_type, name });
-
-            // Eat remaining the variable declarations.
-            while check_next(tokenizer, Token::Symbol(Symbol::Comma)) {
-                // Eat the comma.
-                eat!(tokenizer, Token::Symbol(Symbol::Comma))?;
-
-                // Eat the next variable name.
-                let name = eat!(tokenizer, Token::Identifier)?;
-
-                variables.push(ClassVariableDeclaration { modifier, var_type, name })
+            // Skip to the next member on syntax errors.
+            match member {
+                Ok(()) => {}
+                Err(err) if err.is_recoverable() => {
+                    errors.push(err);
+                    synchronize_class(tokenizer, errors);
+                }
+                Err(err) => return Err(err),
             }
-            eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
-        }
-
-        // Eat all the subroutines.
-        let mut subroutines = Vec::default();
-        while matches!(
-            peek(tokenizer),
-            Some(Token::Keyword(Keyword::Constructor | Keyword::Function | Keyword::Method))
-        ) {
-            // Parse the subroutine body.
-            subroutines.push(SubroutineDeclaration::parse(tokenizer)?);
         }
 
         // Next we eat the body of the class.
-        let class = Class { name: class_name, variables, subroutines };
+        let class =
+            Class { name: class_name.source, span: class_name.span, variables, subroutines };
 
         // Finally we finish up the class declaration.
         eat!(tokenizer, Token::Symbol(Symbol::RightBrace))?;
-        assert!(tokenizer.next().is_none());
+        match tokenizer.next() {
+            Some(Ok(token)) => errors.push(ParseError::TrailingToken(token)),
+            Some(Err(err)) => errors.push(err.into()),
+            None => {}
+        }
 
         Ok(class)
     }
+
+    pub(crate) fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("class");
+        xml.keyword(Keyword::Class);
+        xml.identifier(self.name);
+        xml.symbol(Symbol::LeftBrace);
+        for variable in &self.variables {
+            variable.write_xml(xml);
+        }
+        for subroutine in &self.subroutines {
+            subroutine.write_xml(xml);
+        }
+        xml.symbol(Symbol::RightBrace);
+        xml.close("class");
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct ClassVariableDeclaration<'a> {
     pub(crate) modifier: FieldModifier,
     pub(crate) var_type: Type<'a>,
-    pub(crate) name: &'a str,
+    /// The declared variables & the spans of their names.
+    pub(crate) names: Vec<(&'a str, Span)>,
+}
+
+impl<'a> ClassVariableDeclaration<'a> {
+    /// Parses a `static|field type name (, name)*;` declaration.
+    fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+        // Eat the modifier.
+        let modifier =
+            match eat!(tokenizer, Token::Keyword(Keyword::Static | Keyword::Field))?.token {
+                Token::Keyword(Keyword::Static) => FieldModifier::Static,
+                _ => FieldModifier::Field,
+            };
+
+        // Eat the type.
+        let var_type = Type::parse(tokenizer)?;
+
+        // Eat the first variable name.
+        let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
+        let mut names = vec![(name, span)];
+
+        // Eat remaining the variable declarations.
+        while check_next(tokenizer, Token::Symbol(Symbol::Comma)) {
+            // Eat the comma.
+            eat!(tokenizer, Token::Symbol(Symbol::Comma))?;
+
+            // Eat the next variable name.
+            let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
+            names.push((name, span));
+        }
+        eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
+
+        Ok(ClassVariableDeclaration { modifier, var_type, names })
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("classVarDec");
+        xml.keyword(match self.modifier {
+            FieldModifier::Static => Keyword::Static,
+            FieldModifier::Field => Keyword::Field,
+        });
+        self.var_type.write_xml(xml);
+        write_names_xml(&self.names, xml);
+        xml.symbol(Symbol::Semicolon);
+        xml.close("classVarDec");
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -108,39 +175,46 @@ pub(crate) struct SubroutineDeclaration<'a> {
     pub(crate) subroutine_type: SubroutineType,
     pub(crate) return_type: ReturnType<'a>,
     pub(crate) name: &'a str,
+    pub(crate) span: Span,
     pub(crate) parameters: Vec<ParameterDeclaration<'a>>,
     pub(crate) body: SubroutineBody<'a>,
 }
 
 impl<'a> SubroutineDeclaration<'a> {
-    fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         // Eat the function category.
-        let subroutine_type = tokenizer.next().unwrap()?;
+        let subroutine_type = eat!(
+            tokenizer,
+            Token::Keyword(Keyword::Constructor | Keyword::Function | Keyword::Method)
+        )?;
         let subroutine_type = match subroutine_type.token {
             Token::Keyword(Keyword::Constructor) => SubroutineType::Constructor,
             Token::Keyword(Keyword::Function) => SubroutineType::Function,
-            Token::Keyword(Keyword::Method) => SubroutineType::Method,
-            _ => return Err(ParseError::UnexpectedToken(subroutine_type)),
+            _ => SubroutineType::Method,
         };
 
         // Eat the return type.
-        let SourceToken { source, token } = tokenizer.next().ok_or(ParseError::UnexpectedEof)??;
-        let return_type = match token {
+        let st = tokenizer.peek_0().ok_or(ParseError::UnexpectedEof)??;
+        let return_type = match st.token {
             Token::Keyword(Keyword::Void) => ReturnType::Void,
             Token::Keyword(Keyword::Int) => ReturnType::Int,
             Token::Keyword(Keyword::Char) => ReturnType::Char,
             Token::Keyword(Keyword::Boolean) => ReturnType::Boolean,
-            Token::Identifier => ReturnType::Class(source),
-            _ => return Err(ParseError::UnexpectedToken(SourceToken { source, token })),
+            Token::Identifier => ReturnType::Class(st.source),
+            _ => return Err(ParseError::UnexpectedToken(st)),
         };
+        tokenizer.next();
 
         // Eat the subroutine name.
-        let name = eat!(tokenizer, Token::Identifier)?;
+        let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
 
         // Eat any parameter declarations.
         eat!(tokenizer, Token::Symbol(Symbol::LeftParen))?;
         let mut parameters = Vec::default();
-        let mut more = false;
+        let mut trailing_comma = None;
         loop {
             // If this is not a parameter declaration, we are done.
             if !matches!(
@@ -160,50 +234,56 @@ impl<'a> SubroutineDeclaration<'a> {
             let parameter_type = Type::parse(tokenizer)?;
 
             // Eat the parameter name.
-            let name = eat!(tokenizer, Token::Identifier)?;
+            let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
 
             // Maybe eat a comma.
             let has_comma = matches!(
                 tokenizer.peek_0(),
                 Some(Ok(SourceToken { token: Token::Symbol(Symbol::Comma), .. }))
             );
-            if has_comma {
-                tokenizer.next().unwrap().unwrap();
-            }
-            more = has_comma;
+            trailing_comma = match has_comma {
+                true => Some(tokenizer.next().unwrap().unwrap().span),
+                false => None,
+            };
 
-            parameters.push(ParameterDeclaration { parameter_type, name })
+            parameters.push(ParameterDeclaration { parameter_type, name, span })
         }
-        if more {
-            return Err(ParseError::TrailingComma);
+        if let Some(span) = trailing_comma {
+            return Err(ParseError::TrailingComma(span));
         }
         eat!(tokenizer, Token::Symbol(Symbol::RightParen))?;
 
-        let body = SubroutineBody::parse(tokenizer)?;
+        let body = SubroutineBody::parse(tokenizer, errors)?;
 
-        Ok(SubroutineDeclaration { subroutine_type, return_type, name, parameters, body })
+        Ok(SubroutineDeclaration { subroutine_type, return_type, name, span, parameters, body })
     }
 
-    pub(crate) fn compile(&self, class: &ClassContext) -> Result<Vec<String>, CompileError<'a>> {
-        // Construct the subroutine's nested symbol table.
-        let mut subroutine_symbols: HashMap<&'a str, SymbolEntry<'_>> = HashMap::default();
+    /// Builds the subroutine's nested symbol table.
+    pub(crate) fn context(&self) -> Result<SubroutineContext<'a>, CompileError<'a>> {
+        let mut symbols: HashMap<&'a str, SymbolEntry<'a>> = HashMap::default();
         let params = self
             .parameters
             .iter()
-            .map(|param| (param.name, param.parameter_type, SymbolLocation::Argument))
+            .map(|param| (param.name, param.span, param.parameter_type, SymbolLocation::Argument))
             .enumerate();
         let vars = self
             .body
             .variables
             .iter()
-            .map(|var| (var.name, var.var_type, SymbolLocation::Local))
+            .flat_map(|var| {
+                var.names
+                    .iter()
+                    .map(|&(name, span)| (name, span, var.var_type, SymbolLocation::Local))
+            })
             .enumerate();
-        for (i, (name, symbol_type, location)) in params.chain(vars) {
+        for (i, (name, span, symbol_type, location)) in params.chain(vars) {
             let has_this = self.subroutine_type == SubroutineType::Method
                 && location == SymbolLocation::Argument;
 
-            match subroutine_symbols.entry(name) {
-                Entry::Occupied(_) => return Err(CompileError::DuplicateSymbol(name)),
+            match symbols.entry(name) {
+                Entry::Occupied(_) => {
+                    return Err(CompileError::DuplicateSymbol { symbol: name, span })
+                }
                 Entry::Vacant(entry) => entry.insert(SymbolEntry {
                     symbol_type,
                     location,
@@ -212,9 +292,20 @@ impl<'a> SubroutineDeclaration<'a> {
             };
         }
 
+        Ok(SubroutineContext {
+            subroutine_type: self.subroutine_type,
+            return_type: self.return_type,
+            symbols,
+        })
+    }
+
+    pub(crate) fn compile(&self, class: &ClassContext) -> Result<Vec<String>, CompileError<'a>> {
+        let subroutine = self.context()?;
+
         // Function boilerplate.
         let mut code = Vec::default();
-        code.push(format!("function {}.{} {}", class.name, self.name, self.body.variables.len()));
+        let locals: usize = self.body.variables.iter().map(|var| var.names.len()).sum();
+        code.push(format!("function {}.{} {locals}", class.name, self.name));
         match self.subroutine_type {
             SubroutineType::Method => {
                 code.push("push argument 0".to_string());
@@ -229,10 +320,42 @@ impl<'a> SubroutineDeclaration<'a> {
         }
 
         // Function body.
-        code.extend(self.body.compile(class, &subroutine_symbols)?);
+        code.extend(self.body.compile(class, &subroutine)?);
 
         Ok(code)
     }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("subroutineDec");
+        xml.keyword(match self.subroutine_type {
+            SubroutineType::Constructor => Keyword::Constructor,
+            SubroutineType::Function => Keyword::Function,
+            SubroutineType::Method => Keyword::Method,
+        });
+        match self.return_type {
+            ReturnType::Void => xml.keyword(Keyword::Void),
+            ReturnType::Int => xml.keyword(Keyword::Int),
+            ReturnType::Char => xml.keyword(Keyword::Char),
+            ReturnType::Boolean => xml.keyword(Keyword::Boolean),
+            ReturnType::Class(class) => xml.identifier(class),
+        }
+        xml.identifier(self.name);
+
+        xml.symbol(Symbol::LeftParen);
+        xml.open("parameterList");
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            if i > 0 {
+                xml.symbol(Symbol::Comma);
+            }
+            parameter.parameter_type.write_xml(xml);
+            xml.identifier(parameter.name);
+        }
+        xml.close("parameterList");
+        xml.symbol(Symbol::RightParen);
+
+        self.body.write_xml(xml);
+        xml.close("subroutineDec");
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -255,18 +378,30 @@ pub(crate) enum ReturnType<'a> {
 pub(crate) struct ParameterDeclaration<'a> {
     pub(crate) parameter_type: Type<'a>,
     pub(crate) name: &'a str,
+    pub(crate) span: Span,
 }
 
 impl<'a> Type<'a> {
     pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
-        let st = tokenizer.next().ok_or(ParseError::UnexpectedEof)??;
-
-        match st.token {
-            Token::Keyword(Keyword::Int) => Ok(Self::Int),
-            Token::Keyword(Keyword::Char) => Ok(Self::Char),
-            Token::Keyword(Keyword::Boolean) => Ok(Self::Boolean),
-            Token::Identifier => Ok(Self::Class(st.source)),
-            _ => Err(ParseError::UnexpectedToken(st)),
+        let st = tokenizer.peek_0().ok_or(ParseError::UnexpectedEof)??;
+        let parsed = match st.token {
+            Token::Keyword(Keyword::Int) => Self::Int,
+            Token::Keyword(Keyword::Char) => Self::Char,
+            Token::Keyword(Keyword::Boolean) => Self::Boolean,
+            Token::Identifier => Self::Class(st.source),
+            _ => return Err(ParseError::UnexpectedToken(st)),
+        };
+        tokenizer.next();
+
+        Ok(parsed)
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        match self {
+            Self::Int => xml.keyword(Keyword::Int),
+            Self::Char => xml.keyword(Keyword::Char),
+            Self::Boolean => xml.keyword(Keyword::Boolean),
+            Self::Class(class) => xml.identifier(class),
         }
     }
 }
@@ -278,35 +413,28 @@ pub(crate) struct SubroutineBody<'a> {
 }
 
 impl<'a> SubroutineBody<'a> {
-    pub(crate) fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+    pub(crate) fn parse(
+        tokenizer: &mut Tokenizer<'a>,
+        errors: &mut Vec<ParseError<'a>>,
+    ) -> Result<Self, ParseError<'a>> {
         eat!(tokenizer, Token::Symbol(Symbol::LeftBrace))?;
 
-        // Eat all variable declarations.
+        // Eat all variable declarations, skipping to the next statement on syntax
+        // errors.
         let mut variables = Vec::default();
         while check_next(tokenizer, Token::Keyword(Keyword::Var)) {
-            // Eat the first variable.
-            eat!(tokenizer, Token::Keyword(Keyword::Var))?;
-            let var_type = Type::parse(tokenizer)?;
-            let name = eat!(tokenizer, Token::Identifier)?;
-
-            // Eat the remaining variables.
-            variables.push(SubroutineVariableDeclaration { var_type, name });
-            while check_next(tokenizer, Token::Symbol(Symbol::Comma)) {
-                eat!(tokenizer, Token::Symbol(Symbol::Comma))?;
-                variables.push(SubroutineVariableDeclaration {
-                    var_type,
-                    name: eat!(tokenizer, Token::Identifier)?,
-                });
+            match SubroutineVariableDeclaration::parse(tokenizer) {
+                Ok(declaration) => variables.push(declaration),
+                Err(err) if err.is_recoverable() => {
+                    errors.push(err);
+                    synchronize_statement(tokenizer, errors);
+                }
+                Err(err) => return Err(err),
             }
-
-            eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
         }
 
         // Eat all statements.
-        let mut statements = Vec::default();
-        while !check_next(tokenizer, Token::Symbol(Symbol::RightBrace)) {
-            statements.push(Statement::parse(tokenizer)?);
-        }
+        let statements = parse_statements(tokenizer, errors)?;
         eat!(tokenizer, Token::Symbol(Symbol::RightBrace))?;
 
         Ok(SubroutineBody { variables, statements })
@@ -315,7 +443,7 @@ impl<'a> SubroutineBody<'a> {
     pub(crate) fn compile(
         &self,
         class: &ClassContext,
-        subroutine: &HashMap<&str, SymbolEntry>,
+        subroutine: &SubroutineContext,
     ) -> Result<Vec<String>, CompileError<'a>> {
         Ok(self
             .statements
@@ -326,10 +454,103 @@ impl<'a> SubroutineBody<'a> {
             .flatten()
             .collect())
     }
+    pub(crate) fn check(
+        &self,
+        class: &ClassContext,
+        subroutine: &SubroutineContext,
+        errors: &mut Vec<CompileError<'static>>,
+    ) {
+        for statement in &self.statements {
+            statement.check(class, subroutine, errors);
+        }
+    }
+
+    fn write_xml(&self, xml: &mut XmlWriter<impl Write>) {
+        xml.open("subroutineBody");
+        xml.symbol(Symbol::LeftBrace);
+        for variable in &self.variables {
+            xml.open("varDec");
+            xml.keyword(Keyword::Var);
+            variable.var_type.write_xml(xml);
+            write_names_xml(&variable.names, xml);
+            xml.symbol(Symbol::Semicolon);
+            xml.close("varDec");
+        }
+        write_statements_xml(&self.statements, xml);
+        xml.symbol(Symbol::RightBrace);
+        xml.close("subroutineBody");
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct SubroutineVariableDeclaration<'a> {
     pub(crate) var_type: Type<'a>,
-    pub(crate) name: &'a str,
+    /// The declared variables & the spans of their names.
+    pub(crate) names: Vec<(&'a str, Span)>,
+}
+
+impl<'a> SubroutineVariableDeclaration<'a> {
+    /// Parses a `var type name (, name)*;` declaration.
+    fn parse(tokenizer: &mut Tokenizer<'a>) -> Result<Self, ParseError<'a>> {
+        // Eat the first variable.
+        eat!(tokenizer, Token::Keyword(Keyword::Var))?;
+        let var_type = Type::parse(tokenizer)?;
+        let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
+
+        // Eat the remaining variables.
+        let mut names = vec![(name, span)];
+        while check_next(tokenizer, Token::Symbol(Symbol::Comma)) {
+            eat!(tokenizer, Token::Symbol(Symbol::Comma))?;
+            let SourceToken { source: name, span, .. } = eat!(tokenizer, Token::Identifier)?;
+            names.push((name, span));
+        }
+        eat!(tokenizer, Token::Symbol(Symbol::Semicolon))?;
+
+        Ok(SubroutineVariableDeclaration { var_type, names })
+    }
+}
+
+/// Writes the comma separated names of a variable declaration.
+fn write_names_xml(names: &[(&str, Span)], xml: &mut XmlWriter<impl Write>) {
+    for (i, (name, _)) in names.iter().enumerate() {
+        if i > 0 {
+            xml.symbol(Symbol::Comma);
+        }
+        xml.identifier(name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_tokens_between_members() {
+        let source = "class Main { @ field int x; # function void f() { let x = $; return; } }";
+        let mut errors = Vec::default();
+        let class = Class::parse(&mut Tokenizer::new(source), &mut errors).unwrap();
+
+        assert_eq!(class.variables.len(), 1);
+        assert_eq!(class.subroutines.len(), 1);
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|err| err.span().map(|span| &source[span.start..span.end]))
+            .collect();
+        assert_eq!(errors, [Some("@"), Some("#"), Some("$")]);
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        for (source, expected) in [
+            ("class Main {} class Other {}", "Unexpected token after class; token=class"),
+            ("class Main {} @", "Invalid token; err=Unexpected character; char=@"),
+        ] {
+            let mut errors = Vec::default();
+            let class = Class::parse(&mut Tokenizer::new(source), &mut errors).unwrap();
+
+            assert_eq!(class.name, "Main");
+            let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+            assert_eq!(errors, [expected], "{source}");
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use crate::parser::error::ParseError;
-use crate::tokenizer::{Token, Tokenizer};
+use crate::tokenizer::{Keyword, Symbol, Token, Tokenizer};
 
 pub(crate) fn next<'a>(tokenizer: &mut Tokenizer<'a>) -> Result<Token, ParseError<'a>> {
     Ok(tokenizer.next().ok_or(ParseError::UnexpectedEof)??.token)
@@ -19,16 +19,93 @@ pub(crate) fn check_next(tokenizer: &mut Tokenizer, expected: Token) -> bool {
     st.token == expected
 }
 
+/// Peeks the next valid token, consuming the invalid ones before it & recording
+/// them in `errors`.
+fn peek_valid<'a>(
+    tokenizer: &mut Tokenizer<'a>,
+    errors: &mut Vec<ParseError<'a>>,
+) -> Option<Token> {
+    loop {
+        match tokenizer.peek_0()? {
+            Ok(st) => return Some(st.token),
+            Err(err) => {
+                // The parser may have stopped at this token, recording it already.
+                if !matches!(errors.last(), Some(ParseError::InvalidToken(last)) if *last == err) {
+                    errors.push(err.into());
+                }
+                tokenizer.next();
+            }
+        }
+    }
+}
+
+/// Skips tokens after a syntax error in a subroutine body until the next
+/// statement: past a `;`, or up to a statement keyword or `}`.
+pub(crate) fn synchronize_statement<'a>(
+    tokenizer: &mut Tokenizer<'a>,
+    errors: &mut Vec<ParseError<'a>>,
+) {
+    while let Some(token) = peek_valid(tokenizer, errors) {
+        if matches!(
+            token,
+            Token::Keyword(
+                Keyword::Let
+                    | Keyword::Do
+                    | Keyword::If
+                    | Keyword::While
+                    | Keyword::Return
+                    | Keyword::Var
+            ) | Token::Symbol(Symbol::RightBrace)
+        ) {
+            return;
+        }
+
+        tokenizer.next();
+        if token == Token::Symbol(Symbol::Semicolon) {
+            return;
+        }
+    }
+}
+
+/// Skips tokens after a syntax error in a class until the next member
+/// declaration or the `}` closing the class.
+pub(crate) fn synchronize_class<'a>(
+    tokenizer: &mut Tokenizer<'a>,
+    errors: &mut Vec<ParseError<'a>>,
+) {
+    let mut depth = 0usize;
+    while let Some(token) = peek_valid(tokenizer, errors) {
+        match token {
+            Token::Keyword(
+                Keyword::Static
+                | Keyword::Field
+                | Keyword::Constructor
+                | Keyword::Function
+                | Keyword::Method,
+            ) => return,
+            Token::Symbol(Symbol::RightBrace) if depth == 0 => return,
+            Token::Symbol(Symbol::RightBrace) => depth -= 1,
+            Token::Symbol(Symbol::LeftBrace) => depth += 1,
+            _ => {}
+        }
+
+        tokenizer.next();
+    }
+}
+
+/// Eats the next token if it matches `$expected`, the token is left in place
+/// otherwise so the parser can resume from it.
 macro_rules! eat {
     ($tokenizer:expr, $expected:pat) => {{
-        let $crate::tokenizer::SourceToken { source, token } = $tokenizer.next().unwrap()?;
-        if !matches!(token, $expected) {
-            return Err($crate::parser::error::ParseError::UnexpectedToken(
-                $crate::tokenizer::SourceToken { source, token },
-            ));
+        let token = $tokenizer
+            .peek_0()
+            .ok_or($crate::parser::error::ParseError::UnexpectedEof)??;
+        if !matches!(token.token, $expected) {
+            return Err($crate::parser::error::ParseError::UnexpectedToken(token));
         }
+        $tokenizer.next();
 
-        Ok::<_, $crate::parser::error::ParseError>(source)
+        Ok::<_, $crate::parser::error::ParseError>(token)
     }};
 }
 
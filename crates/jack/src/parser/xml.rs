@@ -0,0 +1,60 @@
+use std::io::Write;
+
+use crate::tokenizer::{Keyword, Symbol};
+
+/// Writes the parse tree XML of the nand2tetris reference files, one element
+/// per line indented by two spaces per level.
+pub(crate) struct XmlWriter<W> {
+    wx: W,
+    depth: usize,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub(crate) fn new(wx: W) -> Self {
+        Self { wx, depth: 0 }
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.wx
+    }
+
+    pub(crate) fn open(&mut self, tag: &str) {
+        self.line(&format!("<{tag}>"));
+        self.depth += 1;
+    }
+
+    pub(crate) fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{tag}>"));
+    }
+
+    pub(crate) fn keyword(&mut self, keyword: Keyword) {
+        self.line(&format!("<keyword> {} </keyword>", <&str>::from(keyword)));
+    }
+
+    pub(crate) fn symbol(&mut self, symbol: Symbol) {
+        self.line(&format!("<symbol> {} </symbol>", escape(symbol.into())));
+    }
+
+    pub(crate) fn identifier(&mut self, identifier: &str) {
+        self.line(&format!("<identifier> {identifier} </identifier>"));
+    }
+
+    pub(crate) fn integer_constant(&mut self, integer: i16) {
+        self.line(&format!("<integerConstant> {integer} </integerConstant>"));
+    }
+
+    pub(crate) fn string_constant(&mut self, string: &str) {
+        self.line(&format!("<stringConstant> {} </stringConstant>", escape(string)));
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.wx, "{:indent$}{line}", "", indent = self.depth * 2).unwrap();
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -8,38 +8,63 @@ use thiserror::Error;
 
 pub(crate) struct Tokenizer<'a> {
     source: &'a str,
-    errored: bool,
     peeked: [Option<Option<Result<SourceToken<'a>, TokenizeError>>>; 2],
+    /// The byte offset of `source` within the original source.
+    offset: usize,
+    line: usize,
+    /// The byte offset of the start of the current line.
+    line_start: usize,
+    /// The span of the last token returned by [`Tokenizer::next`].
+    previous: Span,
 }
 
 impl<'a> Tokenizer<'a> {
     pub(crate) fn new(source: &'a str) -> Self {
-        Self { source, errored: false, peeked: [None; 2] }
+        Self {
+            source,
+            peeked: [None; 2],
+            offset: 0,
+            line: 1,
+            line_start: 0,
+            previous: Span::default(),
+        }
     }
 
     pub(crate) fn remaining(&self) -> &'a str {
         self.source
     }
 
+    /// The span of the most recently consumed token.
+    pub(crate) fn previous(&self) -> Span {
+        self.previous
+    }
+
     pub(crate) fn next(&mut self) -> Option<Result<SourceToken<'a>, TokenizeError>> {
         // Return a previously peeked value if one exists.
-        if let Some(peeked) = self.peeked[0] {
-            self.peeked[0] = self.peeked[1];
-            self.peeked[1] = None;
+        let next = match self.peeked[0] {
+            Some(peeked) => {
+                self.peeked[0] = self.peeked[1];
+                self.peeked[1] = None;
 
-            return peeked;
+                peeked
+            }
+            None => self.read(),
+        };
+        if let Some(Ok(token)) = next {
+            self.previous = token.span;
         }
 
-        // Fuse ourselves if we have errored to prevent callers from suppressing errors
-        // and infinite looping.
-        if self.errored {
-            return None;
-        }
+        next
+    }
 
+    /// Reads the next token, errors skip the offending source so tokenizing
+    /// resumes after it.
+    fn read(&mut self) -> Option<Result<SourceToken<'a>, TokenizeError>> {
         // Loop until we are done or find a valid token.
         loop {
             // Strip any whitespace left over after parsing the previous iteration.
-            self.source = self.source.trim_ascii_start();
+            let whitespace = self.source.len() - self.source.trim_ascii_start().len();
+            self.advance(whitespace);
 
             // If we have no source left we are done.
             if self.source.is_empty() {
@@ -49,24 +74,15 @@ impl<'a> Tokenizer<'a> {
             // If this is an empty line, skip it.
             let source = self.source.as_bytes();
             if source[0] == b'\n' {
-                // SAFETY: As we are trimming an ASCII byte, there is no possibility of UTF-8
-                // char splitting.
-                self.source =
-                    unsafe { core::str::from_utf8_unchecked(&self.source.as_bytes()[1..]) };
+                self.advance(1);
 
                 continue;
             }
 
             // If this is a single line comment, skip it.
             if source.get(0..2).is_some_and(|chars| chars == b"//") {
-                self.source = source.iter().position(|byte| byte == &b'\n').map_or(
-                    &self.source[0..0],
-                    |pos| {
-                        // SAFETY: As we have found a valid ASCII byte, we can be sure this byte
-                        // does not belong to some multi-byte UTF-8 char.
-                        unsafe { core::str::from_utf8_unchecked(&self.source.as_bytes()[pos..]) }
-                    },
-                );
+                let end = source.iter().position(|byte| byte == &b'\n');
+                self.advance(end.unwrap_or(source.len()));
 
                 continue;
             }
@@ -74,17 +90,12 @@ impl<'a> Tokenizer<'a> {
             // If this is a doc comment comment, skip it.
             if source.get(0..3).is_some_and(|chars| chars == b"/**") {
                 let Some(end) = source[3..].windows(2).position(|window| window == b"*/") else {
-                    self.errored = true;
-
-                    return Some(Err(TokenizeError::UnclosedComment));
-                };
+                    let span = self.span(3);
+                    self.advance(source.len());
 
-                // SAFETY: This is safe because the end character is in ASCII which cannot be
-                // present in a multi-byte UTF-8 character, thus no character splitting can
-                // occur.
-                self.source = unsafe {
-                    core::str::from_utf8_unchecked(&self.source.as_bytes()[(3 + end + 2)..])
+                    return Some(Err(TokenizeError::UnclosedComment(span)));
                 };
+                self.advance(3 + end + 2);
 
                 continue;
             }
@@ -106,15 +117,20 @@ impl<'a> Tokenizer<'a> {
 
             // Try eat an integer literal.
             if let Some(token) = self.try_parse_integer_literal() {
-                return Some(Ok(token));
+                return Some(token);
             }
 
             // Try eat a string literal.
             if let Some(token) = self.try_parse_string_literal() {
-                return Some(Ok(token));
+                return Some(token);
             }
 
-            todo!("Could not parse a token");
+            // Nothing in Jack starts with this character.
+            let char = self.source.chars().next().unwrap();
+            let span = self.span(char.len_utf8());
+            self.advance(char.len_utf8());
+
+            return Some(Err(TokenizeError::UnexpectedCharacter { char, span }));
         }
     }
 
@@ -123,19 +139,55 @@ impl<'a> Tokenizer<'a> {
             return peeked;
         }
 
-        let next = self.next();
+        let next = self.read();
         self.peeked[0] = Some(next);
 
         next
     }
 
     pub(crate) fn peek_1(&mut self) -> Option<Result<SourceToken<'a>, TokenizeError>> {
-        let peek_0 = self.next();
-        let peek_1 = self.next();
-        self.peeked[0] = Some(peek_0);
-        self.peeked[1] = Some(peek_1);
+        self.peek_0();
+        if let Some(peeked) = self.peeked[1] {
+            return peeked;
+        }
+
+        let next = self.read();
+        self.peeked[1] = Some(next);
 
-        peek_1
+        next
+    }
+
+    /// The span of the next `len` bytes of source.
+    fn span(&self, len: usize) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset + len,
+            line: self.line,
+            column: self.offset - self.line_start + 1,
+        }
+    }
+
+    /// Consumes `len` bytes of source, tracking our line & column.
+    fn advance(&mut self, len: usize) {
+        let (consumed, rest) = self.source.split_at(len);
+        for (i, _) in consumed
+            .bytes()
+            .enumerate()
+            .filter(|(_, byte)| byte == &b'\n')
+        {
+            self.line += 1;
+            self.line_start = self.offset + i + 1;
+        }
+        self.source = rest;
+        self.offset += len;
+    }
+
+    /// Consumes a `len` byte token.
+    fn token(&mut self, len: usize, token: Token) -> SourceToken<'a> {
+        let token = SourceToken { source: &self.source[..len], token, span: self.span(len) };
+        self.advance(len);
+
+        token
     }
 
     fn try_parse_symbol(&mut self) -> Option<SourceToken<'a>> {
@@ -164,13 +216,7 @@ impl<'a> Tokenizer<'a> {
             _ => return None,
         };
 
-        // Construct our token.
-        let token = SourceToken { source: &self.source[..1], token: Token::Symbol(symbol) };
-
-        // Advance our position in the source.
-        self.source = &self.source[1..];
-
-        Some(token)
+        Some(self.token(1, Token::Symbol(symbol)))
     }
 
     fn try_parse_keyword(&mut self) -> Option<SourceToken<'a>> {
@@ -212,14 +258,7 @@ impl<'a> Tokenizer<'a> {
             _ => return None,
         };
 
-        // Construct our token.
-        let token =
-            SourceToken { source: &self.source[..word.len()], token: Token::Keyword(keyword) };
-
-        // Advance our position in the source.
-        self.source = &self.source[word.len()..];
-
-        Some(token)
+        Some(self.token(word.len(), Token::Keyword(keyword)))
     }
 
     fn try_parse_identifier(&mut self) -> Option<SourceToken<'a>> {
@@ -246,18 +285,10 @@ impl<'a> Tokenizer<'a> {
             return None;
         }
 
-        // Construct our token.
-        let token = SourceToken { source: identifier, token: Token::Identifier };
-
-        // SAFETY: As we know `word` to be valid UTF8, we can safely trim the entire
-        // word without splitting a UTF-8 char boundary.
-        self.source =
-            unsafe { core::str::from_utf8_unchecked(&self.source.as_bytes()[identifier.len()..]) };
-
-        Some(token)
+        Some(self.token(identifier.len(), Token::Identifier))
     }
 
-    fn try_parse_integer_literal(&mut self) -> Option<SourceToken<'a>> {
+    fn try_parse_integer_literal(&mut self) -> Option<Result<SourceToken<'a>, TokenizeError>> {
         debug_assert!(self.source.as_bytes()[0] != b' ');
 
         // Integer literals must contain only digits.
@@ -280,26 +311,19 @@ impl<'a> Tokenizer<'a> {
         }
 
         // Jack integers are 16bit signed values but only the 0 & positive integers are
-        // usable, thus the range is 0..2**15.
+        // usable, thus the range is 0..2**15. As the literal contains only digits, it
+        // cannot be negative.
         let Ok(literal) = literal_s.parse::<i16>() else {
-            todo!("Invalid jack integer literal");
-        };
+            let span = self.span(literal_s.len());
+            self.advance(literal_s.len());
 
-        // Jack integers literals cannot be negative.
-        assert!(!literal.is_negative());
-
-        // Construct our token.
-        let token = SourceToken { source: literal_s, token: Token::IntegerConstant(literal) };
-
-        // SAFETY: As we know `word` to be valid UTF8, we can safely trim the entire
-        // word without splitting a UTF-8 char boundary.
-        self.source =
-            unsafe { core::str::from_utf8_unchecked(&self.source.as_bytes()[literal_s.len()..]) };
+            return Some(Err(TokenizeError::IntegerOverflow(span)));
+        };
 
-        Some(token)
+        Some(Ok(self.token(literal_s.len(), Token::IntegerConstant(literal))))
     }
 
-    fn try_parse_string_literal(&mut self) -> Option<SourceToken<'a>> {
+    fn try_parse_string_literal(&mut self) -> Option<Result<SourceToken<'a>, TokenizeError>> {
         debug_assert!(self.source.as_bytes()[0] != b' ');
 
         // String literals must start with a double quote.
@@ -321,41 +345,104 @@ impl<'a> Tokenizer<'a> {
             Some(end) => unsafe {
                 core::str::from_utf8_unchecked(&self.source.as_bytes()[..(end + 2)])
             },
-            None => self.source,
+            None => {
+                let line = self.source.lines().next().unwrap_or_default();
+                let span = self.span(line.len());
+                self.advance(line.len());
+
+                return Some(Err(TokenizeError::UnclosedString(span)));
+            }
         };
-        assert!(!literal.is_empty());
 
-        if literal.bytes().any(|byte| byte == b'\n') {
-            todo!("Jack specification forbids newlines in string literals");
+        // Jack specification forbids newlines in string literals, the next line
+        // is more likely code than the rest of the string.
+        if let Some(newline) = literal.bytes().position(|byte| byte == b'\n') {
+            let span = self.span(newline);
+            self.advance(newline);
+
+            return Some(Err(TokenizeError::NewlineInString(span)));
         }
 
-        // Construct our token.
-        let token = SourceToken { source: literal, token: Token::StringConstant };
+        // The Hack character set only has the printable ASCII characters.
+        let content = &literal[1..literal.len() - 1];
+        if let Some((i, char)) = content
+            .char_indices()
+            .find(|(_, char)| !(' '..='~').contains(char))
+        {
+            let start = self.offset + 1 + i;
+            let span = Span {
+                start,
+                end: start + char.len_utf8(),
+                line: self.line,
+                column: start - self.line_start + 1,
+            };
+
+            self.advance(literal.len());
 
-        // SAFETY: As we know `word` to be valid UTF8, we can safely trim the entire
-        // word without splitting a UTF-8 char boundary.
-        self.source =
-            unsafe { core::str::from_utf8_unchecked(&self.source.as_bytes()[literal.len()..]) };
+            return Some(Err(TokenizeError::InvalidStringCharacter { char, span }));
+        }
 
-        Some(token)
+        Some(Ok(self.token(literal.len(), Token::StringConstant)))
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
 pub(crate) enum TokenizeError {
     #[error("Unclosed comment")]
-    UnclosedComment,
+    UnclosedComment(Span),
+    #[error("Unexpected character; char={char}")]
+    UnexpectedCharacter { char: char, span: Span },
+    #[error("Integer literal out of range")]
+    IntegerOverflow(Span),
+    #[error("Unclosed string literal")]
+    UnclosedString(Span),
+    #[error("Newline in string literal")]
+    NewlineInString(Span),
+    #[error("Invalid character in string literal; char={char:?}")]
+    InvalidStringCharacter { char: char, span: Span },
+}
+
+impl TokenizeError {
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Self::UnclosedComment(span)
+            | Self::UnexpectedCharacter { span, .. }
+            | Self::IntegerOverflow(span)
+            | Self::UnclosedString(span)
+            | Self::NewlineInString(span)
+            | Self::InvalidStringCharacter { span, .. } => *span,
+        }
+    }
+}
+
+/// A region of the source file, lines & columns start at 1.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    /// The byte offset of the first byte.
+    pub(crate) start: usize,
+    /// The byte offset one past the last byte.
+    pub(crate) end: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl Span {
+    /// The span covering both `self` and a later `other`.
+    pub(crate) fn to(self, other: Span) -> Span {
+        Span { end: other.end.max(self.end), ..self }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) struct SourceToken<'a> {
     pub(crate) source: &'a str,
     pub(crate) token: Token,
+    pub(crate) span: Span,
 }
 
 impl<'a> SourceToken<'a> {
     pub(crate) fn write_xml(&self, wx: &mut impl Write) {
-        let Self { source, token } = self;
+        let Self { source, token, .. } = self;
 
         match token {
             Token::Keyword(_) => {
@@ -396,6 +483,7 @@ pub(crate) enum Token {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
 pub(crate) enum Keyword {
     Class,
     Constructor,
@@ -428,24 +516,43 @@ impl Keyword {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr)]
 pub(crate) enum Symbol {
+    #[strum(to_string = "{")]
     LeftBrace,
+    #[strum(to_string = "}")]
     RightBrace,
+    #[strum(to_string = "(")]
     LeftParen,
+    #[strum(to_string = ")")]
     RightParen,
+    #[strum(to_string = "[")]
     LeftBracket,
+    #[strum(to_string = "]")]
     RightBracket,
+    #[strum(to_string = ".")]
     Dot,
+    #[strum(to_string = ",")]
     Comma,
+    #[strum(to_string = ";")]
     Semicolon,
+    #[strum(to_string = "+")]
     Plus,
+    #[strum(to_string = "-")]
     Minus,
+    #[strum(to_string = "*")]
     Asterisk,
+    #[strum(to_string = "/")]
     ForwardSlash,
+    #[strum(to_string = "&")]
     Ampersand,
+    #[strum(to_string = "|")]
     Pipe,
+    #[strum(to_string = "<")]
     LeftAngleBracket,
+    #[strum(to_string = ">")]
     RightAngleBracket,
+    #[strum(to_string = "=")]
     Equals,
+    #[strum(to_string = "~")]
     Tilde,
 }
 
@@ -454,3 +561,96 @@ impl Symbol {
         wx.write_all(<&str>::from(self).as_bytes()).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tokenizes `source`, returning the source of each token or the error &
+    /// the source it points at.
+    fn tokenize(source: &str) -> Vec<Result<&str, (String, &str)>> {
+        let mut tokenizer = Tokenizer::new(source);
+        std::iter::from_fn(|| tokenizer.next())
+            .map(|token| {
+                token
+                    .map(|token| token.source)
+                    .map_err(|err| (err.to_string(), &source[err.span().start..err.span().end]))
+            })
+            .collect()
+    }
+
+    fn error<'a>(message: &str, source: &'a str) -> Result<&'a str, (String, &'a str)> {
+        Err((message.to_owned(), source))
+    }
+
+    #[test]
+    fn unexpected_character() {
+        assert_eq!(
+            tokenize("let x = @#;"),
+            [
+                Ok("let"),
+                Ok("x"),
+                Ok("="),
+                error("Unexpected character; char=@", "@"),
+                error("Unexpected character; char=#", "#"),
+                Ok(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn integer_overflow() {
+        assert_eq!(
+            tokenize("32767 32768 99999;"),
+            [
+                Ok("32767"),
+                error("Integer literal out of range", "32768"),
+                error("Integer literal out of range", "99999"),
+                Ok(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn newline_in_string() {
+        // The next line is tokenized as code.
+        assert_eq!(
+            tokenize("let s = \"abc\nx\";"),
+            [
+                Ok("let"),
+                Ok("s"),
+                Ok("="),
+                error("Newline in string literal", "\"abc"),
+                Ok("x"),
+                error("Unclosed string literal", "\";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_string() {
+        assert_eq!(
+            tokenize("let s = \"abc;"),
+            [Ok("let"), Ok("s"), Ok("="), error("Unclosed string literal", "\"abc;")]
+        );
+    }
+
+    #[test]
+    fn invalid_string_character() {
+        // The rest of the string is skipped.
+        assert_eq!(
+            tokenize("\"a\u{e9}b\" \"\tc\" \"~\";"),
+            [
+                error("Invalid character in string literal; char='\u{e9}'", "\u{e9}"),
+                error("Invalid character in string literal; char='\\t'", "\t"),
+                Ok("\"~\""),
+                Ok(";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_comment() {
+        assert_eq!(tokenize("x /** y; z"), [Ok("x"), error("Unclosed comment", "/**")]);
+    }
+}
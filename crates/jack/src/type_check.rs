@@ -0,0 +1,292 @@
+use std::fmt::Display;
+use std::sync::atomic::AtomicU64;
+
+use crate::code_gen::{ClassContext, CompileError, ProgramContext};
+use crate::parser::structure::{Class, ReturnType, Type};
+
+/// How type errors are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::VariantNames)]
+#[strum(serialize_all = "kebab-case")]
+pub(crate) enum TypeCheck {
+    Off,
+    Warn,
+    Deny,
+}
+
+/// Type checks every subroutine in `class`, returning the type errors found.
+///
+/// Symbol resolution errors are left for code generation to report.
+pub(crate) fn check<'a>(
+    program: &'a ProgramContext<'a>,
+    class: &Class<'a>,
+) -> Result<Vec<CompileError<'static>>, CompileError<'a>> {
+    // Type checking allocates no labels.
+    static LABELS: AtomicU64 = AtomicU64::new(0);
+    let context = ClassContext::new(&LABELS, program, class)?;
+
+    let mut errors = Vec::default();
+    for subroutine in &class.subroutines {
+        let name = format!("{}.{}", class.name, subroutine.name);
+        let subroutine_context = subroutine
+            .context()
+            .map_err(|err| CompileError::Subroutine {
+                subroutine: name.clone(),
+                err: Box::new(err),
+            })?;
+
+        let mut subroutine_errors = Vec::default();
+        subroutine
+            .body
+            .check(&context, &subroutine_context, &mut subroutine_errors);
+        errors.extend(
+            subroutine_errors
+                .into_iter()
+                .map(|err| CompileError::Subroutine {
+                    subroutine: name.clone(),
+                    err: Box::new(err),
+                }),
+        );
+    }
+
+    Ok(errors)
+}
+
+/// The type of an expression, as far as it can be determined statically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExpressionType<'a> {
+    Int,
+    Char,
+    Boolean,
+    Class(&'a str),
+    Null,
+    Void,
+    /// Array elements are untyped.
+    Unknown,
+}
+
+impl<'a> ExpressionType<'a> {
+    /// Whether a value of this type can be stored in a `target` variable.
+    ///
+    /// Jack is weakly typed: `int` & `char` are interchangeable, `null` is any
+    /// object and `Array` is an untyped pointer that converts to and from any
+    /// object or integer.
+    pub(crate) fn assignable_to(self, target: Type) -> bool {
+        match (self, target) {
+            (Self::Unknown, _) => true,
+            (Self::Void, _) => false,
+            (Self::Int | Self::Char, Type::Int | Type::Char) => true,
+            (Self::Boolean, Type::Boolean) => true,
+            (Self::Null, Type::Class(_)) => true,
+            (Self::Int | Self::Char | Self::Class(_), Type::Class("Array")) => true,
+            (Self::Class("Array"), Type::Int | Type::Class(_)) => true,
+            (Self::Class(class), Type::Class(target)) => class == target,
+            _ => false,
+        }
+    }
+}
+
+impl<'a> From<Type<'a>> for ExpressionType<'a> {
+    fn from(value: Type<'a>) -> Self {
+        match value {
+            Type::Int => Self::Int,
+            Type::Char => Self::Char,
+            Type::Boolean => Self::Boolean,
+            Type::Class(class) => Self::Class(class),
+        }
+    }
+}
+
+impl<'a> From<ReturnType<'a>> for ExpressionType<'a> {
+    fn from(value: ReturnType<'a>) -> Self {
+        match value {
+            ReturnType::Void => Self::Void,
+            ReturnType::Int => Self::Int,
+            ReturnType::Char => Self::Char,
+            ReturnType::Boolean => Self::Boolean,
+            ReturnType::Class(class) => Self::Class(class),
+        }
+    }
+}
+
+impl Display for ExpressionType<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int => write!(f, "int"),
+            Self::Char => write!(f, "char"),
+            Self::Boolean => write!(f, "boolean"),
+            Self::Class(class) => write!(f, "{class}"),
+            Self::Null => write!(f, "null"),
+            Self::Void => write!(f, "void"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::structure::Class;
+    use crate::tokenizer::Tokenizer;
+
+    /// Type checks `class Main { subroutines }`, returning each error & the
+    /// source it points at.
+    fn errors(subroutines: &str) -> Vec<(String, String)> {
+        let source = format!("class Main {{ {subroutines} }}");
+        let classes = [Class::parse(&mut Tokenizer::new(&source), &mut Vec::default()).unwrap()];
+        let program = ProgramContext::new(&classes).unwrap();
+
+        check(&program, &classes[0])
+            .unwrap()
+            .into_iter()
+            .map(|err| match err {
+                CompileError::Subroutine { err, .. } => *err,
+                err => err,
+            })
+            .map(|err| (err.to_string(), source[err.span().start..err.span().end].to_owned()))
+            .collect()
+    }
+
+    fn error(message: &str, source: &str) -> Vec<(String, String)> {
+        vec![(message.to_owned(), source.to_owned())]
+    }
+
+    #[test]
+    fn well_typed() {
+        let subroutines = "
+            function char main() {
+                var int i;
+                var char c;
+                var Array a;
+                var Main m;
+                let i = c;
+                let c = 65;
+                let a = 8000;
+                let a = m;
+                let m = a;
+                let m = null;
+                let i = a[0];
+                let a[i] = true;
+                while (~(i = 0)) { let i = i - 1; }
+                do Main.f(c, a);
+                return i;
+            }
+            function void f(int i, Main m) { return; }
+        ";
+
+        assert_eq!(errors(subroutines), vec![]);
+    }
+
+    #[test]
+    fn assignment_type() {
+        let subroutines = "function void main() { var boolean b; let b = 1; return; }";
+
+        assert_eq!(
+            errors(subroutines),
+            error("Mismatched assignment type; variable=b; expected=boolean; actual=int", "b = 1")
+        );
+    }
+
+    #[test]
+    fn condition_type() {
+        let subroutines = "function void main() { var int i; if (1) {} while (i) {} return; }";
+
+        assert_eq!(
+            errors(subroutines),
+            [
+                error("Non-boolean condition; actual=int", "1"),
+                error("Non-boolean condition; actual=int", "i")
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn return_type() {
+        let subroutines = "function int main() { return true; }";
+
+        assert_eq!(
+            errors(subroutines),
+            error("Mismatched return type; expected=int; actual=boolean", "true")
+        );
+    }
+
+    #[test]
+    fn void_return() {
+        let subroutines = "function void main() { return 1; }";
+
+        assert_eq!(errors(subroutines), error("Value returned from void subroutine", "1"));
+    }
+
+    #[test]
+    fn missing_return_value() {
+        let subroutines = "function int main() { return; }";
+
+        assert_eq!(errors(subroutines), error("Missing return value; expected=int", "return"));
+    }
+
+    #[test]
+    fn argument_type() {
+        let subroutines = "
+            function void main() { do Main.f(1, true); return; }
+            function void f(int i, int j) { return; }
+        ";
+
+        assert_eq!(
+            errors(subroutines),
+            error(
+                "Mismatched argument type; subroutine=Main.f; argument=1; expected=int; \
+                 actual=boolean",
+                "true"
+            )
+        );
+    }
+
+    #[test]
+    fn void_value() {
+        let subroutines = "
+            function void main() { var int i; let i = Main.f(); return; }
+            function void f() { return; }
+        ";
+
+        assert_eq!(
+            errors(subroutines),
+            error("Void subroutine used as a value; subroutine=Main.f", "Main.f")
+        );
+    }
+
+    #[test]
+    fn assignable_to() {
+        use ExpressionType as E;
+
+        let cases = [
+            (E::Int, Type::Int, true),
+            (E::Int, Type::Char, true),
+            (E::Char, Type::Int, true),
+            (E::Int, Type::Boolean, false),
+            (E::Boolean, Type::Boolean, true),
+            (E::Boolean, Type::Int, false),
+            (E::Null, Type::Class("Main"), true),
+            (E::Null, Type::Int, false),
+            (E::Int, Type::Class("Array"), true),
+            (E::Char, Type::Class("Array"), true),
+            (E::Class("Main"), Type::Class("Array"), true),
+            (E::Boolean, Type::Class("Array"), false),
+            (E::Class("Array"), Type::Int, true),
+            (E::Class("Array"), Type::Class("Main"), true),
+            (E::Class("Array"), Type::Char, false),
+            (E::Class("Main"), Type::Class("Main"), true),
+            (E::Class("Main"), Type::Class("String"), false),
+            (E::Class("Main"), Type::Int, false),
+            (E::Unknown, Type::Boolean, true),
+            (E::Void, Type::Int, false),
+        ];
+
+        let mut failures = Vec::default();
+        for (value, target, expected) in cases {
+            if value.assignable_to(target) != expected {
+                failures.push(format!("{value} -> {target:?}: expected {expected}"));
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
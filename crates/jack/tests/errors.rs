@@ -0,0 +1,38 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Symbol errors stop the type check of a class, they should be reported once
+/// & no VM code written.
+#[test]
+fn duplicate_symbol() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("duplicate_symbol");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = "\
+class Main {
+    function void main() {
+        var int x, x;
+        return;
+    }
+}
+";
+    std::fs::write(dir.join("Main.jack"), source).unwrap();
+
+    for type_check in ["off", "warn", "deny"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_jack"))
+            .args(["compile", "--type-check", type_check])
+            .arg(&dir)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+
+        assert!(!output.status.success());
+        let errors: Vec<_> = stderr
+            .lines()
+            .filter(|line| line.starts_with("error"))
+            .collect();
+        assert_eq!(errors, ["error: Duplicate symbol; symbol=x; in=Main.main"], "{stderr}");
+        assert!(stderr.contains("Main.jack:3:20"), "{stderr}");
+        assert!(!dir.join("Main.vm").exists());
+    }
+}
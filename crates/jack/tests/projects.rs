@@ -0,0 +1,226 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use vm_emu::Vm;
+use vmt::OpCode;
+
+/// Tokenizes & parses every class in `projects/10`, comparing the output
+/// against the reference `T.xml` & `.xml` files.
+#[test]
+fn project_10_xml() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects/10");
+
+    let mut failures = Vec::default();
+    for dir in project_dirs(&projects) {
+        for source in files(&dir, "jack") {
+            let name = source.file_stem().unwrap().to_str().unwrap();
+            for (args, reference) in [
+                (&["tokenize"][..], format!("{name}T.xml")),
+                (&["parse", "--format", "xml"][..], format!("{name}.xml")),
+            ] {
+                let output = jack(args, &source);
+                let expected = std::fs::read_to_string(dir.join(&reference)).unwrap();
+                if !output.lines().eq(expected.lines()) {
+                    failures.push(format!("{}: output differs", dir.join(reference).display()));
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Compiles & runs every program in `projects/11` against the OS, checking what
+/// they print & write to RAM. The interactive games have no expected output,
+/// they are only run for a while without any keys pressed.
+#[test]
+fn project_11_programs() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects/11");
+
+    for dir in project_dirs(&projects) {
+        let name = dir.file_name().unwrap().to_str().unwrap();
+        let mut vm = program(name);
+        match name {
+            "Seven" => assert_eq!(run(&mut vm, &[]), "7"),
+            // Converts RAM[8000] to binary in RAM[8001..8017], least
+            // significant bit first.
+            "ConvertToBin" => {
+                let value: i16 = 0b0101_0000_0000_1011;
+                vm.ram_mut()[8000] = value;
+                run(&mut vm, &[]);
+                let bits: Vec<_> = (0..16).map(|bit| (value >> bit) & 1).collect();
+                assert_eq!(vm.ram()[8001..8017], bits);
+            }
+            "Average" => {
+                let output = run(&mut vm, "3\n10\n20\n33\n".as_bytes());
+                assert_eq!(
+                    output,
+                    "How many numbers? 3\nEnter a number: 10\nEnter a number: 20\nEnter a number: \
+                     33\nThe average is 21"
+                );
+            }
+            // Prints `Test n: expected result: x; actual result: y` per test.
+            "ComplexArrays" => {
+                let output = run(&mut vm, &[]);
+                let results: Vec<_> = output
+                    .lines()
+                    .map(|line| {
+                        let (_, results) = line.split_once("expected result: ").unwrap();
+                        results.split_once("; actual result: ").unwrap()
+                    })
+                    .collect();
+                assert_eq!(results.len(), 5, "{output}");
+                for (expected, actual) in results {
+                    assert_eq!(expected, actual, "{output}");
+                }
+            }
+            "Pong" | "Square" => {
+                while vm.steps() < 1_000_000 {
+                    vm.step().unwrap();
+                }
+                assert!(vm
+                    .call_stack()
+                    .iter()
+                    .any(|function| function == "Main.main"));
+            }
+            _ => panic!("Unknown program; name={name}"),
+        }
+    }
+}
+
+/// Compiles the program in `projects/11/{name}` & loads it alongside the OS
+/// VM files from `projects/12`.
+fn program(name: &str) -> Vm {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects");
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("programs")
+        .join(name);
+    std::fs::create_dir_all(&scratch).unwrap();
+    for source in files(&projects.join("11").join(name), "jack") {
+        std::fs::copy(&source, scratch.join(source.file_name().unwrap())).unwrap();
+    }
+    jack(&["compile"], &scratch);
+
+    let os = project_dirs(&projects.join("12"))
+        .into_iter()
+        .flat_map(|dir| files(&dir, "vm"))
+        .filter(|path| path.file_stem().unwrap() != "Main");
+    let sources: Vec<_> = files(&scratch, "vm")
+        .into_iter()
+        .chain(os)
+        .map(|path| (path.display().to_string(), std::fs::read_to_string(path).unwrap()))
+        .collect();
+    let sources: Vec<_> = sources
+        .iter()
+        .map(|(file, source)| (file.as_str(), source.as_str()))
+        .collect();
+
+    Vm::from_sources(&sources).unwrap()
+}
+
+/// Runs `Main.main` to completion, typing `keys` (`\n` as the newline key)
+/// & returning the text printed through `Output`.
+fn run(vm: &mut Vm, keys: &[u8]) -> String {
+    const MAX_STEPS: u64 = 50_000_000;
+    const KEYBOARD: usize = 24576;
+    const ARG: usize = 2;
+
+    let function = |name: &str| {
+        vm.program()
+            .iter()
+            .position(|ix| matches!(&ix.opcode, OpCode::Function { name: f, .. } if f == name))
+            .unwrap()
+    };
+    let (print_char, println) = (function("Output.printChar"), function("Output.println"));
+    let (read_char, key_pressed) = (function("Keyboard.readChar"), function("Keyboard.keyPressed"));
+
+    let mut keys = keys.iter().map(|&key| match key {
+        b'\n' => 128,
+        key => i16::from(key),
+    });
+    // The number of `keyPressed` calls since the last key was pressed.
+    let mut polls = None;
+    let mut output = String::default();
+    let mut started = false;
+    while vm.steps() < MAX_STEPS {
+        vm.step().unwrap();
+
+        // Press the next key once `readChar` waits for one, & release it after
+        // it has been read so `readChar` stops waiting for the release.
+        let argument = vm.ram()[vm.ram()[ARG] as usize];
+        match vm.pc() {
+            pc if pc == read_char => {
+                vm.ram_mut()[KEYBOARD] = keys.next().expect("Program read more keys");
+                polls = Some(0);
+            }
+            pc if pc == key_pressed => match polls {
+                Some(0) => polls = Some(1),
+                Some(_) => {
+                    vm.ram_mut()[KEYBOARD] = 0;
+                    polls = None;
+                }
+                None => {}
+            },
+            // Record the text printed when entering the `Output` functions.
+            pc if pc == print_char => match u8::try_from(argument) {
+                Ok(char) if char.is_ascii() => output.push(char::from(char)),
+                _ => {}
+            },
+            pc if pc == println => output.push('\n'),
+            _ => {}
+        }
+
+        // `Sys.init` returns to nowhere once `Main.main` has returned.
+        let in_main = vm
+            .call_stack()
+            .iter()
+            .any(|function| function == "Main.main");
+        if started && !in_main {
+            return output;
+        }
+        started |= in_main;
+    }
+
+    panic!("Program did not finish; output={output}");
+}
+
+/// Runs the compiler, asserting it succeeds & returning its stdout.
+fn jack(args: &[&str], path: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_jack"))
+        .args(args)
+        .arg(path)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "jack {} {} failed:\n{}",
+        args.join(" "),
+        path.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn project_dirs(projects: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(projects)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    assert!(!dirs.is_empty());
+
+    dirs
+}
+
+fn files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+
+    files
+}
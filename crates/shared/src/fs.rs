@@ -0,0 +1,17 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes `contents` to `path` through a temporary file in the same directory
+/// that is then renamed into place, so `path` is never left partially written.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+
+    let res = std::fs::write(&temp, contents).and_then(|()| std::fs::rename(&temp, path));
+    if res.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+
+    res
+}
@@ -61,3 +61,90 @@ pub enum AluOutput {
     #[strum(to_string = "D|M")]
     D_OR_M,
 }
+
+impl AluOutput {
+    /// Returns the 7 `a c1..c6` comp bits of a C instruction computing this
+    /// output.
+    pub fn encode(&self) -> u16 {
+        match self {
+            Self::ZERO => 0b0101010,
+            Self::ONE => 0b0111111,
+            Self::NEGATIVE_ONE => 0b0111010,
+            Self::D => 0b0001100,
+            Self::A => 0b0110000,
+            Self::M => 0b1110000,
+            Self::NEGATE_D => 0b0001101,
+            Self::NEGATE_A => 0b0110001,
+            Self::NEGATE_M => 0b1110001,
+            Self::NEGATIVE_D => 0b0001111,
+            Self::NEGATIVE_A => 0b0110011,
+            Self::NEGATIVE_M => 0b1110011,
+            Self::D_INC => 0b0011111,
+            Self::A_INC => 0b0110111,
+            Self::M_INC => 0b1110111,
+            Self::D_DEC => 0b0001110,
+            Self::A_DEC => 0b0110010,
+            Self::M_DEC => 0b1110010,
+            Self::D_PLUS_A => 0b0000010,
+            Self::D_PLUS_M => 0b1000010,
+            Self::D_MINUS_A => 0b0010011,
+            Self::D_MINUS_M => 0b1010011,
+            Self::A_MINUS_D => 0b0000111,
+            Self::M_MINUS_D => 0b1000111,
+            Self::D_AND_A => 0b0000000,
+            Self::D_AND_M => 0b1000000,
+            Self::D_OR_A => 0b0010101,
+            Self::D_OR_M => 0b1010101,
+        }
+    }
+
+    /// Returns true if the output is computed from `M` rather than `A`.
+    pub fn reads_m(&self) -> bool {
+        self.encode() & 0b100_0000 != 0
+    }
+
+    /// Returns true if the output depends on `D` (the canonical encodings only
+    /// zero `D` through `zx` when it is unused).
+    pub fn reads_d(&self) -> bool {
+        self.encode() & 0b10_0000 == 0
+    }
+
+    /// Decodes the 7 `a c1..c6` comp bits of a C instruction.
+    ///
+    /// Besides the canonical encodings, this accepts every control bit
+    /// combination that the ALU evaluates to one of the supported outputs
+    /// (e.g. `zx` & `zy` with `f=0` also computes `0`). Re-encoding such an
+    /// output yields the canonical bits. Returns `None` for combinations
+    /// computing anything else (e.g. `!(D&A)`).
+    pub fn decode(bits: u16) -> Option<Self> {
+        // Select the A or M flavour of the output based on the `a` bit.
+        let y = |a, m| match bits & 0b100_0000 {
+            0 => a,
+            _ => m,
+        };
+
+        Some(match bits & 0b11_1111 {
+            0b101010 | 0b001000 | 0b011000 | 0b100000 | 0b100100 | 0b101000 | 0b101100
+            | 0b101111 | 0b111000 | 0b111011 | 0b111101 => Self::ZERO,
+            0b111111 => Self::ONE,
+            0b111010 | 0b001001 | 0b011001 | 0b100001 | 0b100101 | 0b101001 | 0b101011
+            | 0b101101 | 0b101110 | 0b111001 | 0b111100 => Self::NEGATIVE_ONE,
+            0b001100 | 0b001010 | 0b011011 | 0b011101 => Self::D,
+            0b110000 | 0b100010 | 0b100111 | 0b110101 => y(Self::A, Self::M),
+            0b001101 | 0b001011 | 0b011010 | 0b011100 => Self::NEGATE_D,
+            0b110001 | 0b100011 | 0b100110 | 0b110100 => y(Self::NEGATE_A, Self::NEGATE_M),
+            0b001111 => Self::NEGATIVE_D,
+            0b110011 => y(Self::NEGATIVE_A, Self::NEGATIVE_M),
+            0b011111 => Self::D_INC,
+            0b110111 => y(Self::A_INC, Self::M_INC),
+            0b001110 => Self::D_DEC,
+            0b110010 => y(Self::A_DEC, Self::M_DEC),
+            0b000010 => y(Self::D_PLUS_A, Self::D_PLUS_M),
+            0b010011 => y(Self::D_MINUS_A, Self::D_MINUS_M),
+            0b000111 => y(Self::A_MINUS_D, Self::M_MINUS_D),
+            0b000000 => y(Self::D_AND_A, Self::D_AND_M),
+            0b010101 => y(Self::D_OR_A, Self::D_OR_M),
+            _ => return None,
+        })
+    }
+}
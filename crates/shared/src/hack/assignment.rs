@@ -4,9 +4,62 @@ use strum::EnumString;
 pub enum Assignment {
     M,
     D,
+    #[strum(to_string = "DM", serialize = "MD")]
     DM,
     A,
+    #[strum(to_string = "AM", serialize = "MA")]
     AM,
+    #[strum(to_string = "AD", serialize = "DA")]
     AD,
+    #[strum(
+        to_string = "ADM",
+        serialize = "AMD",
+        serialize = "DAM",
+        serialize = "DMA",
+        serialize = "MAD",
+        serialize = "MDA"
+    )]
     ADM,
 }
+
+impl Assignment {
+    /// Returns the 3 `d1 d2 d3` dest bits of a C instruction (`A`, `D`, `M`).
+    pub fn encode(&self) -> u16 {
+        match self {
+            Self::M => 0b001,
+            Self::D => 0b010,
+            Self::DM => 0b011,
+            Self::A => 0b100,
+            Self::AM => 0b101,
+            Self::AD => 0b110,
+            Self::ADM => 0b111,
+        }
+    }
+
+    pub fn writes_a(&self) -> bool {
+        self.encode() & 0b100 != 0
+    }
+
+    pub fn writes_d(&self) -> bool {
+        self.encode() & 0b010 != 0
+    }
+
+    pub fn writes_m(&self) -> bool {
+        self.encode() & 0b001 != 0
+    }
+
+    /// Decodes the 3 dest bits of a C instruction, `0b000` assigns nothing.
+    pub fn decode(bits: u16) -> Option<Self> {
+        match bits & 0b111 {
+            0b000 => None,
+            0b001 => Some(Self::M),
+            0b010 => Some(Self::D),
+            0b011 => Some(Self::DM),
+            0b100 => Some(Self::A),
+            0b101 => Some(Self::AM),
+            0b110 => Some(Self::AD),
+            0b111 => Some(Self::ADM),
+            _ => unreachable!(),
+        }
+    }
+}
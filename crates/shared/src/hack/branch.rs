@@ -10,3 +10,34 @@ pub enum Branch {
     JLE,
     JMP,
 }
+
+impl Branch {
+    /// Returns the 3 `j1 j2 j3` jump bits of a C instruction (`<0`, `=0`,
+    /// `>0`).
+    pub fn encode(&self) -> u16 {
+        match self {
+            Self::JGT => 0b001,
+            Self::JEQ => 0b010,
+            Self::JGE => 0b011,
+            Self::JLT => 0b100,
+            Self::JNE => 0b101,
+            Self::JLE => 0b110,
+            Self::JMP => 0b111,
+        }
+    }
+
+    /// Decodes the 3 jump bits of a C instruction, `0b000` never jumps.
+    pub fn decode(bits: u16) -> Option<Self> {
+        match bits & 0b111 {
+            0b000 => None,
+            0b001 => Some(Self::JGT),
+            0b010 => Some(Self::JEQ),
+            0b011 => Some(Self::JGE),
+            0b100 => Some(Self::JLT),
+            0b101 => Some(Self::JNE),
+            0b110 => Some(Self::JLE),
+            0b111 => Some(Self::JMP),
+            _ => unreachable!(),
+        }
+    }
+}
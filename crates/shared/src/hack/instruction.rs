@@ -2,9 +2,14 @@ use std::fmt::Display;
 use std::str::FromStr;
 
 use eyre::eyre;
+use thiserror::Error;
 
 use super::{AluOutput, Assignment, Branch};
 
+/// The largest address an A instruction can load (the MSB selects C
+/// instructions).
+pub const MAX_ADDRESS: u16 = 0x7FFF;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
     A(Location),
@@ -12,6 +17,63 @@ pub enum Instruction {
     Label(String),
 }
 
+impl Instruction {
+    /// Encodes the instruction as a 16-bit Hack machine word.
+    ///
+    /// # Panics
+    ///
+    /// Labels & A instructions referencing labels have no encoding, all symbols
+    /// must be resolved to addresses before calling this.
+    pub fn encode(&self) -> u16 {
+        match self {
+            Instruction::A(Location::Address(address)) => {
+                debug_assert!(*address <= MAX_ADDRESS);
+
+                *address
+            }
+            Instruction::C(assignment, alu_output, branch) => {
+                0b111 << 13
+                    | alu_output.encode() << 6
+                    | assignment.map_or(0, |assignment| assignment.encode()) << 3
+                    | branch.map_or(0, |branch| branch.encode())
+            }
+            Instruction::A(Location::Label(label)) | Instruction::Label(label) => {
+                panic!("Cannot encode unresolved symbol; symbol={label}")
+            }
+        }
+    }
+
+    /// Decodes a 16-bit Hack machine word.
+    ///
+    /// As the hardware ignores them, bits 13 & 14 of C instructions are not
+    /// required to be set. See [`AluOutput::decode`] for the accepted comp
+    /// bits.
+    pub fn decode(word: u16) -> Result<Self, DecodeError> {
+        if word & 0x8000 == 0 {
+            return Ok(Instruction::A(Location::Address(word)));
+        }
+
+        let comp = (word >> 6) & 0b111_1111;
+        let alu_output = AluOutput::decode(comp).ok_or(DecodeError::Comp(comp))?;
+
+        Ok(Instruction::C(Assignment::decode(word >> 3), alu_output, Branch::decode(word)))
+    }
+}
+
+impl TryFrom<u16> for Instruction {
+    type Error = DecodeError;
+
+    fn try_from(word: u16) -> Result<Self, Self::Error> {
+        Self::decode(word)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    #[error("Unsupported comp bits; comp={0:#09b}")]
+    Comp(u16),
+}
+
 impl Display for Instruction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -42,23 +104,22 @@ impl FromStr for Instruction {
             Some('@') => {
                 let target = &s[1..];
                 match target.chars().next() {
-                    Some('R') => {
-                        let register = target
-                            .get(1..)
-                            .ok_or_else(|| eyre!("Missing register number; instruction={s}"))?;
-                        let register = register.parse::<u16>()?;
+                    Some('0'..='9') => {
+                        let address = target.parse::<u16>()?;
                         eyre::ensure!(
-                            register < 16,
-                            "Invalid register; register={register}; instruction={s}"
+                            address <= MAX_ADDRESS,
+                            "Address out of range; address={address}; instruction={s}"
                         );
 
-                        Ok(Instruction::A(Location::Address(register)))
+                        Ok(Instruction::A(Location::Address(address)))
                     }
-                    Some('0'..='9') => Ok(Instruction::A(Location::Address(target.parse()?))),
-                    Some(_) => Ok(match PredefinedSymbols::from_str(target) {
-                        Ok(symbol) => Instruction::A(Location::Address(symbol.address())),
-                        Err(_) => Instruction::A(Location::Label(target.to_owned())),
-                    }),
+                    Some(_) => Ok(Instruction::A(
+                        match (parse_register(target), PredefinedSymbols::from_str(target)) {
+                            (Some(register), _) => Location::Address(register),
+                            (None, Ok(symbol)) => Location::Address(symbol.address()),
+                            (None, Err(_)) => Location::Label(target.to_owned()),
+                        },
+                    )),
                     _ => Err(eyre!("Invalid A instruction; instruction={s}")),
                 }
             }
@@ -71,34 +132,54 @@ impl FromStr for Instruction {
             }
             None => Err(eyre!("Empty string")),
             _ => {
-                let mut alu_output = None;
-
-                // Parse assignment if it exists (setting alu ix at the same time).
-                let assignment = match s.split_once('=') {
-                    Some((assignment, rest)) => {
-                        alu_output = Some(rest.split(';').next().unwrap_or(rest).parse()?);
-                        Some(assignment.parse()?)
-                    }
-                    None => None,
+                // Split off the assignment if it exists.
+                let (assignment, rest) = match s.split_once('=') {
+                    Some((assignment, rest)) => (
+                        Some(
+                            assignment
+                                .parse()
+                                .map_err(|_| eyre!("Invalid dest; instruction={s}"))?,
+                        ),
+                        rest,
+                    ),
+                    None => (None, s),
                 };
 
-                // Parse branch if it exists (setting alu ix at the same time).
-                let branch = match s.split_once(';') {
-                    Some((rest, branch)) => {
-                        alu_output = Some(rest.split('=').nth(1).unwrap_or(rest).parse()?);
-                        Some(branch.parse()?)
-                    }
-                    None => None,
+                // Split off the branch if it exists (tolerating an empty trailing `;`).
+                let (alu_output, branch) = match rest.split_once(';') {
+                    Some((alu_output, "")) => (alu_output, None),
+                    Some((alu_output, branch)) => (
+                        alu_output,
+                        Some(
+                            branch
+                                .parse()
+                                .map_err(|_| eyre!("Invalid jump; instruction={s}"))?,
+                        ),
+                    ),
+                    None => (rest, None),
                 };
 
-                alu_output
-                    .ok_or(eyre!("Invalid C instruction; instruction={s}"))
-                    .map(|alu_output| Instruction::C(assignment, alu_output, branch))
+                // Whatever remains must be the ALU output.
+                let alu_output = alu_output
+                    .parse()
+                    .map_err(|_| eyre!("Invalid comp; instruction={s}"))?;
+
+                Ok(Instruction::C(assignment, alu_output, branch))
             }
         }
     }
 }
 
+/// Parses `R0`..`R15` into their register address.
+fn parse_register(target: &str) -> Option<u16> {
+    let register = target.strip_prefix('R')?;
+
+    register
+        .parse::<u16>()
+        .ok()
+        .filter(|address| *address < 16 && address.to_string() == register)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Location {
     Address(u16),
@@ -138,4 +219,155 @@ impl PredefinedSymbols {
             Self::Kbd => 24576,
         }
     }
+
+    pub fn from_address(address: u16) -> Option<Self> {
+        match address {
+            0 => Some(Self::Sp),
+            1 => Some(Self::Lcl),
+            2 => Some(Self::Arg),
+            3 => Some(Self::This),
+            4 => Some(Self::That),
+            16384 => Some(Self::Screen),
+            24576 => Some(Self::Kbd),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the comp bits of a C instruction on the ALU.
+    fn alu(comp: u16, d: u16, a: u16, m: u16) -> u16 {
+        let bit = |i: u16| comp & (1 << (5 - i)) != 0;
+        let x = if bit(0) { 0 } else { d };
+        let x = if bit(1) { !x } else { x };
+        let y = if comp & 0b100_0000 != 0 { m } else { a };
+        let y = if bit(2) { 0 } else { y };
+        let y = if bit(3) { !y } else { y };
+        let out = if bit(4) { x.wrapping_add(y) } else { x & y };
+
+        if bit(5) {
+            !out
+        } else {
+            out
+        }
+    }
+
+    /// The accepted comp bits compute the same outputs as their canonical
+    /// encoding, & the rejected ones compute none of the supported outputs.
+    #[test]
+    fn comp_bits() {
+        let values = [0, 1, 0xFFFF, 0x8000, 0x7FFF, 0x5555, 0x3333, 0x1234];
+        let outputs = |comp: u16| -> Vec<u16> {
+            let mut outputs = Vec::default();
+            for d in values {
+                for a in values {
+                    for m in values {
+                        outputs.push(alu(comp, d, a, m));
+                    }
+                }
+            }
+
+            outputs
+        };
+
+        let mut canonical: Vec<AluOutput> = Vec::default();
+        for alu_output in (0..0b1000_0000).filter_map(AluOutput::decode) {
+            if !canonical.contains(&alu_output) {
+                canonical.push(alu_output);
+            }
+        }
+        assert_eq!(canonical.len(), 28);
+
+        let mut failures = Vec::default();
+        for comp in 0..0b1000_0000 {
+            match AluOutput::decode(comp) {
+                Some(alu_output) => {
+                    let bits = alu_output.encode();
+                    if outputs(bits) != outputs(comp) {
+                        failures.push(format!("{comp:#09b}: computes other than {alu_output}"));
+                    }
+                    if AluOutput::decode(bits) != Some(alu_output) {
+                        failures.push(format!("{comp:#09b}: {bits:#09b} does not round-trip"));
+                    }
+                }
+                None => {
+                    if let Some(alu_output) = canonical
+                        .iter()
+                        .find(|alu_output| outputs(alu_output.encode()) == outputs(comp))
+                    {
+                        failures.push(format!("{comp:#09b}: rejected but computes {alu_output}"));
+                    }
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    /// Every instruction with resolved symbols survives encoding & decoding.
+    #[test]
+    fn instructions() {
+        let alu_outputs = (0..0b1000_0000).filter_map(AluOutput::decode);
+        let assignments = (0..0b1000).map(Assignment::decode);
+        let branches: Vec<_> = (0..0b1000).map(Branch::decode).collect();
+
+        let mut instructions: Vec<_> = (0..=MAX_ADDRESS)
+            .map(|address| Instruction::A(Location::Address(address)))
+            .collect();
+        for alu_output in alu_outputs {
+            for assignment in assignments.clone() {
+                for branch in &branches {
+                    instructions.push(Instruction::C(assignment, alu_output, *branch));
+                }
+            }
+        }
+
+        let mut failures = Vec::default();
+        for instruction in instructions {
+            let word = instruction.encode();
+            match Instruction::decode(word) {
+                Ok(decoded) if decoded == instruction => {}
+                res => failures.push(format!("{instruction}: {word:#018b} decoded as {res:?}")),
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    /// Every word either fails to decode on its comp bits or re-encodes to
+    /// itself, with bits 13 & 14 set & non-canonical comp bits replaced by
+    /// the canonical ones.
+    #[test]
+    fn words() {
+        let mut failures = Vec::default();
+        for word in 0..=u16::MAX {
+            let comp = (word >> 6) & 0b111_1111;
+            match Instruction::decode(word) {
+                Ok(instruction) => {
+                    let expected = match &instruction {
+                        Instruction::C(_, alu_output, _) => {
+                            word & !(0b111_1111 << 6) | 0b011 << 13 | alu_output.encode() << 6
+                        }
+                        _ => word,
+                    };
+                    if instruction.encode() != expected {
+                        failures.push(format!(
+                            "{word:#018b}: {instruction} encoded as {:#018b}",
+                            instruction.encode()
+                        ));
+                    }
+                }
+                Err(DecodeError::Comp(bits)) => {
+                    if word & 0x8000 == 0 || bits != comp || AluOutput::decode(comp).is_some() {
+                        failures.push(format!("{word:#018b}: unexpected comp error"));
+                    }
+                }
+            }
+        }
+
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
 }
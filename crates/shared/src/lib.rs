@@ -1 +1,3 @@
+pub mod fs;
 pub mod hack;
+pub mod tst;
@@ -0,0 +1,356 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use super::OutputColumn;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Loads a program or chip, `None` loads the script's directory.
+    Load(Option<String>),
+    /// Loads a file into a built-in part, e.g. `ROM32K load Max.hack`.
+    LoadPart {
+        part: String,
+        file: String,
+    },
+    OutputFile(String),
+    CompareTo(String),
+    OutputList(Vec<OutputColumn>),
+    Set {
+        variable: String,
+        value: i64,
+    },
+    Output,
+    Eval,
+    Tick,
+    Tock,
+    TickTock,
+    VmStep,
+    /// Repeats the body `count` times or forever if `None`.
+    Repeat {
+        count: Option<u64>,
+        body: Vec<Command>,
+    },
+    While {
+        condition: Condition,
+        body: Vec<Command>,
+    },
+    Echo(String),
+    ClearEcho,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub variable: String,
+    pub comparison: Comparison,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+pub enum Comparison {
+    #[strum(serialize = "=")]
+    Eq,
+    #[strum(serialize = "<>")]
+    Ne,
+    #[strum(serialize = "<")]
+    Lt,
+    #[strum(serialize = "<=")]
+    Le,
+    #[strum(serialize = ">")]
+    Gt,
+    #[strum(serialize = ">=")]
+    Ge,
+}
+
+impl Comparison {
+    pub fn evaluate(&self, left: i64, right: i64) -> bool {
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            Comparison::Ge => left >= right,
+        }
+    }
+}
+
+/// Parses a test script into its commands.
+pub fn parse_script(source: &str) -> Result<Vec<Command>, ScriptError> {
+    let tokens = tokenize(source)?;
+    let mut tokens = tokens.iter().peekable();
+    let commands = parse_block(&mut tokens)?;
+
+    match tokens.next() {
+        Some((line, token)) => {
+            Err(ScriptError::UnexpectedToken { line: *line, token: token.to_string() })
+        }
+        None => Ok(commands),
+    }
+}
+
+/// Parses a value in one of the `%B`, `%X`, `%D` or plain decimal formats.
+pub fn parse_value(s: &str) -> Option<i64> {
+    match s.strip_prefix('%') {
+        Some(value) => match value.split_at_checked(1)? {
+            ("B", digits) => i64::from_str_radix(digits, 2).ok(),
+            ("X", digits) => i64::from_str_radix(digits, 16).ok(),
+            ("D", digits) => digits.parse().ok(),
+            _ => None,
+        },
+        None => s.parse().ok(),
+    }
+}
+
+type Tokens<'a> = std::iter::Peekable<std::slice::Iter<'a, (usize, Token)>>;
+
+fn parse_block(tokens: &mut Tokens) -> Result<Vec<Command>, ScriptError> {
+    let mut commands = Vec::default();
+    while let Some((line, token)) = tokens.peek() {
+        let line = *line;
+        let word = match token {
+            Token::Word(word) => word.as_str(),
+            Token::RightBrace => break,
+            // Empty commands are permitted.
+            Token::Comma | Token::Semicolon => {
+                tokens.next();
+
+                continue;
+            }
+            token => return Err(ScriptError::UnexpectedToken { line, token: token.to_string() }),
+        };
+        tokens.next();
+
+        // Blocks are not followed by a terminator.
+        match word {
+            "repeat" => {
+                let count = match tokens.peek() {
+                    Some((_, Token::Word(count))) => {
+                        tokens.next();
+
+                        Some(count.parse().map_err(|_| ScriptError::InvalidArgument {
+                            line,
+                            argument: count.to_owned(),
+                        })?)
+                    }
+                    _ => None,
+                };
+                let body = parse_body(tokens, line)?;
+                commands.push(Command::Repeat { count, body });
+
+                continue;
+            }
+            "while" => {
+                let arguments = take_until(tokens, |token| token == &Token::LeftBrace);
+                let [variable, comparison, value] = arguments.as_slice() else {
+                    return Err(ScriptError::ArgumentCount { line, command: word.to_owned() });
+                };
+                let condition = Condition {
+                    variable: variable.to_string(),
+                    comparison: Comparison::from_str(comparison).map_err(|_| {
+                        ScriptError::InvalidArgument { line, argument: comparison.to_string() }
+                    })?,
+                    value: parse_value(value).ok_or_else(|| ScriptError::InvalidArgument {
+                        line,
+                        argument: value.to_string(),
+                    })?,
+                };
+                let body = parse_body(tokens, line)?;
+                commands.push(Command::While { condition, body });
+
+                continue;
+            }
+            _ => {}
+        }
+
+        // All other commands take arguments up to their terminator.
+        let arguments = take_until(tokens, |token| {
+            matches!(token, Token::Comma | Token::Semicolon | Token::LeftBrace | Token::RightBrace)
+        });
+        match tokens.next() {
+            Some((_, Token::Comma | Token::Semicolon)) => {}
+            Some((line, token)) => {
+                return Err(ScriptError::UnexpectedToken { line: *line, token: token.to_string() })
+            }
+            None => return Err(ScriptError::UnexpectedEof),
+        }
+
+        let single = |arguments: &[&str]| match arguments {
+            [argument] => Ok(argument.to_string()),
+            _ => Err(ScriptError::ArgumentCount { line, command: word.to_owned() }),
+        };
+        let none = |arguments: &[&str], command| match arguments.is_empty() {
+            true => Ok(command),
+            false => Err(ScriptError::ArgumentCount { line, command: word.to_owned() }),
+        };
+        commands.push(match word {
+            "load" => match arguments.as_slice() {
+                [] => Command::Load(None),
+                [file] => Command::Load(Some(file.to_string())),
+                _ => return Err(ScriptError::ArgumentCount { line, command: word.to_owned() }),
+            },
+            "output-file" => Command::OutputFile(single(&arguments)?),
+            "compare-to" => Command::CompareTo(single(&arguments)?),
+            "output-list" => Command::OutputList(
+                arguments
+                    .iter()
+                    .map(|column| {
+                        column.parse().map_err(|_| ScriptError::InvalidArgument {
+                            line,
+                            argument: column.to_string(),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            "set" => match arguments.as_slice() {
+                [variable, value] => Command::Set {
+                    variable: variable.to_string(),
+                    value: parse_value(value).ok_or_else(|| ScriptError::InvalidArgument {
+                        line,
+                        argument: value.to_string(),
+                    })?,
+                },
+                _ => return Err(ScriptError::ArgumentCount { line, command: word.to_owned() }),
+            },
+            "echo" => Command::Echo(single(&arguments)?),
+            "output" => none(&arguments, Command::Output)?,
+            "eval" => none(&arguments, Command::Eval)?,
+            "tick" => none(&arguments, Command::Tick)?,
+            "tock" => none(&arguments, Command::Tock)?,
+            "ticktock" => none(&arguments, Command::TickTock)?,
+            "vmstep" => none(&arguments, Command::VmStep)?,
+            "clear-echo" => none(&arguments, Command::ClearEcho)?,
+            part if arguments.first() == Some(&"load") => match arguments.as_slice() {
+                [_, file] => Command::LoadPart { part: part.to_owned(), file: file.to_string() },
+                _ => return Err(ScriptError::ArgumentCount { line, command: word.to_owned() }),
+            },
+            _ => return Err(ScriptError::UnknownCommand { line, command: word.to_owned() }),
+        });
+    }
+
+    Ok(commands)
+}
+
+fn parse_body(tokens: &mut Tokens, line: usize) -> Result<Vec<Command>, ScriptError> {
+    match tokens.next() {
+        Some((_, Token::LeftBrace)) => {}
+        Some((line, token)) => {
+            return Err(ScriptError::UnexpectedToken { line: *line, token: token.to_string() })
+        }
+        None => return Err(ScriptError::UnexpectedEof),
+    }
+    let body = parse_block(tokens)?;
+    match tokens.next() {
+        Some((_, Token::RightBrace)) => Ok(body),
+        _ => Err(ScriptError::UnclosedBlock { line }),
+    }
+}
+
+fn take_until<'a>(tokens: &mut Tokens<'a>, end: impl Fn(&Token) -> bool) -> Vec<&'a str> {
+    let mut arguments = Vec::default();
+    while let Some((_, token)) = tokens.peek().filter(|(_, token)| !end(token)) {
+        match token {
+            Token::Word(word) | Token::String(word) => arguments.push(word.as_str()),
+            _ => unreachable!(),
+        }
+        tokens.next();
+    }
+
+    arguments
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, strum::Display)]
+enum Token {
+    #[strum(to_string = "{0}")]
+    Word(String),
+    #[strum(to_string = "\"{0}\"")]
+    String(String),
+    #[strum(to_string = ",")]
+    Comma,
+    #[strum(to_string = ";")]
+    Semicolon,
+    #[strum(to_string = "{{")]
+    LeftBrace,
+    #[strum(to_string = "}}")]
+    RightBrace,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ScriptError> {
+    let mut tokens = Vec::default();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '\n' => line += 1,
+            char if char.is_whitespace() => {}
+            ',' => tokens.push((line, Token::Comma)),
+            ';' => tokens.push((line, Token::Semicolon)),
+            '{' => tokens.push((line, Token::LeftBrace)),
+            '}' => tokens.push((line, Token::RightBrace)),
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|char| char != &'\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                let start = line;
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(char) => {
+                            line += usize::from(char == '\n');
+                            prev = char;
+                        }
+                        None => return Err(ScriptError::UnclosedComment { line: start }),
+                    }
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut string = String::default();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(char) => {
+                            line += usize::from(char == '\n');
+                            string.push(char);
+                        }
+                        None => return Err(ScriptError::UnclosedString { line: start }),
+                    }
+                }
+                tokens.push((start, Token::String(string)));
+            }
+            char => {
+                let mut word = String::from(char);
+                while let Some(char) = chars.next_if(|char| {
+                    !char.is_whitespace() && !matches!(char, ',' | ';' | '{' | '}' | '"')
+                }) {
+                    word.push(char);
+                }
+                tokens.push((line, Token::Word(word)));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ScriptError {
+    #[error("Unexpected token; line={line}; token={token}")]
+    UnexpectedToken { line: usize, token: String },
+    #[error("Unexpected eof")]
+    UnexpectedEof,
+    #[error("Unclosed block; line={line}")]
+    UnclosedBlock { line: usize },
+    #[error("Unclosed comment; line={line}")]
+    UnclosedComment { line: usize },
+    #[error("Unclosed string; line={line}")]
+    UnclosedString { line: usize },
+    #[error("Unknown command; line={line}; command={command}")]
+    UnknownCommand { line: usize, command: String },
+    #[error("Invalid argument count; line={line}; command={command}")]
+    ArgumentCount { line: usize, command: String },
+    #[error("Invalid argument; line={line}; argument={argument}")]
+    InvalidArgument { line: usize, argument: String },
+}
@@ -0,0 +1,11 @@
+//! The nand2tetris test script language shared by the hardware, CPU & VM
+//! simulators.
+
+mod command;
+pub use command::*;
+
+mod output;
+pub use output::*;
+
+mod runner;
+pub use runner::*;
@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+/// A column of an `output-list`, e.g. `RAM[0]%D2.6.2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputColumn {
+    pub variable: String,
+    pub format: OutputFormat,
+    pub left: usize,
+    pub width: usize,
+    pub right: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Binary,
+    Decimal,
+    Hex,
+    String,
+}
+
+impl OutputColumn {
+    /// The column's header, the variable name centered within the column.
+    pub fn header(&self) -> String {
+        let total = self.left + self.width + self.right;
+        let name: String = self.variable.chars().take(total).collect();
+        let padding = total - name.chars().count();
+
+        format!(
+            "{:left$}{name}{:right$}",
+            "",
+            "",
+            left = padding / 2,
+            right = padding - padding / 2
+        )
+    }
+
+    /// Formats a numeric value, binary & hex values show the low `width`
+    /// digits.
+    pub fn value(&self, value: i64) -> String {
+        let width = self.width;
+        let body = match self.format {
+            OutputFormat::Binary => {
+                let digits = format!("{:064b}", value);
+
+                digits[64 - width.min(64)..].to_owned()
+            }
+            OutputFormat::Hex => {
+                let digits = format!("{:016X}", value);
+
+                digits[16 - width.min(16)..].to_owned()
+            }
+            OutputFormat::Decimal => format!("{value:>width$}"),
+            OutputFormat::String => format!("{value:<width$}"),
+        };
+
+        self.pad(&body)
+    }
+
+    /// Formats a string value, left aligned.
+    pub fn string(&self, value: &str) -> String {
+        let width = self.width;
+
+        self.pad(&format!("{value:<width$}"))
+    }
+
+    fn pad(&self, body: &str) -> String {
+        format!("{:left$}{body}{:right$}", "", "", left = self.left, right = self.right)
+    }
+}
+
+impl FromStr for OutputColumn {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((variable, format)) = s.split_once('%') else {
+            return Ok(OutputColumn {
+                variable: s.to_owned(),
+                format: OutputFormat::Binary,
+                left: 1,
+                width: 16,
+                right: 1,
+            });
+        };
+
+        let (format, padding) = format.split_at_checked(1).ok_or(())?;
+        let format = match format {
+            "B" => OutputFormat::Binary,
+            "D" => OutputFormat::Decimal,
+            "X" => OutputFormat::Hex,
+            "S" => OutputFormat::String,
+            _ => return Err(()),
+        };
+        let mut padding = padding
+            .split('.')
+            .map(|number| number.parse().map_err(|_| ()));
+        let (Some(left), Some(width), Some(right), None) =
+            (padding.next(), padding.next(), padding.next(), padding.next())
+        else {
+            return Err(());
+        };
+
+        Ok(OutputColumn {
+            variable: variable.to_owned(),
+            format,
+            left: left?,
+            width: width?,
+            right: right?,
+        })
+    }
+}
+
+/// Joins formatted columns into an output line.
+pub fn output_line(columns: impl IntoIterator<Item = String>) -> String {
+    columns
+        .into_iter()
+        .fold(String::from("|"), |line, column| line + &column + "|")
+}
+
+/// Compares an output line against the compare file, `*` in the compare file
+/// matches any character.
+pub fn matches_compare(output: &str, expected: &str) -> bool {
+    let output = output.trim_end();
+    let expected = expected.trim_end();
+
+    output.len() == expected.len()
+        && output
+            .chars()
+            .zip(expected.chars())
+            .all(|(output, expected)| expected == '*' || output == expected)
+}
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use super::{matches_compare, output_line, parse_script, Command, OutputColumn, ScriptError};
+
+/// The number of loop iterations a script may execute, which stops scripts that
+/// `repeat` forever, such as the interactive ones.
+pub const MAX_STEPS: u64 = 10_000_000;
+
+/// A simulator that can be driven by a test script.
+pub trait Simulator {
+    type Error: std::error::Error;
+
+    /// Loads `file` (or the whole directory if `None`) relative to `dir`.
+    fn load(&mut self, dir: &Path, file: Option<&str>) -> Result<(), Self::Error>;
+
+    /// Loads `path` into a built-in part such as `ROM32K`.
+    fn load_part(&mut self, part: &str, path: &Path) -> Result<(), Self::Error>;
+
+    /// Reads the current value of a script variable.
+    fn get(&self, variable: &str) -> Result<i64, Self::Error>;
+
+    /// Writes a script variable.
+    fn set(&mut self, variable: &str, value: i64) -> Result<(), Self::Error>;
+
+    /// Executes a simulation command (`eval`, `tick`, `tock`, `ticktock` or
+    /// `vmstep`).
+    fn step(&mut self, command: &Command) -> Result<(), Self::Error>;
+
+    /// Renders a single output column for the current state.
+    fn format(&self, column: &OutputColumn) -> Result<String, Self::Error> {
+        Ok(column.value(self.get(&column.variable)?))
+    }
+
+    /// Displays a message from the script, such as instructions for the user.
+    fn echo(&mut self, message: &str) {
+        println!("{message}");
+    }
+}
+
+/// Runs the test script at `path` against `simulator`, returning the number of
+/// output lines that matched the compare file.
+///
+/// Files referenced by the script are resolved relative to the script itself.
+/// The output file is written even if the comparison fails so that it can be
+/// inspected.
+pub fn run_script<S: Simulator>(
+    path: &Path,
+    simulator: &mut S,
+) -> Result<usize, TestError<S::Error>> {
+    let commands = parse_script(&read(path)?)?;
+    let mut runner = Runner {
+        dir: path.parent().unwrap_or(Path::new(".")).to_owned(),
+        simulator,
+        columns: Vec::default(),
+        output: Vec::default(),
+        output_file: None,
+        compare: None,
+        steps: 0,
+    };
+    let res = runner
+        .execute(&commands)
+        .and_then(|_| runner.check_complete());
+
+    if let Some(path) = &runner.output_file {
+        let mut output = runner.output.join("\n");
+        output.push('\n');
+        std::fs::write(path, output).map_err(|err| TestError::Io { path: path.clone(), err })?;
+    }
+
+    res.map(|_| runner.compare.map_or(0, |_| runner.output.len()))
+}
+
+fn read<E>(path: &Path) -> Result<String, TestError<E>> {
+    std::fs::read_to_string(path).map_err(|err| TestError::Io { path: path.to_owned(), err })
+}
+
+struct Runner<'a, S> {
+    dir: PathBuf,
+    simulator: &'a mut S,
+    columns: Vec<OutputColumn>,
+    output: Vec<String>,
+    output_file: Option<PathBuf>,
+    compare: Option<Vec<String>>,
+    /// The number of loop iterations executed so far.
+    steps: u64,
+}
+
+impl<S: Simulator> Runner<'_, S> {
+    fn execute(&mut self, commands: &[Command]) -> Result<(), TestError<S::Error>> {
+        for command in commands {
+            match command {
+                Command::Load(file) => self
+                    .simulator
+                    .load(&self.dir, file.as_deref())
+                    .map_err(TestError::Simulator)?,
+                Command::LoadPart { part, file } => self
+                    .simulator
+                    .load_part(part, &self.dir.join(file))
+                    .map_err(TestError::Simulator)?,
+                Command::OutputFile(file) => self.output_file = Some(self.dir.join(file)),
+                Command::CompareTo(file) => {
+                    let compare = read(&self.dir.join(file))?;
+                    self.compare = Some(compare.lines().map(str::to_owned).collect());
+                }
+                Command::OutputList(columns) => {
+                    self.columns.clone_from(columns);
+                    self.emit(output_line(self.columns.iter().map(OutputColumn::header)))?;
+                }
+                Command::Set { variable, value } => {
+                    self.simulator
+                        .set(variable, *value)
+                        .map_err(TestError::Simulator)?;
+                }
+                Command::Output => {
+                    let line = self
+                        .columns
+                        .iter()
+                        .map(|column| self.simulator.format(column))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(TestError::Simulator)?;
+                    self.emit(output_line(line))?;
+                }
+                Command::Eval
+                | Command::Tick
+                | Command::Tock
+                | Command::TickTock
+                | Command::VmStep => self.simulator.step(command).map_err(TestError::Simulator)?,
+                Command::Repeat { count: Some(count), body } => {
+                    for _ in 0..*count {
+                        self.iterate()?;
+                        self.execute(body)?;
+                    }
+                }
+                Command::Repeat { count: None, body } => loop {
+                    self.iterate()?;
+                    self.execute(body)?;
+                },
+                Command::While { condition, body } => {
+                    while condition.comparison.evaluate(
+                        self.simulator
+                            .get(&condition.variable)
+                            .map_err(TestError::Simulator)?,
+                        condition.value,
+                    ) {
+                        self.iterate()?;
+                        self.execute(body)?;
+                    }
+                }
+                Command::Echo(message) => self.simulator.echo(message),
+                Command::ClearEcho => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Counts a loop iteration, failing once the script has run `MAX_STEPS`.
+    fn iterate(&mut self) -> Result<(), TestError<S::Error>> {
+        if self.steps == MAX_STEPS {
+            return Err(TestError::StepLimit(MAX_STEPS));
+        }
+        self.steps += 1;
+
+        Ok(())
+    }
+
+    /// Fails if the script finished before producing every compared line.
+    fn check_complete(&self) -> Result<(), TestError<S::Error>> {
+        match self
+            .compare
+            .as_ref()
+            .and_then(|compare| compare.get(self.output.len()))
+        {
+            Some(expected) => Err(TestError::MissingOutput {
+                line: self.output.len() + 1,
+                expected: expected.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn emit(&mut self, line: String) -> Result<(), TestError<S::Error>> {
+        if let Some(compare) = &self.compare {
+            let expected = compare.get(self.output.len()).map_or("", String::as_str);
+            if !matches_compare(&line, expected) {
+                let err = TestError::Mismatch {
+                    line: self.output.len() + 1,
+                    expected: expected.to_owned(),
+                    actual: line.clone(),
+                };
+                self.output.push(line);
+
+                return Err(err);
+            }
+        }
+        self.output.push(line);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TestError<E> {
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Invalid script; err={0}")]
+    Script(#[from] ScriptError),
+    #[error("{0}")]
+    Simulator(E),
+    #[error("Comparison failure; line={line}\nexpected: {expected}\n  actual: {actual}")]
+    Mismatch { line: usize, expected: String, actual: String },
+    #[error("Comparison failure, missing output line; line={line}\nexpected: {expected}")]
+    MissingOutput { line: usize, expected: String },
+    #[error("Step limit reached; max_steps={0}")]
+    StepLimit(u64),
+}
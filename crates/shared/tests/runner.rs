@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use shared::tst::{run_script, Command, Simulator, TestError, MAX_STEPS};
+
+/// Counts the simulation commands & records the echoed messages.
+#[derive(Default)]
+struct Counter {
+    time: i64,
+    echoes: Vec<String>,
+}
+
+impl Simulator for Counter {
+    type Error = std::io::Error;
+
+    fn load(&mut self, _: &Path, _: Option<&str>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn load_part(&mut self, _: &str, _: &Path) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn get(&self, _: &str) -> Result<i64, Self::Error> {
+        Ok(self.time)
+    }
+
+    fn set(&mut self, _: &str, value: i64) -> Result<(), Self::Error> {
+        self.time = value;
+
+        Ok(())
+    }
+
+    fn step(&mut self, _: &Command) -> Result<(), Self::Error> {
+        self.time += 1;
+
+        Ok(())
+    }
+
+    fn echo(&mut self, message: &str) {
+        self.echoes.push(message.to_owned());
+    }
+}
+
+fn script(name: &str, source: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("runner");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, source).unwrap();
+
+    path
+}
+
+/// Echoed messages go to the simulator rather than stdout.
+#[test]
+fn echo() {
+    let path = script("Echo.tst", "echo \"Press a key\";\nrepeat 3 { tick; }\necho \"Done\";\n");
+    let mut simulator = Counter::default();
+
+    run_script(&path, &mut simulator).unwrap();
+    assert_eq!(simulator.echoes, ["Press a key", "Done"]);
+    assert_eq!(simulator.time, 3);
+}
+
+/// Scripts that never finish are stopped after `MAX_STEPS` simulation
+/// commands.
+#[test]
+fn step_limit() {
+    for (name, source) in [
+        ("Repeat.tst", "repeat { ticktock; }\n"),
+        ("While.tst", "set time 0;\nwhile time >= 0 { eval; }\n"),
+    ] {
+        let path = script(name, source);
+        let mut simulator = Counter::default();
+
+        let res = run_script(&path, &mut simulator);
+        assert!(matches!(res, Err(TestError::StepLimit(MAX_STEPS))), "{name}");
+        assert_eq!(simulator.time, MAX_STEPS as i64, "{name}");
+    }
+}
+
+/// Loops without any simulation commands are limited too.
+#[test]
+fn step_limit_without_simulation() {
+    for (name, source) in [
+        ("RepeatEcho.tst", "repeat { echo \"Waiting\"; }\n"),
+        ("RepeatEmpty.tst", "repeat { }\n"),
+        ("WhileSet.tst", "while time >= 0 { set time 0; }\n"),
+    ] {
+        let path = script(name, source);
+        let mut simulator = Counter::default();
+
+        let res = run_script(&path, &mut simulator);
+        assert!(matches!(res, Err(TestError::StepLimit(MAX_STEPS))), "{name}");
+        assert_eq!(simulator.time, 0, "{name}");
+    }
+}
+
+/// Compare files with lines the script never produced fail.
+#[test]
+fn missing_output() {
+    let source = "output-file Missing.out;\noutput-list time%D1.4.1;\noutput;\ntick;\noutput;\n";
+    let path = script("Missing.tst", source);
+    run_script(&path, &mut Counter::default()).unwrap();
+    let output = std::fs::read_to_string(path.with_extension("out")).unwrap();
+    assert_eq!(output.lines().count(), 3);
+
+    // The complete output matches.
+    std::fs::write(path.with_extension("cmp"), &output).unwrap();
+    let source = format!("compare-to Missing.cmp;\n{source}");
+    let path = script("Missing.tst", &source);
+    assert_eq!(run_script(&path, &mut Counter::default()).unwrap(), 3);
+
+    std::fs::write(path.with_extension("cmp"), format!("{output}|   2  |\n")).unwrap();
+    let res = run_script(&path, &mut Counter::default());
+    assert!(
+        matches!(&res, Err(TestError::MissingOutput { line: 4, expected }) if expected == "|   2  |"),
+        "{res:?}"
+    );
+}
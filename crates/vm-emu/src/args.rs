@@ -0,0 +1,45 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+pub(crate) struct Args {
+    /// The `.vm` file or directory to execute, or a `.tst` script to run.
+    pub(crate) path: PathBuf,
+    /// The maximum number of VM commands to execute.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub(crate) max_steps: u64,
+    /// Initialize RAM before execution, e.g. `--set 0=261`.
+    #[arg(long, value_parser = parse_assignment)]
+    pub(crate) set: Vec<(usize, i16)>,
+    /// The RAM addresses to print after execution, e.g. `--ram 256..264`.
+    #[arg(long, value_parser = parse_range, default_value = "0..16")]
+    pub(crate) ram: Range<usize>,
+}
+
+fn parse_assignment(s: &str) -> Result<(usize, i16), String> {
+    let (address, value) = s.split_once('=').ok_or("Expected ADDRESS=VALUE")?;
+    let address = address
+        .parse()
+        .map_err(|err| format!("Invalid address; err={err}"))?;
+    let value = value
+        .parse()
+        .map_err(|err| format!("Invalid value; err={err}"))?;
+
+    Ok((address, value))
+}
+
+fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let (start, end) = s.split_once("..").ok_or("Expected START..END")?;
+    let start = start
+        .parse()
+        .map_err(|err| format!("Invalid start; err={err}"))?;
+    let end = end
+        .parse()
+        .map_err(|err| format!("Invalid end; err={err}"))?;
+
+    Ok(start..end)
+}
@@ -0,0 +1,8 @@
+mod loader;
+pub use loader::*;
+
+mod script;
+pub use script::*;
+
+mod vm;
+pub use vm::*;
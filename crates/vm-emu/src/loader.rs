@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use vmt::{OpCode, VmFile};
+
+use crate::{Instruction, Vm};
+
+impl Vm {
+    /// Loads either a single `.vm` file or every `.vm` file in a directory.
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        let paths = match path.is_dir() {
+            true => {
+                let mut paths = std::fs::read_dir(path)
+                    .and_then(|entries| {
+                        entries
+                            .map(|entry| entry.map(|entry| entry.path()))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .map_err(|err| LoadError::Io { path: path.to_owned(), err })?;
+                paths.retain(|path| path.extension().is_some_and(|ext| ext == "vm"));
+                paths.sort();
+
+                paths
+            }
+            false => match path.extension().is_some_and(|ext| ext == "vm") {
+                true => vec![path.to_owned()],
+                false => return Err(LoadError::Extension(path.to_owned())),
+            },
+        };
+        if paths.is_empty() {
+            return Err(LoadError::NoFiles(path.to_owned()));
+        }
+
+        let files = paths
+            .iter()
+            .map(|path| Ok((path.display().to_string(), VmFile::parse_file(path)?)))
+            .collect::<Result<Vec<_>, LoadError>>()?;
+
+        Self::from_files(files)
+    }
+
+    /// Loads a program made of a single VM file.
+    pub fn from_source(file: &str, source: &str) -> Result<Self, LoadError> {
+        Self::from_sources(&[(file, source)])
+    }
+
+    /// Loads a program made of `(file, source)` pairs, statics are allocated
+    /// per file in the order provided.
+    pub fn from_sources(files: &[(&str, &str)]) -> Result<Self, LoadError> {
+        Self::from_files(
+            files
+                .iter()
+                .map(|(file, source)| {
+                    let name = Path::new(file)
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy();
+
+                    (file.to_string(), VmFile::parse(&name, source))
+                })
+                .collect(),
+        )
+    }
+
+    /// Loads a program from parsed files, rejecting them as `vmt` would.
+    fn from_files(files: Vec<(String, VmFile)>) -> Result<Self, LoadError> {
+        let mut program = Vec::default();
+        let mut scopes = Vec::default();
+        let mut functions = HashMap::new();
+        let mut labels = HashMap::new();
+
+        let mut static_offset = 0;
+        for (file, vm_file) in files {
+            if let Some(err) = vm_file.errors(&file).into_iter().next() {
+                return Err(err.into());
+            }
+
+            // Labels are scoped to the enclosing function, or the file outside
+            // any function.
+            let mut function = vm_file.name.clone();
+            for (line, source, res) in vm_file.opcodes {
+                let opcode = res.expect("Parse errors are reported up front");
+                let index = program.len();
+
+                match &opcode {
+                    OpCode::Function { name, .. } => {
+                        if functions.insert(name.clone(), index).is_some() {
+                            return Err(LoadError::DuplicateFunction {
+                                file,
+                                line,
+                                name: name.clone(),
+                            });
+                        }
+                        function = name.clone();
+                    }
+                    OpCode::Label(label) => {
+                        labels.insert((function.clone(), label.clone()), index);
+                    }
+                    _ => {}
+                }
+
+                scopes.push(function.clone());
+                program.push(Instruction {
+                    opcode,
+                    file: file.clone(),
+                    line,
+                    source,
+                    static_offset,
+                    target: None,
+                });
+            }
+
+            static_offset += vm_file.static_variables;
+        }
+
+        // Resolve jump targets, every target was checked to exist in its scope.
+        for (instruction, scope) in program.iter_mut().zip(scopes) {
+            if let OpCode::Goto(label) | OpCode::IfGoto(label) = &instruction.opcode {
+                instruction.target = Some(labels[&(scope, label.clone())]);
+            }
+        }
+
+        Ok(Vm::new(program, functions))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error(transparent)]
+    File(#[from] vmt::LoadError),
+    #[error("Failed to read directory; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Unsupported file extension; path={}", .0.display())]
+    Extension(PathBuf),
+    #[error("No VM files found; path={}", .0.display())]
+    NoFiles(PathBuf),
+    #[error("Duplicate function; file={file}; line={line}; name={name}")]
+    DuplicateFunction { file: String, line: usize, name: String },
+}
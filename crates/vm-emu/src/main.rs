@@ -0,0 +1,72 @@
+mod args;
+
+fn main() -> std::process::ExitCode {
+    use std::process::ExitCode;
+
+    use clap::Parser;
+    use vm_emu::{Vm, RAM_SIZE};
+
+    // Parse command line args.
+    let args = args::Args::parse();
+
+    // Test scripts drive the emulator themselves.
+    if args.path.extension().is_some_and(|ext| ext == "tst") {
+        return match vm_emu::run_script(&args.path) {
+            Ok(lines) => {
+                println!("End of script; compared_lines={lines}");
+
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Load the program.
+    let mut vm = match Vm::load(&args.path) {
+        Ok(vm) => vm,
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Initialize RAM.
+    for (address, value) in args.set {
+        let Some(word) = vm.ram_mut().get_mut(address) else {
+            eprintln!("Address outside of RAM; address={address}");
+
+            return ExitCode::FAILURE;
+        };
+        *word = value;
+    }
+
+    // Execute the program.
+    let steps = match vm.run(args.max_steps) {
+        Ok(steps) => steps,
+        Err(err) => {
+            if let Some(instruction) = vm.program().get(vm.pc()) {
+                eprintln!("{}:{}: {}", instruction.file, instruction.line, instruction.source);
+            }
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+    match vm.is_halted() {
+        true => println!("Halted after {steps} steps"),
+        false => println!("Stopped after {steps} steps"),
+    }
+
+    // Dump the final state.
+    println!("PC={} CALL_STACK={}", vm.pc(), vm.call_stack().join(" > "));
+    for address in args.ram.start.min(RAM_SIZE)..args.ram.end.min(RAM_SIZE) {
+        println!("RAM[{address}]={}", vm.ram()[address]);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use shared::tst::{self, Command, Simulator, TestError};
+use thiserror::Error;
+use vmt::Region;
+
+use crate::{ExecuteError, LoadError, Vm, RAM_SIZE};
+
+/// Runs a VM emulator test script, returning the number of output lines that
+/// matched the compare file.
+pub fn run_script(path: &Path) -> Result<usize, TestError<VmTestError>> {
+    tst::run_script(path, &mut VmSimulator::default())
+}
+
+/// Exposes the VM to test scripts via `sp`, `local`, `argument`, `this`,
+/// `that`, `segment[n]`, `RAM[n]` & `time`.
+#[derive(Default)]
+pub struct VmSimulator {
+    vm: Option<Vm>,
+}
+
+impl VmSimulator {
+    fn vm(&self) -> Result<&Vm, VmTestError> {
+        self.vm.as_ref().ok_or(VmTestError::NoProgram)
+    }
+
+    fn vm_mut(&mut self) -> Result<&mut Vm, VmTestError> {
+        self.vm.as_mut().ok_or(VmTestError::NoProgram)
+    }
+
+    /// Resolves a script variable to a RAM address.
+    fn address(&self, variable: &str) -> Result<usize, VmTestError> {
+        let unknown = || VmTestError::UnknownVariable(variable.to_owned());
+
+        // The segment pointers themselves.
+        if let Some(address) = ["sp", "local", "argument", "this", "that"]
+            .iter()
+            .position(|pointer| *pointer == variable)
+        {
+            return Ok(address);
+        }
+
+        // Indexed access, e.g. `RAM[256]` or `argument[1]`.
+        let (segment, index) = variable
+            .strip_suffix(']')
+            .and_then(|rest| rest.split_once('['))
+            .ok_or_else(unknown)?;
+        let index = index.parse().map_err(|_| unknown())?;
+
+        match segment {
+            "RAM" => Some(usize::from(index)).filter(|address| *address < RAM_SIZE),
+            _ => Region::from_str(segment)
+                .ok()
+                .and_then(|region| self.vm().ok()?.segment_address(&region, index)),
+        }
+        .ok_or_else(unknown)
+    }
+}
+
+impl Simulator for VmSimulator {
+    type Error = VmTestError;
+
+    fn load(&mut self, dir: &Path, file: Option<&str>) -> Result<(), Self::Error> {
+        self.vm = Some(match file {
+            Some(file) => Vm::load(&dir.join(file))?,
+            None => Vm::load(dir)?,
+        });
+
+        Ok(())
+    }
+
+    fn load_part(&mut self, part: &str, _: &Path) -> Result<(), Self::Error> {
+        Err(VmTestError::Unsupported(format!("{part} load")))
+    }
+
+    fn get(&self, variable: &str) -> Result<i64, Self::Error> {
+        let vm = self.vm()?;
+
+        Ok(match variable {
+            "time" => vm.steps() as i64,
+            _ => i64::from(vm.ram()[self.address(variable)?]),
+        })
+    }
+
+    fn set(&mut self, variable: &str, value: i64) -> Result<(), Self::Error> {
+        let address = self.address(variable)?;
+        self.vm_mut()?.ram_mut()[address] = value as i16;
+
+        Ok(())
+    }
+
+    fn step(&mut self, command: &Command) -> Result<(), Self::Error> {
+        match command {
+            Command::VmStep => Ok(self.vm_mut()?.step()?),
+            _ => Err(VmTestError::Unsupported(format!("{command:?}"))),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum VmTestError {
+    #[error("Failed to load program; err={0}")]
+    Load(#[from] LoadError),
+    #[error("Execution failed; err={0}")]
+    Execute(#[from] ExecuteError),
+    #[error("No program loaded")]
+    NoProgram,
+    #[error("Unknown variable; variable={0}")]
+    UnknownVariable(String),
+    #[error("Unsupported command; command={0}")]
+    Unsupported(String),
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+use vmt::{OffsetType, OpCode, Region};
+
+pub const RAM_SIZE: usize = 32 * 1024;
+
+/// The stack base used when the program is not started by a test script.
+pub const STACK_BASE: i16 = 256;
+
+// Segment pointers, see `shared::hack::PredefinedSymbols`.
+const SP: usize = 0;
+const LCL: usize = 1;
+const ARG: usize = 2;
+const THIS: usize = 3;
+const THAT: usize = 4;
+
+/// A single loaded VM command along with where it came from.
+pub struct Instruction {
+    pub opcode: OpCode,
+    pub file: String,
+    pub line: usize,
+    pub source: String,
+    /// The first static address reserved for this instruction's file.
+    pub(crate) static_offset: u16,
+    /// The resolved target of a `goto` or `if-goto`.
+    pub(crate) target: Option<usize>,
+}
+
+/// Executes VM commands directly against a Hack-compatible RAM.
+///
+/// The stack & segment pointers live in RAM exactly where the translated
+/// program would keep them, so the two can be compared word for word. Return
+/// addresses pushed by `call` are indices into the VM program rather than ROM
+/// addresses.
+pub struct Vm {
+    program: Vec<Instruction>,
+    functions: HashMap<String, usize>,
+    ram: Box<[i16]>,
+    pc: usize,
+    call_stack: Vec<String>,
+    steps: u64,
+}
+
+impl Vm {
+    /// Creates a VM for an already resolved program.
+    ///
+    /// Execution starts at `Sys.init` if it exists, else at the first
+    /// command.
+    pub(crate) fn new(program: Vec<Instruction>, functions: HashMap<String, usize>) -> Self {
+        let (pc, call_stack) = match functions.get("Sys.init") {
+            Some(pc) => (*pc, vec!["Sys.init".to_owned()]),
+            None => (0, Vec::default()),
+        };
+        let mut ram = vec![0; RAM_SIZE].into_boxed_slice();
+        ram[SP] = STACK_BASE;
+
+        Vm { program, functions, ram, pc, call_stack, steps: 0 }
+    }
+
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    pub fn ram(&self) -> &[i16] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [i16] {
+        &mut self.ram
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    /// The functions currently being executed, innermost last.
+    pub fn call_stack(&self) -> &[String] {
+        &self.call_stack
+    }
+
+    /// The total number of commands executed.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns true if the VM has run past the end of the program or is parked
+    /// in a `label X` `goto X` infinite loop.
+    pub fn is_halted(&self) -> bool {
+        match self.program.get(self.pc) {
+            None => true,
+            Some(Instruction { opcode: OpCode::Goto(_), target: Some(target), .. }) => {
+                *target <= self.pc
+                    && self.program[*target..self.pc]
+                        .iter()
+                        .all(|instruction| matches!(instruction.opcode, OpCode::Label(_)))
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// Executes a single VM command, labels are skipped over for free.
+    pub fn step(&mut self) -> Result<(), ExecuteError> {
+        self.skip_labels();
+        let pc = self.pc;
+        let instruction = self
+            .program
+            .get(pc)
+            .ok_or(ExecuteError::PcOutOfBounds(pc))?;
+        let target = instruction.target;
+        let mut next = pc + 1;
+
+        match &instruction.opcode {
+            OpCode::Push(region, index) => {
+                let value = match region.offset(instruction.static_offset) {
+                    OffsetType::Constant => *index as i16,
+                    offset => self.ram[self.address(offset, *index)?],
+                };
+                self.push(value)?;
+            }
+            OpCode::Pop(region, index) => {
+                let address = match region.offset(instruction.static_offset) {
+                    OffsetType::Constant => return Err(ExecuteError::PopConstant(pc)),
+                    offset => self.address(offset, *index)?,
+                };
+                self.ram[address] = self.pop()?;
+            }
+            OpCode::Function { args, .. } => {
+                for _ in 0..*args {
+                    self.push(0)?;
+                }
+            }
+            OpCode::Call { name, args } => {
+                let (name, args) = (name.clone(), *args);
+                next = *self
+                    .functions
+                    .get(&name)
+                    .ok_or_else(|| ExecuteError::UnknownFunction { pc, name: name.clone() })?;
+
+                // Save the caller's frame, the return address must fit in a
+                // word.
+                let return_address =
+                    i16::try_from(pc + 1).map_err(|_| ExecuteError::ReturnAddress(pc))?;
+                self.push(return_address)?;
+                for pointer in [LCL, ARG, THIS, THAT] {
+                    self.push(self.ram[pointer])?;
+                }
+                // Only entered once the frame is saved.
+                self.call_stack.push(name);
+
+                // Reposition ARG & LCL for the callee.
+                self.ram[ARG] = self.ram[SP].wrapping_sub(5 + i16::from(args));
+                self.ram[LCL] = self.ram[SP];
+            }
+            OpCode::Return => {
+                let frame = self.ram[LCL];
+                let return_address =
+                    self.ram[self.address(OffsetType::Fixed(0), (frame as u16).wrapping_sub(5))?];
+
+                // Move the return value to the caller's stack & restore its frame.
+                let value = self.pop()?;
+                let address = self.address(OffsetType::Dynamic(ARG as u16), 0)?;
+                self.ram[address] = value;
+                self.ram[SP] = self.ram[ARG].wrapping_add(1);
+                for (pointer, offset) in [(THAT, 1), (THIS, 2), (ARG, 3), (LCL, 4)] {
+                    self.ram[pointer] = self.ram[self
+                        .address(OffsetType::Fixed(0), (frame as u16).wrapping_sub(offset))?];
+                }
+
+                self.call_stack.pop();
+                next = return_address as u16 as usize;
+            }
+            OpCode::Label(_) => {}
+            OpCode::Goto(_) => next = target.expect("Goto targets are resolved"),
+            OpCode::IfGoto(_) => {
+                if self.pop()? != 0 {
+                    next = target.expect("Goto targets are resolved");
+                }
+            }
+            OpCode::Add => self.binary(i16::wrapping_add)?,
+            OpCode::Sub => self.binary(i16::wrapping_sub)?,
+            OpCode::Neg => self.unary(i16::wrapping_neg)?,
+            OpCode::Eq => self.binary(|x, y| -i16::from(x == y))?,
+            OpCode::Lt => self.binary(|x, y| -i16::from(x < y))?,
+            OpCode::Le => self.binary(|x, y| -i16::from(x <= y))?,
+            OpCode::Gt => self.binary(|x, y| -i16::from(x > y))?,
+            OpCode::Ge => self.binary(|x, y| -i16::from(x >= y))?,
+            OpCode::And => self.binary(|x, y| x & y)?,
+            OpCode::Or => self.binary(|x, y| x | y)?,
+            OpCode::Not => self.unary(|x| !x)?,
+        }
+
+        self.pc = next;
+        self.steps += 1;
+        self.skip_labels();
+
+        Ok(())
+    }
+
+    /// Executes until `max_steps` commands have run or the VM halts, returning
+    /// the number of commands executed.
+    pub fn run(&mut self, max_steps: u64) -> Result<u64, ExecuteError> {
+        let start = self.steps;
+        while self.steps - start < max_steps && !self.is_halted() {
+            self.step()?;
+        }
+
+        Ok(self.steps - start)
+    }
+
+    fn skip_labels(&mut self) {
+        while let Some(Instruction { opcode: OpCode::Label(_), .. }) = self.program.get(self.pc) {
+            self.pc += 1;
+        }
+    }
+
+    /// Returns the RAM address of `segment[index]`.
+    pub fn segment_address(&self, region: &Region, index: u16) -> Option<usize> {
+        match region.offset(0) {
+            OffsetType::Constant => None,
+            offset => self.address(offset, index).ok(),
+        }
+    }
+
+    fn address(&self, offset: OffsetType, index: u16) -> Result<usize, ExecuteError> {
+        let address = match offset {
+            OffsetType::Constant => unreachable!("Constants do not live in RAM"),
+            OffsetType::Fixed(base) => base.wrapping_add(index),
+            OffsetType::Dynamic(pointer) => {
+                (self.ram[usize::from(pointer)] as u16).wrapping_add(index)
+            }
+        };
+
+        match usize::from(address) < RAM_SIZE {
+            true => Ok(usize::from(address)),
+            false => Err(ExecuteError::AddressOutOfBounds { pc: self.pc, address }),
+        }
+    }
+
+    fn push(&mut self, value: i16) -> Result<(), ExecuteError> {
+        let address = self.address(OffsetType::Dynamic(SP as u16), 0)?;
+        self.ram[address] = value;
+        self.ram[SP] = self.ram[SP].wrapping_add(1);
+
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<i16, ExecuteError> {
+        self.ram[SP] = self.ram[SP].wrapping_sub(1);
+        let address = self.address(OffsetType::Dynamic(SP as u16), 0)?;
+
+        Ok(self.ram[address])
+    }
+
+    fn unary(&mut self, op: impl Fn(i16) -> i16) -> Result<(), ExecuteError> {
+        let x = self.pop()?;
+
+        self.push(op(x))
+    }
+
+    fn binary(&mut self, op: impl Fn(i16, i16) -> i16) -> Result<(), ExecuteError> {
+        let y = self.pop()?;
+        let x = self.pop()?;
+
+        self.push(op(x, y))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExecuteError {
+    #[error("Program counter out of bounds; pc={0}")]
+    PcOutOfBounds(usize),
+    #[error("Memory access out of bounds; pc={pc}; address={address}")]
+    AddressOutOfBounds { pc: usize, address: u16 },
+    #[error("Cannot pop to constant; pc={0}")]
+    PopConstant(usize),
+    #[error("Unknown function; pc={pc}; name={name}")]
+    UnknownFunction { pc: usize, name: String },
+    #[error("Return address out of range; pc={0}")]
+    ReturnAddress(usize),
+}
@@ -0,0 +1,405 @@
+//! Differential tests: random VM programs are executed by the VM interpreter
+//! and via `vmt` -> assembler -> CPU emulator, the resulting memory must match.
+
+use std::fmt::Write;
+
+use hack_emu::Cpu;
+use proptest::prelude::*;
+use vm_emu::Vm;
+use vmt::{StaticAllocation, VmFile, Writer};
+
+const MAX_STEPS: u64 = 100_000;
+
+// Segment bases, chosen so the segments never overlap the stack.
+const SP: i16 = 256;
+const LCL: i16 = 1000;
+const ARG: i16 = 1100;
+const THIS: i16 = 3000;
+const THAT: i16 = 3100;
+const SEGMENT_SIZE: u16 = 8;
+
+/// Temp 7 is reserved as the loop counter.
+const LOOP_COUNTER: u16 = 7;
+
+const FUNCTION_ARGS: u8 = 2;
+const FUNCTION_LOCALS: u8 = 3;
+
+#[derive(Debug, Clone, Copy)]
+enum Segment {
+    Local,
+    Argument,
+    This,
+    That,
+    Temp,
+    Static,
+}
+
+impl Segment {
+    fn name(self) -> &'static str {
+        match self {
+            Segment::Local => "local",
+            Segment::Argument => "argument",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Temp => "temp",
+            Segment::Static => "static",
+        }
+    }
+}
+
+/// An abstract program step, lowered into well-formed VM code by
+/// [`Lowering`].
+///
+/// Steps are repaired during lowering (e.g. a binary op on a shallow stack
+/// becomes a push), so any subsequence of steps is still a valid program and
+/// shrinking can freely drop steps.
+#[derive(Debug, Clone)]
+enum Step {
+    PushConstant(u16),
+    PushPointer(u16),
+    Push(Segment, u16),
+    Pop(Segment, u16),
+    Unary(&'static str),
+    Binary(&'static str),
+    /// Opens a block that is skipped if the popped value is non-zero.
+    SkipIf,
+    /// Opens a block that runs the given number of times.
+    Loop(u16),
+    /// Closes the innermost block.
+    End,
+    Call(usize),
+}
+
+fn segment() -> impl Strategy<Value = Segment> {
+    prop_oneof![
+        Just(Segment::Local),
+        Just(Segment::Argument),
+        Just(Segment::This),
+        Just(Segment::That),
+        Just(Segment::Temp),
+        Just(Segment::Static),
+    ]
+}
+
+fn step(functions: usize) -> impl Strategy<Value = Step> {
+    prop_oneof![
+        4 => prop_oneof![0..4u16, 0..=32767u16, Just(32767u16)].prop_map(Step::PushConstant),
+        1 => (0..2u16).prop_map(Step::PushPointer),
+        3 => (segment(), 0..SEGMENT_SIZE).prop_map(|(segment, index)| Step::Push(segment, index)),
+        3 => (segment(), 0..SEGMENT_SIZE).prop_map(|(segment, index)| Step::Pop(segment, index)),
+        2 => prop_oneof![Just("neg"), Just("not")].prop_map(Step::Unary),
+        6 => prop_oneof![
+            Just("add"),
+            Just("sub"),
+            Just("and"),
+            Just("or"),
+            Just("eq"),
+            Just("lt"),
+            Just("gt"),
+        ]
+        .prop_map(Step::Binary),
+        1 => Just(Step::SkipIf),
+        1 => (0..4u16).prop_map(Step::Loop),
+        2 => Just(Step::End),
+        2 => (0..functions.max(1)).prop_map(Step::Call),
+    ]
+}
+
+#[derive(Debug, Clone)]
+struct Program {
+    main: Vec<Step>,
+    functions: Vec<Vec<Step>>,
+    arguments: Vec<i16>,
+}
+
+fn program() -> impl Strategy<Value = Program> {
+    (0..4usize).prop_flat_map(|functions| {
+        (
+            prop::collection::vec(step(functions), 0..64),
+            prop::collection::vec(prop::collection::vec(step(functions), 0..32), functions),
+            prop::collection::vec(any::<i16>(), usize::from(SEGMENT_SIZE)),
+        )
+            .prop_map(|(main, functions, arguments)| Program {
+                main,
+                functions,
+                arguments,
+            })
+    })
+}
+
+/// Lowers steps into VM source while tracking the stack depth.
+struct Lowering<'a> {
+    source: String,
+    labels: &'a mut usize,
+    /// The function being lowered, callees must have a higher index so that
+    /// programs always terminate.
+    function: Option<usize>,
+    functions: usize,
+    depth: usize,
+    blocks: Vec<(Block, usize)>,
+}
+
+enum Block {
+    SkipIf(usize),
+    Loop(usize),
+}
+
+impl Lowering<'_> {
+    fn emit(&mut self, line: impl AsRef<str>) {
+        writeln!(self.source, "{}", line.as_ref()).unwrap();
+    }
+
+    fn label(&mut self) -> usize {
+        *self.labels += 1;
+
+        *self.labels
+    }
+
+    fn push(&mut self, line: impl AsRef<str>) {
+        self.emit(line);
+        self.depth += 1;
+    }
+
+    fn pop(&mut self, line: impl AsRef<str>) {
+        self.emit(line);
+        self.depth -= 1;
+    }
+
+    /// Pushes or pops until the stack is `depth` deep.
+    fn balance(&mut self, depth: usize) {
+        while self.depth < depth {
+            self.push("push constant 0");
+        }
+        while self.depth > depth {
+            self.pop("pop temp 0");
+        }
+    }
+
+    fn lower(&mut self, steps: &[Step]) {
+        for step in steps {
+            match step {
+                Step::PushConstant(value) => self.push(format!("push constant {value}")),
+                Step::PushPointer(index) => self.push(format!("push pointer {index}")),
+                Step::Push(segment, index) => {
+                    let index = self.index(*segment, *index);
+                    self.push(format!("push {} {index}", segment.name()));
+                }
+                Step::Pop(..) if self.depth == 0 => self.push("push constant 1"),
+                Step::Pop(segment, index) => {
+                    let index = self.index(*segment, *index);
+                    self.pop(format!("pop {} {index}", segment.name()));
+                }
+                Step::Unary(_) if self.depth == 0 => self.push("push constant 2"),
+                Step::Unary(op) => self.emit(op),
+                Step::Binary(_) if self.depth < 2 => self.push("push constant 3"),
+                Step::Binary(op) => self.pop(op),
+                Step::SkipIf if self.depth == 0 => self.push("push constant 0"),
+                Step::SkipIf => {
+                    let label = self.label();
+                    self.pop(format!("if-goto SKIP{label}"));
+                    self.blocks.push((Block::SkipIf(label), self.depth));
+                }
+                Step::Loop(_) if self.function.is_some() || self.in_loop() => {}
+                Step::Loop(count) => {
+                    let label = self.label();
+                    self.emit(format!("push constant {count}"));
+                    self.emit(format!("pop temp {LOOP_COUNTER}"));
+                    self.emit(format!("label LOOP{label}"));
+                    self.emit(format!("push temp {LOOP_COUNTER}"));
+                    self.emit(format!("if-goto BODY{label}"));
+                    self.emit(format!("goto DONE{label}"));
+                    self.emit(format!("label BODY{label}"));
+                    self.blocks.push((Block::Loop(label), self.depth));
+                }
+                Step::End => self.end(),
+                Step::Call(callee) => {
+                    let lowest = self.function.map_or(0, |function| function + 1);
+                    if *callee < lowest || *callee >= self.functions {
+                        continue;
+                    }
+
+                    self.balance(self.depth.max(usize::from(FUNCTION_ARGS)));
+                    self.emit(format!("call Fuzz.f{callee} {FUNCTION_ARGS}"));
+                    self.depth = self.depth - usize::from(FUNCTION_ARGS) + 1;
+                }
+            }
+        }
+
+        while !self.blocks.is_empty() {
+            self.end();
+        }
+    }
+
+    fn end(&mut self) {
+        let Some((block, depth)) = self.blocks.pop() else {
+            return;
+        };
+
+        self.balance(depth);
+        match block {
+            Block::SkipIf(label) => self.emit(format!("label SKIP{label}")),
+            Block::Loop(label) => {
+                self.emit(format!("push temp {LOOP_COUNTER}"));
+                self.emit("push constant 1");
+                self.emit("sub");
+                self.emit(format!("pop temp {LOOP_COUNTER}"));
+                self.emit(format!("goto LOOP{label}"));
+                self.emit(format!("label DONE{label}"));
+            }
+        }
+    }
+
+    fn in_loop(&self) -> bool {
+        self.blocks
+            .iter()
+            .any(|(block, _)| matches!(block, Block::Loop(_)))
+    }
+
+    /// Keeps segment accesses within the current frame.
+    fn index(&self, segment: Segment, index: u16) -> u16 {
+        match (segment, self.function) {
+            (Segment::Temp, _) => index % LOOP_COUNTER,
+            (Segment::Local, Some(_)) => index % u16::from(FUNCTION_LOCALS),
+            (Segment::Argument, Some(_)) => index % u16::from(FUNCTION_ARGS),
+            _ => index,
+        }
+    }
+}
+
+impl Program {
+    fn source(&self) -> String {
+        let mut labels = 0;
+        let mut main = Lowering {
+            source: String::new(),
+            labels: &mut labels,
+            function: None,
+            functions: self.functions.len(),
+            depth: 0,
+            blocks: Vec::default(),
+        };
+        main.lower(&self.main);
+        main.emit("label END");
+        main.emit("goto END");
+        let mut source = main.source;
+
+        for (index, steps) in self.functions.iter().enumerate() {
+            let mut function = Lowering {
+                source: format!("function Fuzz.f{index} {FUNCTION_LOCALS}\n"),
+                labels: &mut labels,
+                function: Some(index),
+                functions: self.functions.len(),
+                depth: 0,
+                blocks: Vec::default(),
+            };
+            function.lower(steps);
+            function.balance(1);
+            function.emit("return");
+            source.push_str(&function.source);
+        }
+
+        source
+    }
+
+    /// Seeds the segment pointers & the argument segment.
+    fn initialize(&self, ram: &mut [i16]) {
+        ram[..5].copy_from_slice(&[SP, LCL, ARG, THIS, THAT]);
+        let arg = ARG as usize;
+        ram[arg..arg + self.arguments.len()].copy_from_slice(&self.arguments);
+    }
+}
+
+/// The memory both implementations must agree on once the program halts.
+fn observable(ram: &[i16]) -> Vec<(usize, i16)> {
+    let sp = ram[0] as usize;
+
+    [0..16, 16..16 + usize::from(SEGMENT_SIZE), SP as usize..sp]
+        .into_iter()
+        .chain([LCL, ARG, THIS, THAT].map(|base| {
+            let base = base as usize;
+
+            base..base + usize::from(SEGMENT_SIZE)
+        }))
+        // R13-R15 are scratch registers for the translated code.
+        .flat_map(|range| range.filter(|address| !(13..16).contains(address)))
+        .map(|address| (address, ram[address]))
+        .collect()
+}
+
+fn translate(source: &str, statics: StaticAllocation, optimize: bool) -> String {
+    let mut asm = Vec::new();
+    Writer::new(vec![VmFile::parse("Fuzz", source)], statics, optimize)
+        .unwrap()
+        .write(&mut asm)
+        .unwrap();
+
+    String::from_utf8(asm).unwrap()
+}
+
+/// Moves symbolic statics to where the interpreter keeps them, the assembler
+/// allocates `Fuzz.i` in order of first use rather than by index.
+fn relocate_statics(asm: &str, ram: &[i16]) -> Vec<i16> {
+    let mut symbols = Vec::default();
+    for line in asm.lines() {
+        if let Some(index) = line
+            .strip_prefix("@Fuzz.")
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            if !symbols.contains(&index) {
+                symbols.push(index);
+            }
+        }
+    }
+
+    let mut relocated = ram.to_vec();
+    relocated[16..16 + usize::from(SEGMENT_SIZE)].fill(0);
+    for (allocated, index) in symbols.into_iter().enumerate() {
+        relocated[16 + index] = ram[16 + allocated];
+    }
+
+    relocated
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 512,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn interpreter_matches_translation(program in program()) {
+        let source = program.source();
+
+        // Reference: the VM interpreter.
+        let mut vm = Vm::from_source("Fuzz.vm", &source).unwrap();
+        program.initialize(vm.ram_mut());
+        vm.run(MAX_STEPS).unwrap();
+        prop_assert!(vm.is_halted(), "VM did not halt\n{source}");
+
+        // Under test: vmt -> assembler -> CPU, with & without the optimizer.
+        for statics in [StaticAllocation::Fixed, StaticAllocation::Symbolic] {
+            for optimize in [false, true] {
+                let asm = translate(&source, statics, optimize);
+                let mut cpu = Cpu::from_asm("Fuzz.asm", &asm).unwrap();
+                program.initialize(cpu.ram_mut());
+                cpu.run(MAX_STEPS * 64).unwrap();
+                prop_assert!(
+                    cpu.is_halted(),
+                    "CPU did not halt; statics={statics:?}; optimize={optimize}\n{source}"
+                );
+
+                let ram = match statics {
+                    StaticAllocation::Fixed => cpu.ram().to_vec(),
+                    StaticAllocation::Symbolic => relocate_statics(&asm, cpu.ram()),
+                };
+                prop_assert_eq!(
+                    observable(vm.ram()),
+                    observable(&ram),
+                    "statics={:?}; optimize={}\n{}",
+                    statics,
+                    optimize,
+                    source
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::process::Command;
+
+use vm_emu::{ExecuteError, Vm};
+
+/// Programs are rejected with the same errors `vmt` reports.
+#[test]
+fn load_errors() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("load_errors");
+    for (source, expected) in [
+        ("pop constant 0\n", "Failed to parse; file={file}; line=1; source=pop constant 0;"),
+        ("push static 65535\n", "Failed to parse; file={file}; line=1; source=push static 65535;"),
+        ("label A\nlabel A\n", "Duplicate label; file={file}; line=2; label=A"),
+        (
+            "function F.f 0\ngoto A\nfunction F.g 0\nlabel A\n",
+            "Unknown label; file={file}; line=2; label=A",
+        ),
+    ] {
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("A.vm"), source).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_vm-emu"))
+            .arg(&dir)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+
+        assert!(!output.status.success());
+        let expected = expected.replace("{file}", &dir.join("A.vm").display().to_string());
+        assert!(stderr.starts_with(&expected), "expected `{expected}` in:\n{stderr}");
+    }
+}
+
+/// Labels outside any function are scoped to their file, so several files may
+/// define the same one.
+#[test]
+fn top_level_labels() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("top_level_labels");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("A.vm"), "push constant 1\nlabel END\ngoto END\n").unwrap();
+    std::fs::write(dir.join("B.vm"), "label END\ngoto END\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vm-emu"))
+        .args(["--max-steps", "10"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // Execution stays in the loop of the first file.
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("PC=2 "), "{stdout}");
+}
+
+/// Return addresses are pushed as words, so calls from past the last
+/// addressable command fail instead of wrapping.
+#[test]
+fn return_address_overflow() {
+    // Pad the program so the calls are the commands at 32766 & 32767.
+    let labels = (3..32766).fold(String::default(), |labels, i| labels + &format!("label L{i}\n"));
+    let source = format!(
+        "function Main.f 0\npush constant 0\nreturn\n{labels}call Main.f 0\ncall Main.f 0\n"
+    );
+    let mut vm = Vm::from_source("Main.vm", &source).unwrap();
+
+    vm.set_pc(32766);
+    vm.step().unwrap();
+    assert_eq!(vm.pc(), 0);
+
+    vm.set_pc(32767);
+    let call_stack = vm.call_stack().to_vec();
+    assert!(matches!(vm.step(), Err(ExecuteError::ReturnAddress(32767))));
+    assert_eq!(vm.call_stack(), call_stack);
+}
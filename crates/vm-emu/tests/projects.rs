@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+/// Runs every `*VME.tst` script in `projects/07` & `projects/08` against a
+/// scratch copy of its directory, so the generated `.out` files stay out of the
+/// tree.
+#[test]
+fn project_scripts() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects");
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("projects");
+
+    let mut dirs = Vec::default();
+    for project in ["07", "08"] {
+        program_dirs(&projects.join(project), &mut dirs);
+    }
+    dirs.sort();
+
+    let mut scripts = Vec::default();
+    for dir in dirs {
+        let copy = scratch.join(dir.strip_prefix(&projects).unwrap());
+        std::fs::create_dir_all(&copy).unwrap();
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let target = copy.join(path.file_name().unwrap());
+            std::fs::copy(&path, &target).unwrap();
+
+            let name = path.file_name().unwrap().to_str().unwrap();
+            if name.ends_with("VME.tst") {
+                scripts.push(target);
+            }
+        }
+    }
+    scripts.sort();
+    assert!(!scripts.is_empty());
+
+    let mut failures = Vec::default();
+    for script in scripts {
+        if let Err(err) = vm_emu::run_script(&script) {
+            failures.push(format!("{}: {err}", script.display()));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Collects the directories below `dir` that contain files.
+fn program_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) {
+    let mut has_files = false;
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        match path.is_dir() {
+            true => program_dirs(&path, dirs),
+            false => has_files = true,
+        }
+    }
+
+    if has_files {
+        dirs.push(dir.to_owned());
+    }
+}
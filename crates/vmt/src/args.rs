@@ -1,10 +1,32 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use clap::builder::{PossibleValuesParser, TypedValueParser};
 use clap::Parser;
+use strum::VariantNames;
+use vmt::StaticAllocation;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub(crate) struct Args {
+    /// Either a single VM file or a directory containing VM files.
     pub(crate) path: PathBuf,
+    /// Where the assembly is written, `-` for stdout. Defaults to `Foo.asm`
+    /// next to `Foo.vm`, or `Dir/Dir.asm` for a directory `Dir`.
+    #[arg(short, long)]
+    pub(crate) output: Option<PathBuf>,
+    /// How static variables are allocated, `symbolic` emits `@Foo.i` per file
+    /// `Foo` & leaves the addresses to the assembler.
+    #[arg(
+        long,
+        default_value = "symbolic",
+        value_parser = PossibleValuesParser::new(StaticAllocation::VARIANTS)
+            .map(|s| StaticAllocation::from_str(&s).unwrap())
+    )]
+    pub(crate) statics: StaticAllocation,
+    /// Run the peephole optimizer over the generated assembly, which drops the
+    /// source line comments.
+    #[arg(long)]
+    pub(crate) optimize: bool,
 }
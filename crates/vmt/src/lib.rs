@@ -0,0 +1,14 @@
+mod opcode;
+pub use opcode::*;
+
+mod optimizer;
+pub use optimizer::*;
+
+mod parser;
+pub use parser::*;
+
+mod region;
+pub use region::*;
+
+mod writer;
+pub use writer::*;
@@ -1,32 +1,104 @@
 mod args;
-mod opcode;
-mod parser;
-mod region;
-mod writer;
 
-fn main() {
-    use clap::Parser;
+fn main() -> std::process::ExitCode {
+    use std::io::Write;
+    use std::process::ExitCode;
 
-    use crate::parser::VmFile;
-    use crate::writer::Writer;
+    use clap::Parser;
+    use vmt::{VmFile, Writer};
 
     // Parse command line args.
     let args = args::Args::parse();
 
-    // Load & parse all provided files.
-    let files = match args.path.is_dir() {
-        true => std::fs::read_dir(&args.path)
-            .unwrap()
-            .map(|res| res.unwrap().path())
-            .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("vm"))
-            .map(|path| VmFile::parse_file(&path))
-            .collect(),
-        false => vec![VmFile::parse_file(&args.path)],
+    // Collect the files to translate, statics are allocated in this order.
+    let paths = match args.path.is_dir() {
+        true => {
+            let paths = std::fs::read_dir(&args.path).and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            });
+            let mut paths = match paths {
+                Ok(paths) => paths,
+                Err(err) => {
+                    eprintln!("Failed to read directory; path={}; err={err}", args.path.display());
+
+                    return ExitCode::FAILURE;
+                }
+            };
+            paths.retain(|path| path.extension().is_some_and(|ext| ext == "vm"));
+            paths.sort();
+
+            paths
+        }
+        false => vec![args.path.clone()],
     };
 
+    // Load & validate all files up front, reporting every error before
+    // refusing to emit any output.
+    let mut files = Vec::default();
+    let mut errors = Vec::default();
+    for path in &paths {
+        match VmFile::parse_file(path) {
+            Ok(file) => {
+                errors.extend(file.errors(&path.display().to_string()));
+                files.push(file);
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        for err in &errors {
+            eprintln!("{err}");
+        }
+
+        return ExitCode::FAILURE;
+    }
+
     // Setup the code writer.
-    let writer = Writer::new(files);
+    let writer = match Writer::new(files, args.statics, args.optimize) {
+        Ok(writer) => writer,
+        Err(err) => {
+            eprintln!("{err}");
+
+            return ExitCode::FAILURE;
+        }
+    };
 
     // Generate hack assembly for all parsed lines.
-    writer.write();
+    let mut asm = Vec::default();
+    if let Err(err) = writer.write(&mut asm) {
+        eprintln!("Failed to translate; err={err}");
+
+        return ExitCode::FAILURE;
+    }
+
+    // Write the assembly next to the input unless told otherwise.
+    let output = match args.output {
+        Some(output) if output.as_os_str() == "-" => {
+            return match std::io::stdout().lock().write_all(&asm) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("Failed to write to stdout; err={err}");
+
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Some(output) => output,
+        None => match args.path.is_dir() {
+            true => args
+                .path
+                .join(args.path.file_name().unwrap_or_default())
+                .with_extension("asm"),
+            false => args.path.with_extension("asm"),
+        },
+    };
+    if let Err(err) = shared::fs::write_atomic(&output, asm) {
+        eprintln!("Failed to write file; path={}; err={err}", output.display());
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
 }
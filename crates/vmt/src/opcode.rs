@@ -4,10 +4,10 @@ use std::str::FromStr;
 use shared::hack;
 use thiserror::Error;
 
-use crate::region::{OffsetType, Region};
+use crate::{OffsetType, Region, StaticBase, MAX_STATICS};
 
 #[derive(Debug)]
-pub(crate) enum OpCode {
+pub enum OpCode {
     // Memory access
     Push(Region, u16),
     Pop(Region, u16),
@@ -41,13 +41,29 @@ pub(crate) enum OpCode {
 }
 
 impl OpCode {
-    pub(crate) fn bytecode(
+    /// Translates the opcode, `function` is the enclosing function (or file,
+    /// outside any function) that labels are scoped to.
+    pub fn bytecode(
         &self,
         label_counter: &mut LabelCounter,
-        static_offset: u16,
+        statics: StaticBase,
+        function: Option<&str>,
     ) -> Vec<hack::Instruction> {
         match self {
-            OpCode::Push(region, index) => match region.offset(static_offset) {
+            OpCode::Push(Region::Static, index) => {
+                [Self::static_variable(statics, *index), hack!("D=M")]
+                    .into_iter()
+                    .chain(Self::write_head())
+                    .chain(Self::increment_stack())
+                    .collect()
+            }
+            OpCode::Pop(Region::Static, index) => Self::decrement_stack()
+                .into_iter()
+                .chain(Self::read_head())
+                .chain([Self::static_variable(statics, *index), hack!("M=D")])
+                .collect(),
+            // Statics are handled above, the offset is unused.
+            OpCode::Push(region, index) => match region.offset(0) {
                 OffsetType::Constant => {
                     [hack::Instruction::A(hack::Location::Address(*index)), hack!("D=A")]
                         .into_iter()
@@ -76,8 +92,8 @@ impl OpCode {
                 .chain(Self::increment_stack())
                 .collect(),
             },
-            OpCode::Pop(region, index) => match region.offset(static_offset) {
-                OffsetType::Constant => panic!("Cannot pop to constant"),
+            OpCode::Pop(region, index) => match region.offset(0) {
+                OffsetType::Constant => unreachable!("Popping constants is rejected when parsing"),
                 OffsetType::Dynamic(offset) => Self::decrement_stack()
                     .into_iter()
                     .chain(Self::read_head())
@@ -114,12 +130,14 @@ impl OpCode {
             OpCode::Function { name, args } => Self::function(name, *args),
             OpCode::Call { name, args } => Self::function_call(label_counter, name, *args),
             OpCode::Return => Self::function_return(),
-            OpCode::Label(label) => vec![hack!("({label})")],
-            OpCode::Goto(label) => vec![hack!("@{label}"), hack!("0;JMP")],
+            OpCode::Label(label) => vec![hack!("({})", Self::scoped(function, label))],
+            OpCode::Goto(label) => {
+                vec![hack!("@{}", Self::scoped(function, label)), hack!("0;JMP")]
+            }
             OpCode::IfGoto(label) => Self::decrement_stack()
                 .into_iter()
                 .chain(Self::read_head())
-                .chain([hack!("@{label}"), hack!("D;JNE")])
+                .chain([hack!("@{}", Self::scoped(function, label)), hack!("D;JNE")])
                 .collect(),
             OpCode::Add => Self::decrement_stack()
                 .into_iter()
@@ -182,6 +200,24 @@ impl OpCode {
         }
     }
 
+    /// Labels are emitted as `Scope$label`, `None` only for the bootstrap code
+    /// which has no labels of its own.
+    fn scoped(function: Option<&str>, label: &str) -> String {
+        match function {
+            Some(function) => format!("{function}${label}"),
+            None => label.to_owned(),
+        }
+    }
+
+    fn static_variable(statics: StaticBase, index: u16) -> hack::Instruction {
+        match statics {
+            StaticBase::Fixed(offset) => {
+                hack::Instruction::A(hack::Location::Address(16 + offset + index))
+            }
+            StaticBase::Symbolic(file) => hack!("@{file}.{index}"),
+        }
+    }
+
     fn read_head() -> [hack::Instruction; 2] {
         [hack!("A=M"), hack!("D=M")]
     }
@@ -259,8 +295,9 @@ impl OpCode {
     }
 
     fn function_return() -> Vec<hack::Instruction> {
+        // NB: R5-R12 are the temp segment, so only R13-R15 are free for scratch.
         // frame = LCL
-        [hack!("@LCL"), hack!("D=M"), hack!("@R10"), hack!("M=D")]
+        [hack!("@LCL"), hack!("D=M"), hack!("@R13"), hack!("M=D")]
             .into_iter()
             // retAddr = *(LCL-5)
             .chain([
@@ -270,7 +307,7 @@ impl OpCode {
                 hack!("D=D-A"),
                 hack!("A=D"),
                 hack!("D=M"),
-                hack!("@R11"),
+                hack!("@R14"),
                 hack!("M=D"),
             ])
             // *ARG = pop()
@@ -281,7 +318,7 @@ impl OpCode {
             .chain([hack!("@ARG"), hack!("D=M+1"), hack!("@SP"), hack!("M=D")])
             // THAT = *(--frame)
             .chain([
-                hack!("@R10"),
+                hack!("@R13"),
                 hack!("M=M-1"),
                 hack!("A=M"),
                 hack!("D=M"),
@@ -290,7 +327,7 @@ impl OpCode {
             ])
             // THIS = *(--frame)
             .chain([
-                hack!("@R10"),
+                hack!("@R13"),
                 hack!("M=M-1"),
                 hack!("A=M"),
                 hack!("D=M"),
@@ -299,7 +336,7 @@ impl OpCode {
             ])
             // ARG = *(--frame)
             .chain([
-                hack!("@R10"),
+                hack!("@R13"),
                 hack!("M=M-1"),
                 hack!("A=M"),
                 hack!("D=M"),
@@ -308,7 +345,7 @@ impl OpCode {
             ])
             // LCL = *(--frame)
             .chain([
-                hack!("@R10"),
+                hack!("@R13"),
                 hack!("M=M-1"),
                 hack!("A=M"),
                 hack!("D=M"),
@@ -316,25 +353,22 @@ impl OpCode {
                 hack!("M=D"),
             ])
             // goto retAddr
-            .chain([hack!("@R11"), hack!("A=M"), hack!("0;JMP")])
+            .chain([hack!("@R14"), hack!("A=M"), hack!("0;JMP")])
             .collect()
     }
 
     fn compare(branch: hack::Branch, label_counter: &mut LabelCounter) -> Vec<hack::Instruction> {
+        // Equality survives overflow, ordering needs the signs checked first.
+        let difference = match branch {
+            hack::Branch::JEQ | hack::Branch::JNE => Self::difference(),
+            _ => Self::signed_difference(label_counter),
+        };
         let true_branch = format!("LOW_LEVEL_LABEL{}", label_counter.inc());
         let continue_branch = format!("LOW_LEVEL_LABEL{}", label_counter.inc());
 
-        // Point at the first populated element.
-        Self::decrement_stack()
+        difference
             .into_iter()
-            // Set D to -HEAD.
-            .chain(Self::read_negated_head())
-            // Point at the next element.
-            .chain(Self::decrement_stack())
             .chain([
-                // Get diff of 1st and 2nd element.
-                hack!("A=M"),
-                hack!("D=D+M"),
                 // Jump false if.
                 hack!("@{true_branch}"),
                 hack!("D;{branch}"),
@@ -354,10 +388,73 @@ impl OpCode {
             .chain(Self::increment_stack())
             .collect()
     }
+
+    /// Pops y and points at x, leaving `x - y` in D.
+    fn difference() -> Vec<hack::Instruction> {
+        // Point at the first populated element.
+        Self::decrement_stack()
+            .into_iter()
+            // Set D to -HEAD.
+            .chain(Self::read_negated_head())
+            // Point at the next element.
+            .chain(Self::decrement_stack())
+            // Get diff of 1st and 2nd element.
+            .chain([hack!("A=M"), hack!("D=D+M")])
+            .collect()
+    }
+
+    /// Pops y and points at x, leaving a value with the sign of `x - y` in D.
+    ///
+    /// `x - y` overflows when the operands have different signs, in which case
+    /// the sign of x alone decides the result.
+    fn signed_difference(label_counter: &mut LabelCounter) -> Vec<hack::Instruction> {
+        let x_negative = format!("LOW_LEVEL_LABEL{}", label_counter.inc());
+        let same_sign = format!("LOW_LEVEL_LABEL{}", label_counter.inc());
+        let done = format!("LOW_LEVEL_LABEL{}", label_counter.inc());
+
+        // Store y in R13.
+        Self::decrement_stack()
+            .into_iter()
+            .chain(Self::read_head())
+            .chain([hack!("@R13"), hack!("M=D")])
+            // Load x.
+            .chain(Self::decrement_stack())
+            .chain(Self::read_head())
+            .chain([
+                hack!("@{x_negative}"),
+                hack!("D;JLT"),
+                // x >= 0, so x > y unless y >= 0.
+                hack!("@R13"),
+                hack!("D=M"),
+                hack!("@{same_sign}"),
+                hack!("D;JGE"),
+                hack!("D=1"),
+                hack!("@{done}"),
+                hack!("0;JMP"),
+                // x < 0, so x < y unless y < 0.
+                hack!("({x_negative})"),
+                hack!("@R13"),
+                hack!("D=M"),
+                hack!("@{same_sign}"),
+                hack!("D;JLT"),
+                hack!("D=-1"),
+                hack!("@{done}"),
+                hack!("0;JMP"),
+                // Same sign, x - y cannot overflow.
+                hack!("({same_sign})"),
+                hack!("@SP"),
+                hack!("A=M"),
+                hack!("D=M"),
+                hack!("@R13"),
+                hack!("D=D-M"),
+                hack!("({done})"),
+            ])
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
-pub(crate) enum ParseOpCodeErr {
+pub enum ParseOpCodeErr {
     #[error("Invalid opcode; line={0}")]
     Opcode(String),
     #[error("Invalid argument count; line={0}")]
@@ -368,6 +465,28 @@ pub(crate) enum ParseOpCodeErr {
     Index(String, ParseIntError),
     #[error("Invalid function args; line={0}; err={1}")]
     FunctionArgs(String, ParseIntError),
+    #[error("Cannot pop to constant; line={0}")]
+    PopConstant(String),
+    #[error("Index out of range; line={0}; max={1}")]
+    IndexRange(String, u16),
+}
+
+/// Rejects indices outside of fixed size regions, constants & the offsets into
+/// pointer based regions must fit an A instruction.
+fn check_index(line: &str, region: &Region, index: u16) -> Result<(), ParseOpCodeErr> {
+    let max = match region {
+        Region::Constant | Region::Local | Region::Argument | Region::This | Region::That => {
+            hack::MAX_ADDRESS
+        }
+        Region::Pointer => 1,
+        Region::Temp => 7,
+        Region::Static => MAX_STATICS - 1,
+    };
+
+    match index <= max {
+        true => Ok(()),
+        false => Err(ParseOpCodeErr::IndexRange(line.to_owned(), max)),
+    }
 }
 
 impl FromStr for OpCode {
@@ -390,6 +509,7 @@ impl FromStr for OpCode {
                 let index = index
                     .parse()
                     .map_err(|err| ParseOpCodeErr::Index(index.to_string(), err))?;
+                check_index(s, &region, index)?;
 
                 Ok(OpCode::Push(region, index))
             }
@@ -405,6 +525,10 @@ impl FromStr for OpCode {
                 let index = index
                     .parse()
                     .map_err(|err| ParseOpCodeErr::Index(index.to_string(), err))?;
+                if matches!(region, Region::Constant) {
+                    return Err(ParseOpCodeErr::PopConstant(s.to_owned()));
+                }
+                check_index(s, &region, index)?;
 
                 Ok(OpCode::Pop(region, index))
             }
@@ -470,7 +594,7 @@ impl FromStr for OpCode {
 }
 
 #[derive(Debug, Default)]
-pub(crate) struct LabelCounter {
+pub struct LabelCounter {
     count: u64,
 }
 
@@ -0,0 +1,126 @@
+use shared::hack;
+use shared::hack::{AluOutput, Assignment, Instruction};
+
+/// Runs the peephole passes over a translated program until none of them
+/// changes it any further.
+///
+/// Every pass only rewrites straight-line code, labels end what is known about
+/// the registers as they may be jumped to from anywhere.
+pub fn optimize(mut instructions: Vec<Instruction>) -> Vec<Instruction> {
+    loop {
+        let previous = instructions.clone();
+        instructions = redundant_loads(instructions);
+        instructions = cancel_steps(instructions);
+        instructions = fuse_push_pop(instructions);
+        instructions = dead_stores(instructions);
+
+        if instructions == previous {
+            return instructions;
+        }
+    }
+}
+
+/// Removes A instructions loading the value A already holds, e.g. the `@SP`
+/// starting most opcodes right after the previous one ended with `@SP`.
+fn redundant_loads(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let mut a = None;
+    let mut optimized = Vec::with_capacity(instructions.len());
+    for ix in instructions {
+        match &ix {
+            Instruction::A(location) if a.as_ref() == Some(location) => continue,
+            Instruction::A(location) => a = Some(location.clone()),
+            Instruction::C(assignment, ..) => {
+                if assignment.is_some_and(|assignment| assignment.writes_a()) {
+                    a = None;
+                }
+            }
+            Instruction::Label(_) => a = None,
+        }
+
+        optimized.push(ix);
+    }
+
+    optimized
+}
+
+/// Removes adjacent `M=M+1` & `M=M-1` pairs, which cancel out as neither
+/// changes A. This is what a push followed by a pop leaves of the stack pointer
+/// updates once the redundant `@SP` between them is gone.
+fn cancel_steps(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let step = |ix: &Instruction| match ix {
+        Instruction::C(Some(Assignment::M), AluOutput::M_INC, None) => Some(1),
+        Instruction::C(Some(Assignment::M), AluOutput::M_DEC, None) => Some(-1),
+        _ => None,
+    };
+
+    let mut optimized: Vec<Instruction> = Vec::with_capacity(instructions.len());
+    for ix in instructions {
+        match (optimized.last().and_then(step), step(&ix)) {
+            (Some(last), Some(next)) if last + next == 0 => {
+                optimized.pop();
+            }
+            _ => optimized.push(ix),
+        }
+    }
+
+    optimized
+}
+
+/// Forwards the value a push stored on the stack to the pop reading it straight
+/// back, so `M=D` followed by `D=M` keeps D as is.
+///
+/// The store itself is kept, the translated code may still read the slot above
+/// the stack pointer. Re-deriving `A=M` from `@SP` after storing to the stack
+/// is only redundant as the stack pointer never points at itself.
+fn fuse_push_pop(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let reload: [Instruction; 6] =
+        [hack!("@SP"), hack!("A=M"), hack!("M=D"), hack!("@SP"), hack!("A=M"), hack!("D=M")];
+
+    let mut optimized = Vec::with_capacity(instructions.len());
+    for ix in instructions {
+        if ix == reload[5] && optimized.ends_with(&reload[..5]) {
+            optimized.truncate(optimized.len() - 2);
+        }
+        if ix == reload[5] && optimized.last() == Some(&reload[2]) {
+            continue;
+        }
+
+        optimized.push(ix);
+    }
+
+    optimized
+}
+
+/// Drops assignments to D that are overwritten before being read.
+///
+/// D is assumed to be live at every jump & at the end of the program, as the
+/// code jumped to is unknown.
+fn dead_stores(instructions: Vec<Instruction>) -> Vec<Instruction> {
+    let mut live = true;
+    let mut optimized = Vec::with_capacity(instructions.len());
+    for ix in instructions.into_iter().rev() {
+        let Instruction::C(Some(assignment), alu_output, branch) = ix else {
+            if let Instruction::C(_, alu_output, branch) = &ix {
+                live |= alu_output.reads_d() || branch.is_some();
+            }
+            optimized.push(ix);
+
+            continue;
+        };
+
+        let writes_d = assignment.writes_d();
+        let assignment = match writes_d && !live && branch.is_none() {
+            true => Assignment::decode(assignment.encode() & !0b010),
+            false => Some(assignment),
+        };
+        live = (live && !writes_d) || alu_output.reads_d() || branch.is_some();
+
+        // Without an assignment or jump the computation has no effect.
+        if assignment.is_some() || branch.is_some() {
+            optimized.push(Instruction::C(assignment, alu_output, branch));
+        }
+    }
+    optimized.reverse();
+
+    optimized
+}
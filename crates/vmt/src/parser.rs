@@ -1,17 +1,29 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
-use crate::opcode::{OpCode, ParseOpCodeErr};
-use crate::region::Region;
+use thiserror::Error;
 
-pub(crate) struct VmFile {
-    pub(crate) opcodes: Vec<(usize, String, Result<OpCode, ParseOpCodeErr>)>,
-    pub(crate) static_variables: u16,
+use crate::{OpCode, ParseOpCodeErr, Region, MAX_STATICS};
+
+pub struct VmFile {
+    /// The file stem, which names the file's static variables.
+    pub name: String,
+    pub opcodes: Vec<(usize, String, Result<OpCode, ParseOpCodeErr>)>,
+    pub static_variables: u16,
 }
 
 impl VmFile {
-    pub(crate) fn parse_file(path: &Path) -> VmFile {
-        let opcodes: Vec<_> = std::fs::read_to_string(path)
-            .unwrap()
+    pub fn parse_file(path: &Path) -> Result<VmFile, LoadError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| LoadError::Io { path: path.to_owned(), err })?;
+
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+
+        Ok(VmFile::parse(&name, &source))
+    }
+
+    pub fn parse(name: &str, source: &str) -> VmFile {
+        let opcodes: Vec<_> = source
             .lines()
             .map(|line| {
                 line.split_once("//")
@@ -32,8 +44,91 @@ impl VmFile {
                 _ => None,
             })
             .max()
-            .map_or(0, |offset| offset + 1);
+            .map_or(0, |offset| offset.saturating_add(1));
+
+        VmFile { name: name.to_owned(), opcodes, static_variables }
+    }
+
+    /// Every line of `file` that failed to parse or validate, in source order.
+    ///
+    /// Labels are scoped to their enclosing function (or the file outside any
+    /// function), each must be defined once & every `goto`/`if-goto` must
+    /// target a label within the same scope.
+    pub fn errors(&self, file: &str) -> Vec<LoadError> {
+        let mut errors = Vec::default();
+        let mut labels = HashSet::new();
+        let mut jumps = Vec::default();
+        let mut function = None;
+        for (line, source, res) in &self.opcodes {
+            let opcode = match res {
+                Ok(opcode) => opcode,
+                Err(err) => {
+                    errors.push(LoadError::Parse {
+                        file: file.to_owned(),
+                        line: *line,
+                        source_line: source.clone(),
+                        err: err.clone(),
+                    });
+                    continue;
+                }
+            };
+
+            match opcode {
+                OpCode::Function { name, .. } => function = Some(name.as_str()),
+                OpCode::Label(label) => {
+                    if !labels.insert((function, label.as_str())) {
+                        errors.push(LoadError::DuplicateLabel {
+                            file: file.to_owned(),
+                            line: *line,
+                            label: label.clone(),
+                        });
+                    }
+                }
+                OpCode::Goto(label) | OpCode::IfGoto(label) => {
+                    jumps.push((*line, function, label.as_str()));
+                }
+                _ => {}
+            }
+        }
+
+        // Jumps may target labels defined later in the function.
+        for (line, function, label) in jumps {
+            if !labels.contains(&(function, label)) {
+                errors.push(LoadError::UnknownLabel {
+                    file: file.to_owned(),
+                    line,
+                    label: label.to_owned(),
+                });
+            }
+        }
+        errors.sort_by_key(|err| err.line());
+
+        errors
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Failed to read file; path={}; err={err}", path.display())]
+    Io { path: PathBuf, err: std::io::Error },
+    #[error("Failed to parse; file={file}; line={line}; source={source_line}; err={err}")]
+    Parse { file: String, line: usize, source_line: String, err: ParseOpCodeErr },
+    #[error("Duplicate label; file={file}; line={line}; label={label}")]
+    DuplicateLabel { file: String, line: usize, label: String },
+    #[error("Unknown label; file={file}; line={line}; label={label}")]
+    UnknownLabel { file: String, line: usize, label: String },
+    #[error("Too many static variables; count={count}; max={MAX_STATICS}")]
+    StaticCount { count: u32 },
+}
 
-        VmFile { opcodes, static_variables }
+impl LoadError {
+    /// The line the error occurred on, if it relates to a single line.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            LoadError::Io { .. } | LoadError::StaticCount { .. } => None,
+            LoadError::Parse { line, .. }
+            | LoadError::DuplicateLabel { line, .. }
+            | LoadError::UnknownLabel { line, .. } => Some(*line),
+        }
     }
 }
@@ -1,6 +1,10 @@
+/// The number of static variables the platform has room for, at RAM addresses
+/// 16..=255.
+pub const MAX_STATICS: u16 = 240;
+
 #[derive(Debug, strum::EnumString)]
 #[strum(serialize_all = "lowercase")]
-pub(crate) enum Region {
+pub enum Region {
     Constant,
     Pointer,
     Temp,
@@ -12,7 +16,7 @@ pub(crate) enum Region {
 }
 
 impl Region {
-    pub(crate) fn offset(&self, static_offset: u16) -> OffsetType {
+    pub fn offset(&self, static_offset: u16) -> OffsetType {
         match self {
             Region::Constant => OffsetType::Constant,
             Region::Pointer => OffsetType::Fixed(3),
@@ -27,8 +31,27 @@ impl Region {
 }
 
 #[derive(Debug)]
-pub(crate) enum OffsetType {
+pub enum OffsetType {
     Constant,
     Fixed(u16),
     Dynamic(u16),
 }
+
+/// How static variables are allocated across files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::VariantNames)]
+#[strum(serialize_all = "lowercase")]
+pub enum StaticAllocation {
+    /// Consecutive RAM addresses from 16, in the order the files are provided.
+    Fixed,
+    /// `@Foo.i` symbols per file `Foo`, allocated by the assembler.
+    Symbolic,
+}
+
+/// Where the static variables of a single file live.
+#[derive(Debug, Clone, Copy)]
+pub enum StaticBase<'a> {
+    /// The offset of the file's first static from address 16.
+    Fixed(u16),
+    /// The file name the static symbols are prefixed with.
+    Symbolic(&'a str),
+}
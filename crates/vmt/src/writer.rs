@@ -2,22 +2,44 @@ use std::io::Write;
 
 use shared::hack;
 
-use crate::opcode::{LabelCounter, OpCode};
-use crate::parser::VmFile;
+use crate::{
+    optimize, LabelCounter, LoadError, OpCode, StaticAllocation, StaticBase, VmFile, MAX_STATICS,
+};
 
-pub(crate) struct Writer {
+pub struct Writer {
     input: Vec<VmFile>,
-    output: std::io::Stdout,
+    statics: StaticAllocation,
+    optimize: bool,
     label_counter: LabelCounter,
 }
 
 impl Writer {
-    pub(crate) fn new(files: Vec<VmFile>) -> Self {
-        let output = std::io::stdout();
+    /// Fails if the files' statics do not fit the static area when allocated
+    /// at fixed addresses.
+    pub fn new(
+        files: Vec<VmFile>,
+        statics: StaticAllocation,
+        optimize: bool,
+    ) -> Result<Self, LoadError> {
+        let count: u32 = files
+            .iter()
+            .map(|file| u32::from(file.static_variables))
+            .sum();
+        if statics == StaticAllocation::Fixed && count > u32::from(MAX_STATICS) {
+            return Err(LoadError::StaticCount { count });
+        }
+
+        Ok(Writer { input: files, statics, optimize, label_counter: LabelCounter::default() })
+    }
+
+    pub fn write(mut self, output: &mut impl Write) -> std::io::Result<()> {
+        // The optimizer works across opcodes, so its output is written in one go
+        // without the source line comments.
+        let mut instructions = Vec::default();
 
         // Check if we can/need to generate the bootstrap code.
-        let mut label_counter = LabelCounter::default();
-        if files
+        if self
+            .input
             .iter()
             .flat_map(|file| file.opcodes.iter())
             .any(|(_, _, opcode)| match opcode {
@@ -25,43 +47,65 @@ impl Writer {
                 _ => false,
             })
         {
-            let mut lock = output.lock();
-            for ix in Self::bootstrap_code(&mut label_counter) {
-                writeln!(&mut lock, "{ix}").unwrap();
-            }
+            instructions.extend(Self::bootstrap_code(&mut self.label_counter));
         }
 
-        Writer { input: files, output, label_counter }
-    }
-
-    pub(crate) fn write(mut self) {
-        let mut lock = self.output.lock();
         let mut static_offset = 0;
         for file in &self.input {
+            let statics = match self.statics {
+                StaticAllocation::Fixed => StaticBase::Fixed(static_offset),
+                StaticAllocation::Symbolic => StaticBase::Symbolic(&file.name),
+            };
+            // Labels outside any function are scoped to the file instead, so
+            // files can reuse them.
+            let mut function = file.name.as_str();
             for (line, source, res) in &file.opcodes {
-                let opcode = match res {
-                    Ok(opcode) => opcode,
-                    Err(err) => {
-                        writeln!(&mut lock, "ERR: {err}").unwrap();
-                        continue;
-                    }
-                };
+                // Invalid files should have been rejected up front, never emit
+                // assembly for them.
+                let opcode = res.as_ref().map_err(|err| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, err.clone())
+                })?;
 
-                writeln!(&mut lock, "// L{line}: {source}").unwrap();
-                for ix in opcode.bytecode(&mut self.label_counter, static_offset) {
-                    writeln!(&mut lock, "{ix}").unwrap();
+                if let OpCode::Function { name, .. } = opcode {
+                    function = name.as_str();
+                }
+
+                let bytecode = opcode.bytecode(&mut self.label_counter, statics, Some(function));
+                if self.optimize {
+                    instructions.extend(bytecode);
+                    continue;
+                }
+
+                for ix in instructions.drain(..) {
+                    writeln!(output, "{ix}")?;
+                }
+                writeln!(output, "// L{line}: {source}")?;
+                for ix in bytecode {
+                    writeln!(output, "{ix}")?;
                 }
             }
 
-            static_offset += file.static_variables;
+            // Only read for fixed statics, which `new` checked fit the static area.
+            static_offset = static_offset.saturating_add(file.static_variables);
         }
+
+        if self.optimize {
+            instructions = optimize(instructions);
+        }
+        for ix in instructions {
+            writeln!(output, "{ix}")?;
+        }
+
+        Ok(())
     }
 
     fn bootstrap_code(label_counter: &mut LabelCounter) -> impl Iterator<Item = hack::Instruction> {
         [hack!("@256"), hack!("D=A"), hack!("@SP"), hack!("M=D")]
             .into_iter()
-            .chain(
-                OpCode::Call { name: "Sys.init".to_string(), args: 0 }.bytecode(label_counter, 0),
-            )
+            .chain(OpCode::Call { name: "Sys.init".to_string(), args: 0 }.bytecode(
+                label_counter,
+                StaticBase::Fixed(0),
+                None,
+            ))
     }
 }
@@ -0,0 +1,171 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Translates a directory with invalid lines in several files, every error
+/// should be reported & no assembly written.
+#[test]
+fn parse_errors() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("parse_errors");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("A.vm"), "push constant 1\npush foo 2\n\nfrob\n").unwrap();
+    std::fs::write(dir.join("B.vm"), "function B.f 0\npop local x\nreturn\n").unwrap();
+    let source = "pop constant 0\npush constant 40000\npush pointer 2\npop temp 8\npush temp 7\n";
+    std::fs::write(dir.join("C.vm"), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    let expected = [
+        ("A.vm", 2, "push foo 2"),
+        ("A.vm", 4, "frob"),
+        ("B.vm", 2, "pop local x"),
+        ("C.vm", 1, "pop constant 0"),
+        ("C.vm", 2, "push constant 40000"),
+        ("C.vm", 3, "push pointer 2"),
+        ("C.vm", 4, "pop temp 8"),
+    ];
+    assert_eq!(stderr.lines().count(), expected.len(), "{stderr}");
+    for (file, line, source) in expected {
+        let expected = format!("file={}; line={line}; source={source};", dir.join(file).display());
+        assert!(stderr.contains(&expected), "missing `{expected}` in:\n{stderr}");
+    }
+    assert!(!dir.join("parse_errors.asm").exists());
+}
+
+/// Every segment bounds its index, out of range indices are reported rather
+/// than panicking while translating.
+#[test]
+fn index_errors() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("index_errors");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let cases = [
+        ("push constant 32768", 32767),
+        ("push local 40000", 32767),
+        ("pop argument 32768", 32767),
+        ("push this 65535", 32767),
+        ("pop that 32768", 32767),
+        ("push pointer 2", 1),
+        ("pop temp 8", 7),
+        ("push static 240", 239),
+        ("pop static 65535", 239),
+    ];
+    let valid = "push local 32767\npush static 239\npop that 32767\n";
+    let source = cases
+        .iter()
+        .fold(valid.to_owned(), |source, (line, _)| source + line + "\n");
+    std::fs::write(dir.join("A.vm"), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert!(!stderr.contains("panicked"), "{stderr}");
+    assert_eq!(stderr.lines().count(), cases.len(), "{stderr}");
+    for (i, (source, max)) in cases.into_iter().enumerate() {
+        let expected = format!(
+            "line={}; source={source}; err=Index out of range; line={source}; max={max}",
+            i + 4
+        );
+        assert!(stderr.contains(&expected), "missing `{expected}` in:\n{stderr}");
+    }
+}
+
+/// Fixed statics are allocated across files from address 16, so their total
+/// must fit the static area.
+#[test]
+fn static_count() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("static_count");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("A.vm"), "push static 239\n").unwrap();
+    std::fs::write(dir.join("B.vm"), "pop static 0\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .args(["--statics", "fixed", "-o", "-"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(stderr, "Too many static variables; count=241; max=240\n");
+
+    // Symbolic statics are left to the assembler.
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .args(["--statics", "symbolic", "-o", "-"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+/// Labels are scoped to their function, so the same label may appear in two
+/// functions but not twice in one, & jumps cannot leave their function.
+#[test]
+fn label_errors() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("label_errors");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = "\
+function A.f 0
+label LOOP
+goto LOOP
+label LOOP
+if-goto END
+return
+function A.g 0
+label LOOP
+goto LOOP
+label END
+return
+";
+    std::fs::write(dir.join("A.vm"), source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(!output.status.success());
+    let file = dir.join("A.vm");
+    let expected = [
+        format!("Duplicate label; file={}; line=4; label=LOOP", file.display()),
+        format!("Unknown label; file={}; line=5; label=END", file.display()),
+    ];
+    assert_eq!(stderr.lines().collect::<Vec<_>>(), expected, "{stderr}");
+}
+
+/// Labels outside any function are scoped to their file, so several files may
+/// define the same one.
+#[test]
+fn top_level_labels() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("top_level_labels");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("A.vm"), "label LOOP\ngoto LOOP\n").unwrap();
+    std::fs::write(dir.join("B.vm"), "label LOOP\ngoto LOOP\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vmt"))
+        .arg(&dir)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let asm = std::fs::read_to_string(dir.join("top_level_labels.asm")).unwrap();
+    let lines: Vec<_> = asm.lines().collect();
+    for label in ["A$LOOP", "B$LOOP"] {
+        assert!(lines.contains(&format!("({label})").as_str()), "missing ({label}) in:\n{asm}");
+        assert!(lines.contains(&format!("@{label}").as_str()), "missing @{label} in:\n{asm}");
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use vmt::{StaticAllocation, VmFile, Writer};
+
+/// Translates every VM program in `projects/07` & `08` and runs its CPU
+/// emulator test script against a scratch copy of its directory, so the
+/// generated `.asm` & `.out` files stay out of the tree. Every program is
+/// run with both static allocations, with & without the optimizer.
+#[test]
+fn project_scripts() {
+    let projects = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects");
+    let scratch = Path::new(env!("CARGO_TARGET_TMPDIR")).join("projects");
+
+    let mut programs = Vec::default();
+    for project in ["07", "08"] {
+        find_programs(&projects.join(project), &mut programs);
+    }
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    let mut failures = Vec::default();
+    for program in programs {
+        let name = program.file_name().unwrap().to_str().unwrap().to_owned();
+        let copy = scratch.join(program.strip_prefix(&projects).unwrap());
+        std::fs::create_dir_all(&copy).unwrap();
+        let mut sources: Vec<PathBuf> = Vec::default();
+        for entry in std::fs::read_dir(&program).unwrap() {
+            let path = entry.unwrap().path();
+            std::fs::copy(&path, copy.join(path.file_name().unwrap())).unwrap();
+
+            if path.extension().is_some_and(|ext| ext == "vm") {
+                sources.push(path);
+            }
+        }
+        sources.sort();
+
+        let options = [StaticAllocation::Fixed, StaticAllocation::Symbolic]
+            .into_iter()
+            .flat_map(|statics| [(statics, false), (statics, true)]);
+        for (statics, optimize) in options {
+            // Translate the whole program into the `.asm` the script loads.
+            let files = sources
+                .iter()
+                .map(|path| VmFile::parse_file(path).unwrap())
+                .collect();
+            let mut asm = Vec::default();
+            Writer::new(files, statics, optimize)
+                .unwrap()
+                .write(&mut asm)
+                .unwrap();
+            std::fs::write(copy.join(format!("{name}.asm")), asm).unwrap();
+
+            let script = copy.join(format!("{name}.tst"));
+            if let Err(err) = hack_emu::run_script(&script) {
+                failures.push(format!(
+                    "{} ({statics:?}, optimize={optimize}): {err}",
+                    script.display()
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Collects the directories under `dir` holding a CPU emulator test script
+/// named after the directory.
+fn find_programs(dir: &Path, programs: &mut Vec<PathBuf>) {
+    let name = dir.file_name().unwrap().to_str().unwrap();
+    if dir.join(format!("{name}.tst")).is_file() {
+        programs.push(dir.to_owned());
+    }
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            find_programs(&path, programs);
+        }
+    }
+}